@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serious_save_editor::ctsemeta::CTSEMeta;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CTSEMeta::from_bytes(data);
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use binrw::Endian;
+use libfuzzer_sys::fuzz_target;
+use serious_save_editor::signature_stream::{KeyRing, parse_signature_stream_data};
+
+fuzz_target!(|data: &[u8]| {
+    let key_ring = KeyRing::default();
+    // Only panics are interesting, errors are expected
+    let _ = parse_signature_stream_data(
+        &mut &data[..],
+        Endian::Little,
+        &key_ring,
+        None::<&str>,
+        None::<&str>,
+    );
+});
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+
+const TARGET_TYPE_KEY: &str = "_target_type";
+const TARGET_TYPES_KEY: &str = "_target_types";
+// The f32 variants, a DOUBLE is always exact as a decimal
+const FLOAT_KEYS: [&str; 2] = ["FLOAT", "SFLOAT"];
+
+// Serializes with every pointer annotated with the type name of the object it
+// points to, e.g. {"Pointer": 37, "_target_type": "CTalosProgress"}. Dynamic
+// containers get a parallel "_target_types" array.
+pub fn to_annotated_value(ctsemeta: &CTSEMeta) -> serde_json::Result<Value> {
+    let types = ctsemeta.internal_types.by_id();
+    let target_types = ctsemeta
+        .internal_object_types
+        .types
+        .iter()
+        .filter_map(|object_type| {
+            types
+                .get(&object_type.Type)
+                .map(|data_type| (object_type.Object as i64, data_type.Name.as_str()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut value = serde_json::to_value(ctsemeta)?;
+    if let Some(objects) = value
+        .pointer_mut("/internal_objects/internal_object")
+        .and_then(Value::as_array_mut)
+    {
+        for object in objects {
+            if let Some(value) = object.get_mut("value") {
+                annotate(value, &target_types);
+            }
+        }
+    }
+    Ok(value)
+}
+
+fn annotate(value: &mut Value, target_types: &HashMap<i64, &str>) {
+    match value {
+        Value::Object(map) => {
+            let target_type = map
+                .get("Pointer")
+                .and_then(Value::as_i64)
+                .and_then(|pointer| target_types.get(&pointer));
+            if let Some(target_type) = target_type {
+                map.insert(TARGET_TYPE_KEY.to_owned(), Value::from(*target_type));
+                return;
+            }
+
+            let container_types =
+                map.get("DynamicContainer")
+                    .and_then(Value::as_array)
+                    .map(|pointers| {
+                        pointers
+                            .iter()
+                            .map(|pointer| {
+                                pointer
+                                    .as_i64()
+                                    .and_then(|pointer| target_types.get(&pointer))
+                                    .map(|x| Value::from(*x))
+                                    .unwrap_or_default()
+                            })
+                            .collect::<Vec<_>>()
+                    });
+            if let Some(container_types) = container_types {
+                map.insert(TARGET_TYPES_KEY.to_owned(), Value::from(container_types));
+                return;
+            }
+
+            for value in map.values_mut() {
+                annotate(value, target_types);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                annotate(value, target_types);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Removes the keys added by to_annotated_value so the JSON deserializes again
+pub fn strip_annotations(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove(TARGET_TYPE_KEY);
+            map.remove(TARGET_TYPES_KEY);
+            for value in map.values_mut() {
+                strip_annotations(value);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                strip_annotations(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Rewrites every f32 value as {"f": 1.5, "bits": 1069547520}. Creating a save
+// uses the bits so the value comes back exactly, f is only there to be read.
+pub fn exact_floats(value: &mut Value) {
+    rewrite_values(value, &FLOAT_KEYS, &|value| {
+        // Non-finite floats are strings, let FLOAT's Deserialize read them
+        let float = serde_json::from_value(serde_json::json!({ "FLOAT": value }));
+        if let Ok(InternalObjectDataValue::FLOAT(float)) = float {
+            *value = serde_json::json!({ "f": value, "bits": float.to_bits() });
+        }
+    });
+}
+
+// Writes every UQUAD and SQUAD as a decimal string so tools that read numbers
+// as doubles don't round them
+pub fn string_int64(value: &mut Value) {
+    rewrite_values(value, &["UQUAD", "SQUAD"], &|value| {
+        if value.is_number() {
+            *value = Value::String(value.to_string());
+        }
+    });
+}
+
+// Calls rewrite on the value of every variant in keys in the objects
+fn rewrite_values(value: &mut Value, keys: &[&str], rewrite: &impl Fn(&mut Value)) {
+    fn walk(value: &mut Value, keys: &[&str], rewrite: &impl Fn(&mut Value)) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if keys.contains(&key.as_str()) && (value.is_number() || value.is_string()) {
+                        rewrite(value);
+                    } else {
+                        walk(value, keys, rewrite);
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    walk(value, keys, rewrite);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(objects) = value
+        .pointer_mut("/internal_objects/internal_object")
+        .and_then(Value::as_array_mut)
+    {
+        for object in objects {
+            if let Some(value) = object.get_mut("value") {
+                walk(value, keys, rewrite);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{BufReader, Cursor};
+
+    use binrw::{BinWrite, Endian};
+
+    use crate::annotate::{exact_floats, string_int64, strip_annotations, to_annotated_value};
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+
+    #[test]
+    fn annotate_round_trip() {
+        let ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+
+        let mut value = to_annotated_value(&ctsemeta).unwrap();
+        let members =
+            &value["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"];
+        assert_eq!(
+            members["m_pNext"],
+            serde_json::json!({ "Pointer": 1, "_target_type": "CTalosProgress" })
+        );
+        // Null pointers have nothing to point at
+        let members =
+            &value["internal_objects"]["internal_object"][1]["value"]["Struct"]["members"];
+        assert_eq!(members["m_pNext"], serde_json::json!({ "Pointer": -1 }));
+
+        strip_annotations(&mut value);
+        let stripped: CTSEMeta = serde_json::from_value(value).unwrap();
+        let write = |ctsemeta: &CTSEMeta| {
+            let mut writer = Cursor::new(Vec::new());
+            ctsemeta
+                .write_options(&mut writer, Endian::Little, ())
+                .unwrap();
+            writer.into_inner()
+        };
+        assert_eq!(write(&ctsemeta), write(&stripped));
+    }
+
+    #[test]
+    fn exact_floats_round_trip() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        // Negative zero and the smallest denormal
+        for bits in [0x80000000u32, 0x00000001, 0x3FC00000] {
+            let InternalObjectDataValue::Struct { members, .. } =
+                &mut ctsemeta.internal_objects.internal_object[0].value
+            else {
+                unreachable!()
+            };
+            *members.get_mut("m_fTime").unwrap() =
+                InternalObjectDataValue::FLOAT(f32::from_bits(bits));
+            let mut value = serde_json::to_value(&ctsemeta).unwrap();
+            exact_floats(&mut value);
+            let json = &value["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"]
+                ["m_fTime"]["FLOAT"];
+            assert_eq!(json["bits"], bits);
+            assert_eq!(json["f"].as_f64().unwrap() as f32, f32::from_bits(bits));
+
+            let ctsemeta: CTSEMeta = serde_json::from_value(value).unwrap();
+            let InternalObjectDataValue::Struct { members, .. } =
+                &ctsemeta.internal_objects.internal_object[0].value
+            else {
+                unreachable!()
+            };
+            let InternalObjectDataValue::FLOAT(parsed) = members.get("m_fTime").unwrap() else {
+                unreachable!()
+            };
+            assert_eq!(parsed.to_bits(), bits);
+        }
+
+        // bits wins over f
+        let value: InternalObjectDataValue = serde_json::from_value(
+            serde_json::json!({ "FLOAT": { "f": 2.0, "bits": 0x3FC00000 } }),
+        )
+        .unwrap();
+        assert!(matches!(value, InternalObjectDataValue::FLOAT(x) if x == 1.5));
+        let value: InternalObjectDataValue =
+            serde_json::from_value(serde_json::json!({ "FLOAT": { "f": 2.0 } })).unwrap();
+        assert!(matches!(value, InternalObjectDataValue::FLOAT(x) if x == 2.0));
+    }
+
+    #[test]
+    fn string_int64_round_trip() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_ulCount").unwrap() = InternalObjectDataValue::UQUAD(u64::MAX);
+        *members.get_mut("m_fTime").unwrap() = InternalObjectDataValue::SQUAD(i64::MIN);
+
+        let mut value = serde_json::to_value(&ctsemeta).unwrap();
+        string_int64(&mut value);
+        let members =
+            &value["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"];
+        assert_eq!(members["m_ulCount"]["UQUAD"], "18446744073709551615");
+        assert_eq!(members["m_fTime"]["SQUAD"], "-9223372036854775808");
+
+        let ctsemeta: CTSEMeta = serde_json::from_value(value.clone()).unwrap();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        assert!(matches!(
+            members.get("m_ulCount"),
+            Some(InternalObjectDataValue::UQUAD(u64::MAX))
+        ));
+        assert!(matches!(
+            members.get("m_fTime"),
+            Some(InternalObjectDataValue::SQUAD(i64::MIN))
+        ));
+
+        // Out of range and non-integer values name where they are
+        for (bad, message) in [
+            (serde_json::json!("18446744073709551616"), "invalid UQUAD"),
+            (serde_json::json!("1.5"), "invalid UQUAD"),
+            (serde_json::json!(-1), "UQUAD -1 is out of range"),
+            (serde_json::json!(1.5), "UQUAD 1.5 is not an integer"),
+        ] {
+            let mut value = value.clone();
+            value["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"]["m_ulCount"]
+                ["UQUAD"] = bad.clone();
+            let Err(error) = serde_path_to_error::deserialize::<_, CTSEMeta>(value) else {
+                panic!("{} should be rejected", bad);
+            };
+            assert_eq!(
+                error.path().to_string(),
+                "internal_objects.internal_object[0].value.Struct.members.m_ulCount.UQUAD"
+            );
+            assert!(error.inner().to_string().contains(message), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,514 @@
+// A text form of CTSEMETA that is smaller than the JSON and easier to edit by
+// hand. The tables are one line of JSON each, then every internal object gets
+// a section with a line per member:
+//
+// [object 0 type 4] # CTalosProgress "Cloud_1_01"
+// m_strName = CString "Cloud_1_01"
+// m_vPosition = Vec3D {"x":1.0,"y":2.0,"z":3.0}
+// m_afTimes = Array FLOAT [1.5,2.0]
+// m_aItems = Array
+//   - Struct m_ulCount = ULONG 1
+// <base> = Struct
+//   m_ulBase = ULONG 2
+//
+// Values nested in a struct or array are indented two spaces more than the
+// line holding them. Arrays of plain values that are all the same kind are
+// kept on one line. Member names that aren't a plain identifier are quoted,
+// and an object that isn't a struct is a single "= value" line.
+use std::fmt::Write;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Map, Value};
+
+use crate::ctsemeta::{CTSEMeta, InternalObject, InternalObjectDataValue, StructMembers};
+
+const INDENT: usize = 2;
+const BASE_KEY: &str = "<base>";
+
+// The variant name and its contents, for values that aren't structs or arrays
+fn plain(value: &InternalObjectDataValue) -> Result<(String, Value)> {
+    match serde_json::to_value(value)? {
+        Value::Object(map) if map.len() == 1 => Ok(map.into_iter().next().unwrap()),
+        value => Err(anyhow!("unexpected value {}", value)),
+    }
+}
+
+fn from_plain(tag: &str, inner: Value) -> Result<InternalObjectDataValue> {
+    let mut map = Map::new();
+    map.insert(tag.to_owned(), inner);
+    Ok(serde_json::from_value(Value::Object(map))?)
+}
+
+fn list(value: &InternalObjectDataValue) -> Option<(&'static str, &[InternalObjectDataValue])> {
+    match value {
+        InternalObjectDataValue::Array(values) => Some(("Array", values)),
+        InternalObjectDataValue::StaticStackArray(values) => Some(("StaticStackArray", values)),
+        InternalObjectDataValue::DynamicContainerValues(values) => {
+            Some(("DynamicContainerValues", values))
+        }
+        _ => None,
+    }
+}
+
+fn write_key(out: &mut String, key: &str) -> Result<()> {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        out.push_str(key);
+    } else {
+        out.push_str(&serde_json::to_string(key)?);
+    }
+    Ok(())
+}
+
+// The rest of a line starting at the value and any nested lines after it,
+// indent is that of the line the value is on
+fn write_value(out: &mut String, value: &InternalObjectDataValue, indent: usize) -> Result<()> {
+    if let InternalObjectDataValue::Struct { Base, members } = value {
+        out.push_str("Struct\n");
+        return write_members(out, Base.as_deref(), members, indent + INDENT);
+    }
+    let Some((name, values)) = list(value) else {
+        let (tag, inner) = plain(value)?;
+        writeln!(out, "{} {}", tag, inner)?;
+        return Ok(());
+    };
+
+    let plain_values = values
+        .iter()
+        .filter(|value| !matches!(value, InternalObjectDataValue::Struct { .. }))
+        .filter(|value| list(value).is_none())
+        .map(plain)
+        .collect::<Result<Vec<_>>>()?;
+    if values.is_empty() {
+        writeln!(out, "{} []", name)?;
+    } else if plain_values.len() == values.len()
+        && plain_values
+            .iter()
+            .all(|(tag, _)| *tag == plain_values[0].0)
+    {
+        let tag = plain_values[0].0.clone();
+        let inners = plain_values.into_iter().map(|(_, inner)| inner).collect();
+        writeln!(out, "{} {} {}", name, tag, Value::Array(inners))?;
+    } else {
+        writeln!(out, "{}", name)?;
+        for value in values {
+            write!(out, "{:indent$}- ", "", indent = indent + INDENT)?;
+            write_value(out, value, indent + INDENT)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_members(
+    out: &mut String,
+    base: Option<&InternalObjectDataValue>,
+    members: &StructMembers,
+    indent: usize,
+) -> Result<()> {
+    if let Some(base) = base {
+        write!(out, "{:indent$}{} = ", "", BASE_KEY)?;
+        write_value(out, base, indent)?;
+    }
+    for (name, value) in members.iter() {
+        write!(out, "{:indent$}", "")?;
+        write_key(out, name)?;
+        out.push_str(" = ");
+        write_value(out, value, indent)?;
+    }
+    Ok(())
+}
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+// The end of the lines nested under the one before start
+fn block_end(lines: &[Line], start: usize, indent: usize) -> usize {
+    lines[start..]
+        .iter()
+        .position(|line| line.indent <= indent)
+        .map_or(lines.len(), |end| start + end)
+}
+
+fn parse_key(text: &str) -> Result<(String, &str)> {
+    if text.starts_with('"') {
+        let mut stream = serde_json::Deserializer::from_str(text).into_iter::<String>();
+        let key = stream
+            .next()
+            .ok_or_else(|| anyhow!("expected a member name"))??;
+        Ok((key, &text[stream.byte_offset()..]))
+    } else {
+        let end = text.find(' ').unwrap_or(text.len());
+        Ok((text[..end].to_owned(), &text[end..]))
+    }
+}
+
+fn parse_members(
+    lines: &[Line],
+    indent: usize,
+) -> Result<(Option<Box<InternalObjectDataValue>>, StructMembers)> {
+    let mut base = None;
+    let mut members = StructMembers::default();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let end = block_end(lines, i + 1, line.indent);
+        let result = (|| {
+            if line.indent != indent {
+                bail!("expected {} spaces of indentation", indent);
+            }
+            let (key, rest) = parse_key(line.text)?;
+            let text = rest
+                .strip_prefix(" = ")
+                .ok_or_else(|| anyhow!("expected \" = \" after the member name"))?;
+            let value = parse_value(text, &lines[i + 1..end], indent)?;
+            if key == BASE_KEY {
+                if base.replace(Box::new(value)).is_some() {
+                    bail!("more than one {}", BASE_KEY);
+                }
+            } else {
+                members.push((key, value));
+            }
+            Ok(())
+        })();
+        result.with_context(|| format!("line {}", line.number))?;
+        i = end;
+    }
+    Ok((base, members))
+}
+
+fn parse_items(lines: &[Line], indent: usize) -> Result<Vec<InternalObjectDataValue>> {
+    let mut values = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let end = block_end(lines, i + 1, line.indent);
+        let result = (|| {
+            if line.indent != indent {
+                bail!("expected {} spaces of indentation", indent);
+            }
+            let text = line
+                .text
+                .strip_prefix("- ")
+                .ok_or_else(|| anyhow!("expected \"- \" before an array element"))?;
+            parse_value(text, &lines[i + 1..end], indent)
+        })();
+        values.push(result.with_context(|| format!("line {}", line.number))?);
+        i = end;
+    }
+    Ok(values)
+}
+
+// children are the lines nested under the value's line
+fn parse_value(text: &str, children: &[Line], indent: usize) -> Result<InternalObjectDataValue> {
+    let (tag, rest) = text.split_once(' ').unwrap_or((text, ""));
+    if tag == "Struct" && rest.is_empty() {
+        let (base, members) = parse_members(children, indent + INDENT)?;
+        return Ok(InternalObjectDataValue::Struct {
+            Base: base,
+            members,
+        });
+    }
+    let list = |values| match tag {
+        "Array" => Some(InternalObjectDataValue::Array(values)),
+        "StaticStackArray" => Some(InternalObjectDataValue::StaticStackArray(values)),
+        "DynamicContainerValues" => Some(InternalObjectDataValue::DynamicContainerValues(values)),
+        _ => None,
+    };
+    let is_list = list(Vec::new()).is_some();
+    if is_list && rest.is_empty() {
+        let values = parse_items(children, indent + INDENT)?;
+        return Ok(list(values).unwrap());
+    }
+    if let Some(line) = children.first() {
+        bail!("line {}: {} can't have nested lines", line.number, tag);
+    }
+    if is_list {
+        let values = match rest {
+            "[]" => Vec::new(),
+            rest => {
+                let (element_tag, inners) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| anyhow!("expected the kind of the elements"))?;
+                let Value::Array(inners) = serde_json::from_str(inners)? else {
+                    bail!("expected a JSON array of elements");
+                };
+                inners
+                    .into_iter()
+                    .map(|inner| from_plain(element_tag, inner))
+                    .collect::<Result<_>>()?
+            }
+        };
+        return Ok(list(values).unwrap());
+    }
+    from_plain(tag, serde_json::from_str(rest)?)
+}
+
+fn parse_object(header: &Line, body: &[Line]) -> Result<InternalObject> {
+    let (inside, comment) = header
+        .text
+        .strip_prefix('[')
+        .and_then(|text| text.split_once(']'))
+        .ok_or_else(|| anyhow!("expected [object ID type TYPE]"))?;
+    let comment = comment.trim_start();
+    if !comment.is_empty() && !comment.starts_with('#') {
+        bail!("unexpected {:?} after the section", comment);
+    }
+    let [kind, object, type_kind, object_type] = inside.split(' ').collect::<Vec<_>>()[..] else {
+        bail!("expected [object ID type TYPE]");
+    };
+    if kind != "object" || type_kind != "type" {
+        bail!("expected [object ID type TYPE]");
+    }
+    let object = object.parse().context("object ID")?;
+    let object_type = object_type.parse().context("type ID")?;
+
+    let value = match body.first() {
+        Some(line) if line.indent == 0 && line.text.starts_with('=') => {
+            let value = parse_value(line.text[1..].trim_start(), &body[1..], 0)
+                .with_context(|| format!("line {}", line.number))?;
+            if let Some(line) = body[1..].iter().find(|line| line.indent == 0) {
+                bail!("line {}: expected one value", line.number);
+            }
+            value
+        }
+        _ => {
+            let (base, members) = parse_members(body, 0)?;
+            InternalObjectDataValue::Struct {
+                Base: base,
+                members,
+            }
+        }
+    };
+    Ok(InternalObject {
+        Object: object,
+        Type: object_type,
+        value,
+    })
+}
+
+impl CTSEMeta {
+    pub fn to_ctsem(&self) -> Result<String> {
+        let mut out = "# CTSEMETA text, create --pretty turns it back into a save\n".to_owned();
+        let Value::Object(mut tables) = serde_json::to_value(self)? else {
+            unreachable!()
+        };
+        tables.remove("internal_objects");
+        for (name, table) in tables {
+            writeln!(out, "{} = {}", name, table)?;
+        }
+
+        let types = self.internal_types.by_id();
+        let idents = self.idents.by_id();
+        for object in self.internal_objects.internal_object.iter() {
+            write!(out, "\n[object {} type {}] #", object.Object, object.Type)?;
+            match types.get(&object.Type) {
+                Some(data_type) => write!(out, " {}", data_type.Name)?,
+                None => out.push_str(" unknown type"),
+            }
+            if let Some(ident) = idents.get(&object.Object) {
+                write!(out, " {:?}", ident)?;
+            }
+            out.push('\n');
+            match &object.value {
+                InternalObjectDataValue::Struct { Base, members } => {
+                    write_members(&mut out, Base.as_deref(), members, 0)?
+                }
+                value => {
+                    out.push_str("= ");
+                    write_value(&mut out, value, 0)?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn from_ctsem(text: &str) -> Result<Self> {
+        let lines = text
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let text = line.trim();
+                let indent = line.len() - line.trim_start().len();
+                (!text.is_empty() && !text.starts_with('#')).then_some(Line {
+                    number: i + 1,
+                    indent,
+                    text,
+                })
+            })
+            .collect::<Vec<_>>();
+        let is_section = |line: &Line| line.indent == 0 && line.text.starts_with('[');
+
+        let mut tables = Map::new();
+        let mut i = 0;
+        while i < lines.len() && !is_section(&lines[i]) {
+            let line = &lines[i];
+            let (name, table) = line
+                .text
+                .split_once(" = ")
+                .ok_or_else(|| anyhow!("line {}: expected a table", line.number))?;
+            let table = serde_json::from_str(table)
+                .with_context(|| format!("line {}: {}", line.number, name))?;
+            tables.insert(name.to_owned(), table);
+            i += 1;
+        }
+        tables.insert(
+            "internal_objects".to_owned(),
+            serde_json::json!({ "internal_object": [] }),
+        );
+
+        let mut objects = Vec::new();
+        while i < lines.len() {
+            let header = &lines[i];
+            let end = lines[i + 1..]
+                .iter()
+                .position(is_section)
+                .map_or(lines.len(), |end| i + 1 + end);
+            objects.push(
+                parse_object(header, &lines[i + 1..end])
+                    .with_context(|| format!("object at line {}", header.number))?,
+            );
+            i = end;
+        }
+
+        let mut ctsemeta: Self = serde_path_to_error::deserialize(Value::Object(tables))?;
+        ctsemeta.internal_objects.internal_object = objects.into();
+        ctsemeta.add_missing_idents();
+        Ok(ctsemeta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use binrw::Endian;
+
+    use crate::ctsemeta::{
+        CTSEMeta,
+        InternalObject,
+        InternalObjectDataValue,
+        StructMembers,
+        synthetic,
+    };
+
+    #[test]
+    fn ctsem_round_trip() {
+        let ctsemeta = synthetic();
+        let text = ctsemeta.to_ctsem().unwrap();
+        assert!(text.contains(
+            "[object 0 type 4] # CTalosProgress \"m_strName\"\n\
+             m_strName = CString \"Cloud_1_01\"\n\
+             m_ulCount = ULONG 42\n\
+             m_fTime = FLOAT 1.5\n\
+             m_pNext = Pointer 1\n"
+        ));
+        let parsed = CTSEMeta::from_ctsem(&text).unwrap();
+        assert_eq!(
+            parsed.to_bytes(Endian::Little).unwrap(),
+            ctsemeta.to_bytes(Endian::Little).unwrap()
+        );
+
+        // Values that don't have to match the types
+        let mut ctsemeta = synthetic();
+        let float = |f| InternalObjectDataValue::FLOAT(f);
+        ctsemeta
+            .internal_objects
+            .internal_object
+            .push(InternalObject {
+                Object: 2,
+                Type: 4,
+                value: InternalObjectDataValue::Struct {
+                    Base: Some(Box::new(InternalObjectDataValue::Struct {
+                        Base: None,
+                        members: StructMembers(vec![(
+                            "7".to_owned(),
+                            InternalObjectDataValue::ULONG(2),
+                        )]),
+                    })),
+                    members: StructMembers(vec![
+                        (
+                            "m_str Name".to_owned(),
+                            InternalObjectDataValue::CString("# not a comment\n= [x]".to_owned()),
+                        ),
+                        (
+                            "m_aRows".to_owned(),
+                            InternalObjectDataValue::Array(vec![
+                                InternalObjectDataValue::Array(vec![float(1.0), float(2.0)]),
+                                InternalObjectDataValue::Array(vec![float(f32::NAN), float(-0.0)]),
+                            ]),
+                        ),
+                        (
+                            "m_aMixed".to_owned(),
+                            InternalObjectDataValue::StaticStackArray(vec![
+                                InternalObjectDataValue::Pointer(1),
+                                InternalObjectDataValue::Struct {
+                                    Base: None,
+                                    members: StructMembers::default(),
+                                },
+                            ]),
+                        ),
+                        (
+                            "m_aEmpty".to_owned(),
+                            InternalObjectDataValue::DynamicContainerValues(Vec::new()),
+                        ),
+                        (
+                            "m_cObjects".to_owned(),
+                            InternalObjectDataValue::DynamicContainer(vec![0, 1]),
+                        ),
+                    ]),
+                },
+            });
+        ctsemeta
+            .internal_objects
+            .internal_object
+            .push(InternalObject {
+                Object: 3,
+                Type: 0,
+                value: InternalObjectDataValue::ULONG(5),
+            });
+        let text = ctsemeta.to_ctsem().unwrap();
+        assert!(text.contains(
+            "m_aRows = Array\n  - Array FLOAT [1.0,2.0]\n  - Array FLOAT [\"NaN\",-0.0]\n"
+        ));
+        assert!(text.contains("[object 3 type 0] # ULONG \"m_pNext\"\n= ULONG 5\n"));
+        let parsed = CTSEMeta::from_ctsem(&text).unwrap();
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&ctsemeta).unwrap()
+        );
+        assert_eq!(parsed.to_ctsem().unwrap(), text);
+    }
+
+    #[test]
+    fn ctsem_errors() {
+        let text = synthetic().to_ctsem().unwrap();
+        let error = |text: &str| match CTSEMeta::from_ctsem(text) {
+            Ok(_) => panic!("parsed"),
+            Err(e) => format!("{:#}", e),
+        };
+
+        let bad = text.replace("m_ulCount = ULONG 42", "m_ulCount = ULONG -1");
+        let line = bad
+            .lines()
+            .position(|line| line.contains("ULONG -1"))
+            .unwrap()
+            + 1;
+        assert!(
+            error(&bad).starts_with(&format!("object at line {}: line {}: ", line - 2, line)),
+            "{}",
+            error(&bad)
+        );
+        assert!(
+            error(&text.replace("m_fTime = FLOAT 1.5", "  m_fTime = FLOAT 1.5"))
+                .contains("ULONG can't have nested lines")
+        );
+        assert!(
+            error(&text.replace("\nm_strName", "\n m_strName"))
+                .contains("expected 0 spaces of indentation")
+        );
+        assert!(
+            error(&text.replace("[object 1 type 4]", "[object 1]"))
+                .contains("expected [object ID type TYPE]")
+        );
+    }
+}
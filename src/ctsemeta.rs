@@ -1,18 +1,29 @@
 #![allow(non_snake_case)] // Keep the original names where possible
 
-use std::collections::HashMap;
-use std::io::{Seek, Write};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
 
-use binrw::{BinRead, BinResult, BinWrite, Endian, args, binrw, writer};
-use log::warn;
-use serde::{Deserialize, Serialize};
+use binrw::{BinRead, BinResult, BinWrite, Endian, args, binrw, parser, writer};
+use half::f16;
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
 use crate::helpers::{
-    parse_pascal_string,
-    parse_pascal_vec,
+    ParsedVec,
+    ValidationOptions,
+    detect_endian,
+    parse_validated_pascal_bytes,
+    parse_validated_pascal_string,
+    parse_validated_pascal_vec,
+    write_pascal_bytes,
     write_pascal_string,
     write_pascal_vec,
 };
+use crate::logging::warn;
+use crate::validate::{check_tables, escape, validate_value};
 
 // binrw 0.15.0 doesn't do map and write_with in the right order due to a
 // bug so we do this
@@ -22,29 +33,96 @@ fn write_option_pascal_string(value: &Option<String>) -> BinResult<()> {
     write_pascal_string(value, writer, endian, ())
 }
 
+// map can't be used with args on a parse_with either
+#[parser(reader, endian)]
+fn parse_option_pascal_string(options: ValidationOptions) -> BinResult<Option<String>> {
+    parse_validated_pascal_string(reader, endian, (options,)).map(Some)
+}
+
 #[binrw]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[brw(magic = b"CTSEMETA")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct Metadata {
     #[brw(magic = 0x1234ABCDu32)] // Endianness cookie
     pub version: u32,
-    #[br(if(version >= 2), parse_with = parse_pascal_string, map = |x: String| Some(x))]
+    #[br(if(version >= 2), parse_with = parse_option_pascal_string, args(options))]
     #[bw(if(*version >= 2), write_with = write_option_pascal_string)]
     pub version_string: Option<String>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+pub struct GameVersion {
+    pub game: String,
+    pub version: semver::Version,
+    // As written, 1.09 and 1.9 are both version 1.9.0
+    pub version_text: String,
+    pub build: u32,
+    pub platform: String,
+}
+
+impl Metadata {
+    // Expects something like "Talos 1.09 (Build 307622) for Windows"
+    pub fn parse_version_string(&self) -> Option<GameVersion> {
+        let version_string = self.version_string.as_ref()?;
+        let (head, tail) = version_string.split_once(" (Build ")?;
+        let (build, platform) = tail.split_once(") for ")?;
+        let (game, version) = head.trim().rsplit_once(' ')?;
+
+        // Croteam versions are things like 1.09 which isn't valid semver, so pad
+        // it out to major.minor.patch ourselves
+        let mut components = [0u64; 3];
+        for (i, component) in version.split('.').enumerate() {
+            *components.get_mut(i)? = component.parse().ok()?;
+        }
+
+        Some(GameVersion {
+            game: game.trim().to_owned(),
+            version: semver::Version::new(components[0], components[1], components[2]),
+            version_text: version.to_owned(),
+            build: build.trim().parse().ok()?,
+            platform: platform.trim().to_owned(),
+        })
+    }
+
+    // The version string named the game build that wrote the old version, so
+    // it's cleared rather than left wrong
+    pub fn bump_version(&mut self) {
+        self.version = self.version.saturating_add(1);
+        self.version_string = None;
+    }
+}
+
+// No save with messages has been seen, so the layout is a guess. Each one is
+// assumed to be length prefixed like everything else and is kept as raw bytes.
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[br(import_raw(options: ValidationOptions))]
+pub struct Message {
+    #[br(parse_with = parse_validated_pascal_bytes, args(options))]
+    #[bw(write_with = write_pascal_bytes)]
+    pub data: Vec<u8>,
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"MSGS")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct Messages {
-    #[br(parse_with = parse_pascal_vec, assert(messages.is_empty()))]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, options), map = |messages: ParsedVec<Message>| {
+        if !messages.is_empty() {
+            warn!("MSGS has {} entries, messages are not well tested", messages.len());
+        }
+        messages
+    })]
     #[bw(write_with = write_pascal_vec)]
-    pub messages: Vec<()>,
+    pub messages: ParsedVec<Message>,
 }
 
 #[derive(BinRead, BinWrite, Serialize, Deserialize)]
 #[brw(magic = b"INFO")]
+#[br(import(version: u32))]
 pub struct Info {
-    pub EditDataStripped: u32,
+    // Older versions don't have the flag
+    #[br(if(version >= 8))]
+    pub EditDataStripped: Option<u32>,
     pub ResourceFiles: u32,
     pub Idents: u32,
     pub Types: u32,
@@ -52,7 +130,12 @@ pub struct Info {
 }
 
 impl Info {
+    #[allow(clippy::too_many_arguments)]
     fn new(
+        version: u32,
+        edit_data_stripped: bool,
+        edit_object_types: &EditObjectTypes,
+        edit_objects: &EditObjects,
         resource_file: &ResourceFiles,
         idents: &Idents,
         external_types: &ExternalTypes,
@@ -61,7 +144,12 @@ impl Info {
         internal_objects: &InternalObjects,
     ) -> Self {
         Self {
-            EditDataStripped: 1,
+            // A save can't claim to be stripped while it still has edit data
+            EditDataStripped: (version >= 8).then_some(
+                (edit_data_stripped
+                    && edit_object_types.edit_object_types.is_empty()
+                    && edit_objects.edit_objects.is_empty()) as u32,
+            ),
             ResourceFiles: resource_file.resource_files.len() as u32,
             Idents: idents.idents.len() as u32,
             Types: (external_types.types.len() + internal_types.types.len()) as u32,
@@ -71,54 +159,91 @@ impl Info {
     }
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[br(import(version: u32, options: ValidationOptions))]
+#[bw(import(version: u32))]
+pub struct ResourceFile {
+    // Always the index of the entry
+    pub File: u32,
+    #[br(if(version >= 7))]
+    #[bw(if(version >= 7))]
+    #[serde(default)]
+    pub Flags: u32,
+    #[br(parse_with = parse_validated_pascal_string, args(options))]
+    #[bw(write_with = write_pascal_string)]
+    pub FileName: String,
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"RFIL")]
+#[br(import(version: u32, options: ValidationOptions))]
+#[bw(import(version: u32))]
 pub struct ResourceFiles {
-    #[br(parse_with = parse_pascal_vec, assert(resource_files.is_empty()))]
-    #[bw(write_with = write_pascal_vec)]
-    pub resource_files: Vec<()>,
+    // Only seen populated in AllTalos.dat
+    #[br(parse_with = parse_validated_pascal_vec, args(options, (version, options)), map = |resource_files: ParsedVec<ResourceFile>| {
+        if !resource_files.is_empty() {
+            warn!("RFIL has {} entries, resource files are not well tested", resource_files.len());
+        }
+        resource_files
+    })]
+    #[bw(write_with = write_pascal_vec, args((version,)))]
+    pub resource_files: ParsedVec<ResourceFile>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
+#[br(import_raw(options: ValidationOptions))]
 pub struct Ident {
     pub Ident: u32,
-    #[br(parse_with = parse_pascal_string)]
+    #[br(parse_with = parse_validated_pascal_string, args(options))]
     #[bw(write_with = write_pascal_string)]
     pub Name: String,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"IDNT")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct Idents {
-    #[br(parse_with = parse_pascal_vec)]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, options))]
     #[bw(write_with = write_pascal_vec)]
-    pub idents: Vec<Ident>,
+    pub idents: ParsedVec<Ident>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
+#[br(import_raw(options: ValidationOptions))]
 pub struct ExternalType {
     pub Type: u32,
-    #[br(parse_with = parse_pascal_string)]
+    #[br(parse_with = parse_validated_pascal_string, args(options))]
     #[bw(write_with = write_pascal_string)]
     pub Name: String,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+impl Idents {
+    pub fn by_id(&self) -> HashMap<u32, &str> {
+        self.idents
+            .iter()
+            .map(|ident| (ident.Ident, ident.Name.as_str()))
+            .collect()
+    }
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"EXTY")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct ExternalTypes {
-    #[br(parse_with = parse_pascal_vec)]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, options))]
     #[bw(write_with = write_pascal_vec)]
-    pub types: Vec<ExternalType>,
+    pub types: ParsedVec<ExternalType>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 pub struct DataTypeTypeStructMember {
     pub ID: u32,
     pub Type: u32,
 }
 
 #[binrw]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
+#[br(import_raw(options: ValidationOptions))]
 pub enum DataTypeType {
     #[brw(magic = 0u32)]
     Primitive { Bytes: u32, LBE: u32 },
@@ -129,8 +254,8 @@ pub enum DataTypeType {
     #[brw(magic = 4u32)]
     Array {
         Of: u32,
+        // Arrays with more than one row are read as an Array of rows
         #[brw(magic = b"ADIM")]
-        #[br(assert(rows == 1))]
         rows: u32,
         cols: u32,
     },
@@ -138,9 +263,9 @@ pub enum DataTypeType {
     Struct {
         Base: i32,
         #[brw(magic = b"STMB")]
-        #[br(parse_with = parse_pascal_vec)]
+        #[br(parse_with = parse_validated_pascal_vec, args(options, ()))]
         #[bw(write_with = write_pascal_vec)]
-        members: Vec<DataTypeTypeStructMember>,
+        members: ParsedVec<DataTypeTypeStructMember>,
     },
     #[brw(magic = 7u32)]
     StaticStackArray { Of: u32 },
@@ -148,87 +273,830 @@ pub enum DataTypeType {
     DynamicContainer { Of: u32 },
     #[brw(magic = 13u32)]
     TypeDef { For: u32 },
+    // A kind we don't know the layout of. The bytes up to the next type are
+    // kept so the save can still be written back as long as no object uses it.
+    // Known kinds that fail to read are errors rather than ending up here.
+    // JSON from before tag and data were renamed has magic and raw.
+    Unknown {
+        #[br(assert(!matches!(tag, 0 | 1 | 2 | 4 | 5 | 7 | 8 | 13)))]
+        #[serde(alias = "magic")]
+        tag: u32,
+        #[br(parse_with = parse_unknown_type_data, args(tag))]
+        #[serde(alias = "raw")]
+        data: Vec<u8>,
+    },
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+// Everything up to the next DTTY, or the EXOB after the last type
+#[parser(reader)]
+fn parse_unknown_type_data(tag: u32) -> BinResult<Vec<u8>> {
+    let start = reader.stream_position()?;
+    let mut data = Vec::new();
+    let mut window = [0u8; 4];
+    loop {
+        let pos = reader.stream_position()?;
+        match reader.read_exact(&mut window) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Err(binrw::Error::AssertFail {
+                    pos,
+                    message: "unknown type data runs to the end of the file".to_owned(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        }
+        if &window == b"DTTY" || &window == b"EXOB" {
+            reader.seek(SeekFrom::Start(pos))?;
+            warn!(
+                "unknown type kind {} at {:#x}, keeping its {} bytes as they are",
+                tag,
+                start,
+                data.len()
+            );
+            return Ok(data);
+        }
+        data.push(window[0]);
+        reader.seek(SeekFrom::Start(pos + 1))?;
+    }
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"DTTY")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct DataType {
     pub DataType: u32,
-    #[br(parse_with = parse_pascal_string)]
+    #[br(parse_with = parse_validated_pascal_string, args(options))]
     #[bw(write_with = write_pascal_string)]
     pub Name: String,
     pub Format: u32,
+    #[br(args_raw(options))]
     pub Type: DataTypeType,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"INTY")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct InternalTypes {
-    #[br(parse_with = parse_pascal_vec)]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, options))]
     #[bw(write_with = write_pascal_vec)]
-    pub types: Vec<DataType>,
+    pub types: ParsedVec<DataType>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+impl InternalTypes {
+    pub fn by_id(&self) -> HashMap<u32, &DataType> {
+        self.types.iter().map(|t| (t.DataType, t)).collect()
+    }
+}
+
+// Follows TypeDefs until something concrete, gives up on cycles
+pub fn resolve_type<'a>(types: &HashMap<u32, &'a DataType>, id: u32) -> Option<&'a DataType> {
+    let mut data_type = *types.get(&id)?;
+    for _ in 0..types.len() {
+        match data_type.Type {
+            DataTypeType::TypeDef { For } => data_type = types.get(&For)?,
+            _ => return Some(data_type),
+        }
+    }
+    None
+}
+
+// Every type the given types are made of, themselves included. IDs that aren't
+// internal types are left out.
+pub fn reachable_types(
+    types: &HashMap<u32, &DataType>,
+    roots: impl IntoIterator<Item = u32>,
+) -> BTreeSet<u32> {
+    let mut reachable = BTreeSet::new();
+    let mut pending = roots.into_iter().collect::<Vec<_>>();
+    while let Some(id) = pending.pop() {
+        let Some(data_type) = types.get(&id) else {
+            continue;
+        };
+        if !reachable.insert(id) {
+            continue;
+        }
+        match &data_type.Type {
+            DataTypeType::Primitive { .. }
+            | DataTypeType::Enum { .. }
+            | DataTypeType::Unknown { .. } => {}
+            DataTypeType::Pointer { To: id }
+            | DataTypeType::Array { Of: id, .. }
+            | DataTypeType::StaticStackArray { Of: id }
+            | DataTypeType::DynamicContainer { Of: id }
+            | DataTypeType::TypeDef { For: id } => pending.push(*id),
+            DataTypeType::Struct { .. } if is_csynced(data_type) => {
+                pending.extend(csynced_primitive(types, data_type).map(|x| x.DataType))
+            }
+            DataTypeType::Struct { Base, members } => {
+                if *Base != -1 {
+                    pending.push(*Base as u32);
+                }
+                pending.extend(members.iter().map(|member| member.Type));
+            }
+        }
+    }
+    reachable
+}
+
+// A CSynced struct without members is just the primitive in its name, e.g.
+// CSyncedUWORD is a UWORD. Some builds give them members, those are read like
+// any other struct.
+pub fn is_csynced(data_type: &DataType) -> bool {
+    data_type.Name.starts_with("CSynced")
+        && matches!(&data_type.Type, DataTypeType::Struct { members, .. } if members.is_empty())
+}
+
+// The primitive type a memberless CSynced struct stands for
+pub fn csynced_primitive<'a>(
+    types: &HashMap<u32, &'a DataType>,
+    data_type: &DataType,
+) -> Option<&'a DataType> {
+    let name = data_type.Name.strip_prefix("CSynced")?;
+    types
+        .values()
+        .copied()
+        .filter(|primitive| {
+            primitive.Name == name && matches!(primitive.Type, DataTypeType::Primitive { .. })
+        })
+        .min_by_key(|primitive| primitive.DataType)
+}
+
+fn primitive_bytes(data_type: Option<&DataType>) -> Option<u32> {
+    match data_type?.Type {
+        DataTypeType::Primitive { Bytes, .. } => Some(Bytes),
+        _ => None,
+    }
+}
+
+// Three FLOATs in a row that are read as a Vec3D instead of an Array
+const VEC3D_NAMES: [&str; 4] = ["FLOAT3D", "VEC3D", "VECTOR3D", "CVector3D"];
+// A rotation quaternion then a position, read as a Placement3D
+const PLACEMENT3D_NAMES: [&str; 2] = ["QVECT", "CPlacement3D"];
+
+pub fn is_vec3d(types: &HashMap<u32, &DataType>, data_type: &DataType) -> bool {
+    is_floats(types, data_type, &VEC3D_NAMES, 3)
+}
+
+pub fn is_placement3d(types: &HashMap<u32, &DataType>, data_type: &DataType) -> bool {
+    is_floats(types, data_type, &PLACEMENT3D_NAMES, 7)
+}
+
+// Any single row array of 16 FLOATs, read as a Matrix4x4
+pub fn is_matrix4x4(types: &HashMap<u32, &DataType>, data_type: &DataType) -> bool {
+    match data_type.Type {
+        DataTypeType::Array {
+            Of,
+            rows: 1,
+            cols: 16,
+        } => resolve_type(types, Of).is_some_and(|of| {
+            of.Name == "FLOAT" && matches!(of.Type, DataTypeType::Primitive { Bytes: 4, .. })
+        }),
+        _ => false,
+    }
+}
+
+// One of the names and an array of count FLOATs, or a primitive the same size
+fn is_floats(
+    types: &HashMap<u32, &DataType>,
+    data_type: &DataType,
+    names: &[&str],
+    count: u32,
+) -> bool {
+    if !names.contains(&data_type.Name.as_str()) {
+        return false;
+    }
+    match data_type.Type {
+        DataTypeType::Array { Of, rows: 1, cols } if cols == count => resolve_type(types, Of)
+            .is_some_and(|of| {
+                of.Name == "FLOAT" && matches!(of.Type, DataTypeType::Primitive { Bytes: 4, .. })
+            }),
+        DataTypeType::Primitive { Bytes, .. } => Bytes == count * 4,
+        _ => false,
+    }
+}
+
+// Containers of objects have the object's type or a pointer as their element
+// type, anything else is stored inline
+pub fn container_holds_values(types: &HashMap<u32, &DataType>, of: u32) -> bool {
+    resolve_type(types, of).is_some_and(|data_type| {
+        !matches!(
+            data_type.Type,
+            DataTypeType::Pointer { .. } | DataTypeType::Struct { .. }
+        )
+    })
+}
+
+// An object that lives in one of the resource files, pointers can refer to
+// these by Object like any other object
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[br(import(version: u32, options: ValidationOptions))]
+#[bw(import(version: u32))]
+pub struct ExternalObject {
+    pub Object: u32,
+    // Index into ResourceFiles
+    pub File: u32,
+    #[br(if(version >= 9))]
+    #[bw(if(version >= 9))]
+    #[serde(default)]
+    pub ObtType: u32,
+    #[br(if(ObtType != 0), parse_with = parse_option_pascal_string, args(options))]
+    #[bw(if(*ObtType != 0), write_with = write_option_pascal_string)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ResName: Option<String>,
+    #[br(if(ObtType == 0))]
+    #[bw(if(*ObtType == 0))]
+    #[serde(default)]
+    pub ResID: i32,
+    pub Type: u32,
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"EXOB")]
+#[br(import(version: u32, options: ValidationOptions))]
+#[bw(import(version: u32))]
 pub struct ExternalObjects {
-    #[br(parse_with = parse_pascal_vec, assert(external_objects.is_empty()))]
-    #[bw(write_with = write_pascal_vec)]
-    pub external_objects: Vec<()>,
+    #[br(parse_with = parse_validated_pascal_vec, args(options, (version, options)))]
+    #[bw(write_with = write_pascal_vec, args((version,)))]
+    pub external_objects: ParsedVec<ExternalObject>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 pub struct InternalObjectType {
     pub Object: u32,
     pub Type: u32,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"OBTY")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct InternalObjectTypes {
-    #[br(parse_with = parse_pascal_vec)]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, ()))]
     #[bw(write_with = write_pascal_vec)]
-    pub types: Vec<InternalObjectType>,
+    pub types: ParsedVec<InternalObjectType>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EditObjectType {
+    pub Object: u32,
+    pub Type: u32,
+}
+
+// Only saves written by the editor have these
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"EDTY")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct EditObjectTypes {
-    #[br(parse_with = parse_pascal_vec, assert(edit_object_types.is_empty()))]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, ()), map = |edit_object_types: ParsedVec<EditObjectType>| {
+        if !edit_object_types.is_empty() {
+            warn!("EDTY has {} entries, edit data is not well tested", edit_object_types.len());
+        }
+        edit_object_types
+    })]
     #[bw(write_with = write_pascal_vec)]
-    pub edit_object_types: Vec<()>,
+    pub edit_object_types: ParsedVec<EditObjectType>,
+}
+
+// IDENT values are the ident name when there is one. Names that aren't in
+// Idents are added when the JSON is deserialized.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum IdentRef {
+    Id(u32),
+    Name(String),
+}
+
+impl IdentRef {
+    fn new(idents: &HashMap<u32, &str>, id: u32) -> Self {
+        match idents.get(&id) {
+            Some(name) => Self::Name((*name).to_owned()),
+            None => Self::Id(id),
+        }
+    }
+}
+
+impl fmt::Display for IdentRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// JSON has no NaN or infinity. Those are written as "NaN", "Infinity", and
+// "-Infinity", and any other NaN as its bits in hex like "0x7FA00000" so it
+// comes back exactly. Finite values are plain numbers.
+macro_rules! json_float {
+    ($module:ident, $float:ty, $bits:ty) => {
+        mod $module {
+            use serde::de::Error;
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum JsonFloat {
+                Number($float),
+                String(String),
+                // From extract --exact-floats, bits wins when both are there
+                Exact {
+                    f: Option<Box<JsonFloat>>,
+                    bits: Option<$bits>,
+                },
+            }
+
+            fn from_json<E: Error>(json: JsonFloat) -> Result<$float, E> {
+                match json {
+                    JsonFloat::Number(value) => Ok(value),
+                    JsonFloat::String(string) => match string.as_str() {
+                        "NaN" => Ok(<$float>::NAN),
+                        "Infinity" => Ok(<$float>::INFINITY),
+                        "-Infinity" => Ok(<$float>::NEG_INFINITY),
+                        _ => string
+                            .strip_prefix("0x")
+                            .and_then(|hex| <$bits>::from_str_radix(hex, 16).ok())
+                            .map(<$float>::from_bits)
+                            .ok_or_else(|| E::custom(format!("invalid float {:?}", string))),
+                    },
+                    JsonFloat::Exact {
+                        bits: Some(bits), ..
+                    } => Ok(<$float>::from_bits(bits)),
+                    JsonFloat::Exact { f: Some(f), .. } => from_json(*f),
+                    JsonFloat::Exact { .. } => Err(E::custom("float has neither f nor bits")),
+                }
+            }
+
+            pub fn serialize<S: Serializer>(
+                value: &$float,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                if value.is_finite() {
+                    value.serialize(serializer)
+                } else if *value == <$float>::INFINITY {
+                    serializer.serialize_str("Infinity")
+                } else if *value == <$float>::NEG_INFINITY {
+                    serializer.serialize_str("-Infinity")
+                } else if value.to_bits() == <$float>::NAN.to_bits() {
+                    serializer.serialize_str("NaN")
+                } else {
+                    serializer.serialize_str(&format!(
+                        "0x{:0width$X}",
+                        value.to_bits(),
+                        width = size_of::<$bits>() * 2
+                    ))
+                }
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$float, D::Error> {
+                from_json(JsonFloat::deserialize(deserializer)?)
+            }
+        }
+    };
+}
+
+json_float!(json_f32, f32, u32);
+json_float!(json_f64, f64, u64);
+
+// 64-bit integers are numbers in the JSON, or decimal strings after extract
+// --string-int64 because JavaScript based tools round anything above 2^53.
+// Either is accepted.
+macro_rules! json_int64 {
+    ($module:ident, $int:ty, $name:literal) => {
+        mod $module {
+            use std::fmt;
+
+            use serde::de::{Error, Visitor};
+            use serde::{Deserializer, Serialize, Serializer};
+
+            struct IntVisitor;
+
+            impl Visitor<'_> for IntVisitor {
+                type Value = $int;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "a {} as an integer or a decimal string", $name)
+                }
+
+                fn visit_u64<E: Error>(self, value: u64) -> Result<$int, E> {
+                    <$int>::try_from(value)
+                        .map_err(|_| E::custom(format!("{} {} is out of range", $name, value)))
+                }
+
+                fn visit_i64<E: Error>(self, value: i64) -> Result<$int, E> {
+                    <$int>::try_from(value)
+                        .map_err(|_| E::custom(format!("{} {} is out of range", $name, value)))
+                }
+
+                fn visit_f64<E: Error>(self, value: f64) -> Result<$int, E> {
+                    Err(E::custom(format!("{} {} is not an integer", $name, value)))
+                }
+
+                fn visit_str<E: Error>(self, value: &str) -> Result<$int, E> {
+                    value
+                        .parse()
+                        .map_err(|e| E::custom(format!("invalid {} {:?}: {}", $name, value, e)))
+                }
+            }
+
+            pub fn serialize<S: Serializer>(
+                value: &$int,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                value.serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$int, D::Error> {
+                deserializer.deserialize_any(IntVisitor)
+            }
+        }
+    };
+}
+
+json_int64!(json_u64, u64, "UQUAD");
+json_int64!(json_i64, i64, "SQUAD");
+
+// A Vec3D is {"x": 1.0, "y": 2.0, "z": 3.0}, [1.0, 2.0, 3.0] is accepted too
+fn deserialize_vec3d<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(f32, f32, f32), D::Error> {
+    #[derive(Deserialize)]
+    struct Float(#[serde(with = "json_f32")] f32);
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum JsonVec3D {
+        Struct {
+            #[serde(with = "json_f32")]
+            x: f32,
+            #[serde(with = "json_f32")]
+            y: f32,
+            #[serde(with = "json_f32")]
+            z: f32,
+        },
+        Array([Float; 3]),
+    }
+
+    Ok(match JsonVec3D::deserialize(deserializer)? {
+        JsonVec3D::Struct { x, y, z } => (x, y, z),
+        JsonVec3D::Array([x, y, z]) => (x.0, y.0, z.0),
+    })
+}
+
+// A Placement3D is {"qx": 0.0, "qy": 0.0, "qz": 0.0, "qw": 1.0, "x": 1.0, "y":
+// 2.0, "z": 3.0}, the seven values in that order as an array are accepted too
+#[allow(clippy::type_complexity)]
+fn deserialize_placement3d<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(f32, f32, f32, f32, f32, f32, f32), D::Error> {
+    #[derive(Deserialize)]
+    struct Float(#[serde(with = "json_f32")] f32);
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum JsonPlacement3D {
+        Struct {
+            #[serde(with = "json_f32")]
+            qx: f32,
+            #[serde(with = "json_f32")]
+            qy: f32,
+            #[serde(with = "json_f32")]
+            qz: f32,
+            #[serde(with = "json_f32")]
+            qw: f32,
+            #[serde(with = "json_f32")]
+            x: f32,
+            #[serde(with = "json_f32")]
+            y: f32,
+            #[serde(with = "json_f32")]
+            z: f32,
+        },
+        Array([Float; 7]),
+    }
+
+    Ok(match JsonPlacement3D::deserialize(deserializer)? {
+        JsonPlacement3D::Struct {
+            qx,
+            qy,
+            qz,
+            qw,
+            x,
+            y,
+            z,
+        } => (qx, qy, qz, qw, x, y, z),
+        JsonPlacement3D::Array([qx, qy, qz, qw, x, y, z]) => {
+            (qx.0, qy.0, qz.0, qw.0, x.0, y.0, z.0)
+        }
+    })
+}
+
+// A Matrix4x4 is four rows of four, [[1.0, 0.0, 0.0, 0.0], ...], the 16
+// values as a flat array are accepted too
+fn serialize_matrix4x4<S: Serializer>(
+    matrix: &[f32; 16],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Float(#[serde(with = "json_f32")] f32);
+
+    matrix
+        .chunks(4)
+        .map(|row| row.iter().map(|value| Float(*value)).collect::<Vec<_>>())
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
+
+fn deserialize_matrix4x4<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<[f32; 16], D::Error> {
+    #[derive(Deserialize)]
+    struct Float(#[serde(with = "json_f32")] f32);
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum JsonMatrix4x4 {
+        Rows([[Float; 4]; 4]),
+        Flat([Float; 16]),
+    }
+
+    Ok(match JsonMatrix4x4::deserialize(deserializer)? {
+        JsonMatrix4x4::Rows(rows) => {
+            let mut matrix = [0.0; 16];
+            for (value, float) in matrix.iter_mut().zip(rows.iter().flatten()) {
+                *value = float.0;
+            }
+            matrix
+        }
+        JsonMatrix4x4::Flat(values) => values.map(|float| float.0),
+    })
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub enum InternalObjectDataValue {
     Pointer(i32),
     CString(String),
-    IDENT(u32),
+    // A CString that isn't valid UTF-8, usually Windows-1252 from a localized
+    // install, kept as bytes so it round trips
+    CStringRaw(Vec<u8>),
+    IDENT(IdentRef),
+    Bool(bool),
     UBYTE(u8),
+    UWORD(u16),
+    SWORD(i16),
     ULONG(u32),
     SLONG(i32),
-    UQUAD(u64),
-    SQUAD(i64),
-    FLOAT(f32),
+    UINDEX(u32),
+    UQUAD(#[serde(with = "json_u64")] u64),
+    SQUAD(#[serde(with = "json_i64")] i64),
+    FLOAT(#[serde(with = "json_f32")] f32),
+    // Half precision on disk
+    SFLOAT(#[serde(with = "json_f32")] f32),
+    DOUBLE(#[serde(with = "json_f64")] f64),
+    // Either 4 or 8 bytes on disk, the type's Bytes says which
+    TIME(#[serde(with = "json_f64")] f64),
+    TICK(#[serde(with = "json_f64")] f64),
     Primitive(Vec<u8>),
+    UBYTEEnum(u8),
+    UWORDEnum(u16),
     SLONGEnum(i32),
+    SQUADEnum(i64),
+    // Enums of any other width
     Enum(Vec<u8>),
-    Array(Vec<InternalObjectDataValue>),
+    Array(Vec<Self>),
     Struct {
-        Base: Option<Box<InternalObjectDataValue>>,
-        members: Vec<InternalObjectDataValue>,
+        Base: Option<Box<Self>>,
+        members: StructMembers,
     },
+    // Memberless CSynced structs are read as their primitive now, this is how
+    // CSyncedSLONG was extracted before so that JSON can still be created
     CSyncedSLONG(i32),
-    StaticStackArray(Vec<InternalObjectDataValue>),
+    StaticStackArray(Vec<Self>),
+    #[serde(deserialize_with = "deserialize_vec3d")]
+    Vec3D {
+        #[serde(serialize_with = "json_f32::serialize")]
+        x: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        y: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        z: f32,
+    },
+    #[serde(deserialize_with = "deserialize_placement3d")]
+    Placement3D {
+        #[serde(serialize_with = "json_f32::serialize")]
+        qx: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        qy: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        qz: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        qw: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        x: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        y: f32,
+        #[serde(serialize_with = "json_f32::serialize")]
+        z: f32,
+    },
+    // 16 FLOATs in the order they're stored, row by row in the JSON
+    #[serde(
+        serialize_with = "serialize_matrix4x4",
+        deserialize_with = "deserialize_matrix4x4"
+    )]
+    Matrix4x4([f32; 16]),
     DynamicContainer(Vec<u32>),
+    // A DynamicContainer of values rather than objects
+    DynamicContainerValues(Vec<Self>),
+}
+
+// Struct members keyed by their ident name, or the member ID when there is no
+// ident. Serialized as a JSON object in the order they were read. Writing puts
+// them back in the order of the struct definition so the keys can be in any
+// order.
+#[derive(Clone, Default)]
+pub struct StructMembers(pub Vec<(String, InternalObjectDataValue)>);
+
+impl StructMembers {
+    pub fn get(&self, name: &str) -> Option<&InternalObjectDataValue> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut InternalObjectDataValue> {
+        self.0
+            .iter_mut()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Deref for StructMembers {
+    type Target = Vec<(String, InternalObjectDataValue)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for StructMembers {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Serialize for StructMembers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+    }
+}
+
+impl<'de> Deserialize<'de> for StructMembers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StructMembersVisitor;
+
+        impl<'de> Visitor<'de> for StructMembersVisitor {
+            type Value = StructMembers;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of struct member names to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut members = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some(member) = map.next_entry()? {
+                    members.push(member);
+                }
+                Ok(StructMembers(members))
+            }
+        }
+
+        deserializer.deserialize_map(StructMembersVisitor)
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+pub fn member_name(idents: &HashMap<u32, &str>, id: u32) -> String {
+    idents
+        .get(&id)
+        .map(|name| (*name).to_owned())
+        .unwrap_or_else(|| id.to_string())
+}
+
+// Long arrays only show this many elements when displayed
+const DISPLAY_ARRAY_ELEMENTS: usize = 4;
+
+fn fmt_elements<T: fmt::Display>(f: &mut fmt::Formatter<'_>, elements: &[T]) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, element) in elements.iter().take(DISPLAY_ARRAY_ELEMENTS).enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", element)?;
+    }
+    if elements.len() > DISPLAY_ARRAY_ELEMENTS {
+        write!(f, ", ... {} more", elements.len() - DISPLAY_ARRAY_ELEMENTS)?;
+    }
+    write!(f, "]")
+}
+
+fn fmt_bytes(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+// One line summary of a value, nested structs are not expanded
+impl fmt::Display for InternalObjectDataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pointer(pointer) => write!(f, "Pointer {}", pointer),
+            Self::CString(cstring) => write!(f, "CString {:?}", cstring),
+            Self::CStringRaw(bytes) => {
+                write!(f, "CStringRaw {:?}", String::from_utf8_lossy(bytes))
+            }
+            Self::IDENT(ident) => write!(f, "IDENT {}", ident),
+            Self::Bool(bool) => write!(f, "Bool {}", bool),
+            Self::UBYTE(ubyte) => write!(f, "UBYTE {}", ubyte),
+            Self::UWORD(uword) => write!(f, "UWORD {}", uword),
+            Self::SWORD(sword) => write!(f, "SWORD {}", sword),
+            Self::ULONG(ulong) => write!(f, "ULONG {}", ulong),
+            Self::SLONG(slong) => write!(f, "SLONG {}", slong),
+            Self::UINDEX(uindex) => write!(f, "UINDEX {}", uindex),
+            Self::UQUAD(uquad) => write!(f, "UQUAD {}", uquad),
+            Self::SQUAD(squad) => write!(f, "SQUAD {}", squad),
+            Self::FLOAT(float) => write!(f, "FLOAT {}", float),
+            Self::SFLOAT(sfloat) => write!(f, "SFLOAT {}", sfloat),
+            Self::DOUBLE(double) => write!(f, "DOUBLE {}", double),
+            Self::TIME(time) => write!(f, "TIME {}", time),
+            Self::TICK(tick) => write!(f, "TICK {}", tick),
+            Self::Primitive(bytes) => {
+                write!(f, "Primitive ")?;
+                fmt_bytes(f, bytes)
+            }
+            Self::UBYTEEnum(ubyte_enum) => write!(f, "UBYTEEnum {}", ubyte_enum),
+            Self::UWORDEnum(uword_enum) => write!(f, "UWORDEnum {}", uword_enum),
+            Self::SLONGEnum(slong_enum) => write!(f, "SLONGEnum {}", slong_enum),
+            Self::SQUADEnum(squad_enum) => write!(f, "SQUADEnum {}", squad_enum),
+            Self::Enum(bytes) => {
+                write!(f, "Enum ")?;
+                fmt_bytes(f, bytes)
+            }
+            Self::Array(values) => {
+                write!(f, "Array ")?;
+                fmt_elements(f, values)
+            }
+            Self::Struct { members, .. } => write!(f, "Struct, {} members", members.len()),
+            Self::CSyncedSLONG(csynced_slong) => write!(f, "CSyncedSLONG {}", csynced_slong),
+            Self::Vec3D { x, y, z } => write!(f, "Vec3D ({}, {}, {})", x, y, z),
+            Self::Placement3D {
+                qx,
+                qy,
+                qz,
+                qw,
+                x,
+                y,
+                z,
+            } => write!(
+                f,
+                "Placement3D ({}, {}, {}, {}) ({}, {}, {})",
+                qx, qy, qz, qw, x, y, z
+            ),
+            Self::Matrix4x4(matrix) => {
+                write!(f, "Matrix4x4 ")?;
+                for (i, row) in matrix.chunks(4).enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "({}, {}, {}, {})", row[0], row[1], row[2], row[3])?;
+                }
+                Ok(())
+            }
+            Self::StaticStackArray(values) => {
+                write!(f, "StaticStackArray ")?;
+                fmt_elements(f, values)
+            }
+            Self::DynamicContainer(pointers) => {
+                write!(f, "DynamicContainer ")?;
+                fmt_elements(f, pointers)
+            }
+            Self::DynamicContainerValues(values) => {
+                write!(f, "DynamicContainerValues ")?;
+                fmt_elements(f, values)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct InternalObject {
-    Object: u32,
-    Type: u32,
-    value: InternalObjectDataValue,
+    pub Object: u32,
+    pub Type: u32,
+    pub value: InternalObjectDataValue,
 }
 
 #[derive(BinRead, BinWrite)]
@@ -239,22 +1107,47 @@ struct DCONMagic;
 #[brw(magic = b"SSAR")]
 struct SSARMagic;
 
+// No real type comes close, this is only to stop types that contain themselves
+const MAX_TYPE_DEPTH: usize = 64;
+
+// Kept out of read_type so its stack frame stays small for deep types
+#[cold]
+fn unknown_type_error(pos: u64, data_type: &DataType, tag: u32) -> binrw::Error {
+    binrw::Error::AssertFail {
+        pos,
+        message: format!(
+            "type {} has unknown kind {}, please report this",
+            data_type.Name, tag
+        ),
+    }
+}
+
 impl BinRead for InternalObject {
-    type Args<'a> = (&'a InternalTypes,);
+    type Args<'a> = (&'a InternalTypes, &'a Idents, ValidationOptions);
 
     fn read_options<R: std::io::Read + std::io::Seek>(
         reader: &mut R,
         endian: Endian,
         args: Self::Args<'_>,
     ) -> BinResult<Self> {
-        let internal_types = args.0;
+        let (internal_types, idents, options) = args;
 
         fn read_type<R: std::io::Read + std::io::Seek>(
             reader: &mut R,
             endian: Endian,
             data_type: u32,
             internal_types: &HashMap<u32, &DataType>,
+            idents: &HashMap<u32, &str>,
+            options: &ValidationOptions,
+            depth: usize,
         ) -> BinResult<InternalObjectDataValue> {
+            // Types that contain themselves would recurse forever
+            if depth > MAX_TYPE_DEPTH {
+                return Err(binrw::Error::AssertFail {
+                    pos: reader.stream_position()?,
+                    message: format!("type {} is nested too deeply", data_type),
+                });
+            }
             let data_type = internal_types.get(&data_type).ok_or_else(|| {
                 let pos = match reader.stream_position() {
                     Ok(pos) => pos,
@@ -266,19 +1159,79 @@ impl BinRead for InternalObject {
                 }
             })?;
             let value = match &data_type.Type {
+                _ if is_vec3d(internal_types, data_type) => InternalObjectDataValue::Vec3D {
+                    x: f32::read_options(reader, endian, ())?,
+                    y: f32::read_options(reader, endian, ())?,
+                    z: f32::read_options(reader, endian, ())?,
+                },
+                _ if is_matrix4x4(internal_types, data_type) => {
+                    let mut matrix = [0.0; 16];
+                    for value in &mut matrix {
+                        *value = f32::read_options(reader, endian, ())?;
+                    }
+                    InternalObjectDataValue::Matrix4x4(matrix)
+                }
+                _ if is_placement3d(internal_types, data_type) => {
+                    InternalObjectDataValue::Placement3D {
+                        qx: f32::read_options(reader, endian, ())?,
+                        qy: f32::read_options(reader, endian, ())?,
+                        qz: f32::read_options(reader, endian, ())?,
+                        qw: f32::read_options(reader, endian, ())?,
+                        x: f32::read_options(reader, endian, ())?,
+                        y: f32::read_options(reader, endian, ())?,
+                        z: f32::read_options(reader, endian, ())?,
+                    }
+                }
                 DataTypeType::Primitive { Bytes, .. } => match data_type.Name.as_str() {
                     // Special case for primitive named CString, it is a Pascal string
                     "CString" => {
-                        InternalObjectDataValue::CString(parse_pascal_string(reader, endian, ())?)
+                        let bytes = parse_validated_pascal_bytes(reader, endian, (*options,))?;
+                        match String::from_utf8(bytes) {
+                            Ok(cstring) => InternalObjectDataValue::CString(cstring),
+                            Err(e) => {
+                                warn!("CString is not valid UTF-8, keeping the raw bytes");
+                                InternalObjectDataValue::CStringRaw(e.into_bytes())
+                            }
+                        }
                     }
                     // Special case for primitive named IDENT, it is a ULONG
-                    "IDENT" => {
-                        InternalObjectDataValue::IDENT(u32::read_options(reader, endian, ())?)
-                    }
+                    "IDENT" => InternalObjectDataValue::IDENT(IdentRef::new(
+                        idents,
+                        u32::read_options(reader, endian, ())?,
+                    )),
                     // Special cases for known types so they are easier to edit in the JSON
+                    "BOOL" | "SBOOL" if matches!(Bytes, 1 | 2 | 4) => {
+                        let value = match Bytes {
+                            1 => u8::read_options(reader, endian, ())? as u32,
+                            2 => u16::read_options(reader, endian, ())? as u32,
+                            _ => u32::read_options(reader, endian, ())?,
+                        };
+                        match value {
+                            0 => InternalObjectDataValue::Bool(false),
+                            1 => InternalObjectDataValue::Bool(true),
+                            // Keep the exact value so it round trips
+                            value => {
+                                warn!("{} has the non-boolean value {}", data_type.Name, value);
+                                match Bytes {
+                                    1 => InternalObjectDataValue::UBYTE(value as u8),
+                                    2 => InternalObjectDataValue::UWORD(value as u16),
+                                    _ => InternalObjectDataValue::ULONG(value),
+                                }
+                            }
+                        }
+                    }
                     "UBYTE" => {
                         InternalObjectDataValue::UBYTE(u8::read_options(reader, endian, ())?)
                     }
+                    "UWORD" if *Bytes == 2 => {
+                        InternalObjectDataValue::UWORD(u16::read_options(reader, endian, ())?)
+                    }
+                    "SWORD" if *Bytes == 2 => {
+                        InternalObjectDataValue::SWORD(i16::read_options(reader, endian, ())?)
+                    }
+                    "UINDEX" if *Bytes == 4 => {
+                        InternalObjectDataValue::UINDEX(u32::read_options(reader, endian, ())?)
+                    }
                     "ULONG" => {
                         InternalObjectDataValue::ULONG(u32::read_options(reader, endian, ())?)
                     }
@@ -294,6 +1247,22 @@ impl BinRead for InternalObject {
                     "FLOAT" => {
                         InternalObjectDataValue::FLOAT(f32::read_options(reader, endian, ())?)
                     }
+                    "SFLOAT" if *Bytes == 2 => InternalObjectDataValue::SFLOAT(
+                        f16::from_bits(u16::read_options(reader, endian, ())?).to_f32(),
+                    ),
+                    "DOUBLE" if *Bytes == 8 => {
+                        InternalObjectDataValue::DOUBLE(f64::read_options(reader, endian, ())?)
+                    }
+                    "TIME" | "TICK" if matches!(Bytes, 4 | 8) => {
+                        let value = match Bytes {
+                            4 => f32::read_options(reader, endian, ())? as f64,
+                            _ => f64::read_options(reader, endian, ())?,
+                        };
+                        match data_type.Name.as_str() {
+                            "TIME" => InternalObjectDataValue::TIME(value),
+                            _ => InternalObjectDataValue::TICK(value),
+                        }
+                    }
                     _ => {
                         warn!(
                             "Unknown primitive type: ID: {}, name: {}, size: {}, format: {}",
@@ -309,7 +1278,10 @@ impl BinRead for InternalObject {
                 DataTypeType::Enum { Bytes } => match Bytes {
                     // Special cases for known enum sizes so they are easier to edit in the
                     // JSON
+                    1 => InternalObjectDataValue::UBYTEEnum(u8::read_options(reader, endian, ())?),
+                    2 => InternalObjectDataValue::UWORDEnum(u16::read_options(reader, endian, ())?),
                     4 => InternalObjectDataValue::SLONGEnum(i32::read_options(reader, endian, ())?),
+                    8 => InternalObjectDataValue::SQUADEnum(i64::read_options(reader, endian, ())?),
                     _ => InternalObjectDataValue::Enum(Vec::<u8>::read_options(
                         reader,
                         endian,
@@ -320,57 +1292,154 @@ impl BinRead for InternalObject {
                     // This is either -1 or the ID of another Object in the file
                     InternalObjectDataValue::Pointer(i32::read_options(reader, endian, ())?)
                 }
-                DataTypeType::Array { Of, cols, .. } => InternalObjectDataValue::Array(
-                    std::iter::repeat_with(|| read_type(reader, endian, *Of, internal_types))
-                        .take(*cols as usize)
-                        .collect::<Result<Vec<_>, _>>()?,
-                ),
-                DataTypeType::Struct { Base, members } => match data_type.Name.as_str() {
-                    // Special case for struct named CSyncedSLONG with 0 members, it is an
-                    // SLONG
-                    "CSyncedSLONG" if members.is_empty() => InternalObjectDataValue::CSyncedSLONG(
-                        i32::read_options(reader, endian, ())?,
-                    ),
-                    _ => {
-                        let Base = if *Base != -1 {
-                            Some(Box::new(read_type(
+                DataTypeType::Array { Of, rows, cols } => {
+                    let mut read_row = || {
+                        std::iter::repeat_with(|| {
+                            read_type(
                                 reader,
                                 endian,
-                                *Base as u32,
+                                *Of,
                                 internal_types,
-                            )?))
-                        } else {
-                            None
-                        };
+                                idents,
+                                options,
+                                depth + 1,
+                            )
+                        })
+                        .take(*cols as usize)
+                        .collect::<Result<Vec<_>, _>>()
+                    };
+                    if *rows == 1 {
+                        InternalObjectDataValue::Array(read_row()?)
+                    } else {
+                        InternalObjectDataValue::Array(
+                            (0..*rows)
+                                .map(|_| read_row().map(InternalObjectDataValue::Array))
+                                .collect::<Result<Vec<_>, _>>()?,
+                        )
+                    }
+                }
+                DataTypeType::Struct { .. } if is_csynced(data_type) => {
+                    let Some(primitive) = csynced_primitive(internal_types, data_type) else {
+                        return Err(binrw::Error::Custom {
+                            pos: reader.stream_position()?,
+                            err: Box::new(format!(
+                                "{} has no members and its primitive isn't in the types",
+                                data_type.Name
+                            )),
+                        });
+                    };
+                    read_type(
+                        reader,
+                        endian,
+                        primitive.DataType,
+                        internal_types,
+                        idents,
+                        options,
+                        depth + 1,
+                    )?
+                }
+                DataTypeType::Struct { Base, members } => {
+                    let Base = if *Base != -1 {
+                        Some(Box::new(read_type(
+                            reader,
+                            endian,
+                            *Base as u32,
+                            internal_types,
+                            idents,
+                            options,
+                            depth + 1,
+                        )?))
+                    } else {
+                        None
+                    };
 
-                        let members = members
-                            .iter()
-                            .map(|member| read_type(reader, endian, member.Type, internal_types))
-                            .collect::<Result<Vec<_>, _>>()?;
-                        InternalObjectDataValue::Struct { Base, members }
+                    let members = members
+                        .iter()
+                        .map(|member| {
+                            Ok((
+                                member_name(idents, member.ID),
+                                read_type(
+                                    reader,
+                                    endian,
+                                    member.Type,
+                                    internal_types,
+                                    idents,
+                                    options,
+                                    depth + 1,
+                                )?,
+                            ))
+                        })
+                        .collect::<BinResult<Vec<_>>>()?;
+                    InternalObjectDataValue::Struct {
+                        Base,
+                        members: StructMembers(members),
                     }
-                },
+                }
                 DataTypeType::StaticStackArray { Of } => {
                     SSARMagic::read_options(reader, endian, ())?;
 
                     let count = u32::read_options(reader, endian, ())?;
                     InternalObjectDataValue::StaticStackArray(
-                        std::iter::repeat_with(|| read_type(reader, endian, *Of, internal_types))
-                            .take(count as usize)
-                            .collect::<Result<Vec<_>, _>>()?,
+                        std::iter::repeat_with(|| {
+                            read_type(
+                                reader,
+                                endian,
+                                *Of,
+                                internal_types,
+                                idents,
+                                options,
+                                depth + 1,
+                            )
+                        })
+                        .take(count as usize)
+                        .collect::<Result<Vec<_>, _>>()?,
                     )
                 }
-                DataTypeType::DynamicContainer { .. } => {
+                DataTypeType::DynamicContainer { Of } => {
                     DCONMagic::read_options(reader, endian, ())?;
 
                     let count = u32::read_options(reader, endian, ())?;
-                    InternalObjectDataValue::DynamicContainer(
-                        std::iter::repeat_with(|| u32::read_options(reader, endian, ()))
+                    if container_holds_values(internal_types, *Of) {
+                        InternalObjectDataValue::DynamicContainerValues(
+                            std::iter::repeat_with(|| {
+                                read_type(
+                                    reader,
+                                    endian,
+                                    *Of,
+                                    internal_types,
+                                    idents,
+                                    options,
+                                    depth + 1,
+                                )
+                            })
                             .take(count as usize)
                             .collect::<Result<Vec<_>, _>>()?,
-                    )
+                        )
+                    } else {
+                        InternalObjectDataValue::DynamicContainer(
+                            std::iter::repeat_with(|| u32::read_options(reader, endian, ()))
+                                .take(count as usize)
+                                .collect::<Result<Vec<_>, _>>()?,
+                        )
+                    }
+                }
+                DataTypeType::TypeDef { For } => read_type(
+                    reader,
+                    endian,
+                    *For,
+                    internal_types,
+                    idents,
+                    options,
+                    depth + 1,
+                )?,
+                // Nothing is known about how big a value of this type is
+                DataTypeType::Unknown { tag, .. } => {
+                    return Err(unknown_type_error(
+                        reader.stream_position()?,
+                        data_type,
+                        *tag,
+                    ));
                 }
-                DataTypeType::TypeDef { For } => read_type(reader, endian, *For, internal_types)?,
             };
 
             Ok(value)
@@ -384,9 +1453,10 @@ impl BinRead for InternalObject {
             .by_ref()
             .map(|t| (t.DataType, t))
             .collect::<HashMap<_, _>>();
+        let idents = idents.by_id();
         let Object = u32::read_options(reader, endian, ())?;
         let Type = u32::read_options(reader, endian, ())?;
-        let value = read_type(reader, endian, Type, &internal_types)?;
+        let value = read_type(reader, endian, Type, &internal_types, &idents, &options, 0)?;
 
         Ok(Self {
             Object,
@@ -397,22 +1467,76 @@ impl BinRead for InternalObject {
 }
 
 impl BinWrite for InternalObject {
-    type Args<'a> = ();
+    type Args<'a> = (&'a InternalTypes, &'a Idents);
 
     fn write_options<W: std::io::Write + std::io::Seek>(
         &self,
         writer: &mut W,
         endian: Endian,
-        _args: Self::Args<'_>,
+        args: Self::Args<'_>,
     ) -> BinResult<()> {
+        let (internal_types, idents) = args;
+
+        // FIXME: Same as reading, the HashMaps are rebuilt for every object
+        let internal_types = internal_types.by_id();
+        let idents = idents.by_id();
+        // Writing a value that doesn't match its type would produce a save the
+        // game can't read
+        if let Some(problem) = validate_value(&self.value, self.Type, &internal_types, &idents)
+            .into_iter()
+            .next()
+        {
+            return Err(binrw::Error::Custom {
+                pos: writer.stream_position()?,
+                err: Box::new(format!(
+                    "object {} at value{}: {}",
+                    self.Object, problem.path, problem.message
+                )),
+            });
+        }
+
         self.Object.write_options(writer, endian, ())?;
         self.Type.write_options(writer, endian, ())?;
 
+        // The type is only used to put struct members back in order and for
+        // the width of BOOL, TIME, and TICK, values without a known type are
+        // written as they are
         fn write_value<W: Write + Seek>(
             value: &InternalObjectDataValue,
+            data_type: Option<u32>,
             writer: &mut W,
             endian: Endian,
+            internal_types: &HashMap<u32, &DataType>,
+            idents: &HashMap<u32, &str>,
         ) -> BinResult<()> {
+            let data_type = data_type.and_then(|id| resolve_type(internal_types, id));
+            // Memberless CSynced structs are written as their primitive
+            let data_type = match data_type {
+                Some(data_type)
+                    if is_csynced(data_type)
+                        && !matches!(value, InternalObjectDataValue::CSyncedSLONG(_)) =>
+                {
+                    let Some(primitive) = csynced_primitive(internal_types, data_type) else {
+                        return Err(binrw::Error::Custom {
+                            pos: writer.stream_position()?,
+                            err: Box::new(format!(
+                                "{} has no members and its primitive isn't in the types",
+                                data_type.Name
+                            )),
+                        });
+                    };
+                    Some(primitive)
+                }
+                data_type => data_type,
+            };
+            let element_type = match data_type.map(|x| &x.Type) {
+                Some(
+                    DataTypeType::Array { Of, .. }
+                    | DataTypeType::StaticStackArray { Of }
+                    | DataTypeType::DynamicContainer { Of },
+                ) => Some(*Of),
+                _ => None,
+            };
             match value {
                 InternalObjectDataValue::Pointer(pointer) => {
                     pointer.write_options(writer, endian, ())
@@ -420,34 +1544,154 @@ impl BinWrite for InternalObject {
                 InternalObjectDataValue::CString(cstring) => {
                     write_pascal_string(cstring, writer, endian, ())
                 }
-                InternalObjectDataValue::IDENT(ident) => ident.write_options(writer, endian, ()),
+                InternalObjectDataValue::CStringRaw(bytes) => {
+                    write_pascal_bytes(bytes, writer, endian, ())
+                }
+                InternalObjectDataValue::IDENT(IdentRef::Id(id)) => {
+                    id.write_options(writer, endian, ())
+                }
+                InternalObjectDataValue::IDENT(IdentRef::Name(name)) => {
+                    let Some(id) = idents
+                        .iter()
+                        .find_map(|(id, ident)| (*ident == name).then_some(*id))
+                    else {
+                        return Err(binrw::Error::Custom {
+                            pos: writer.stream_position()?,
+                            err: Box::new(format!("ident {} is not in Idents", name)),
+                        });
+                    };
+                    id.write_options(writer, endian, ())
+                }
+                InternalObjectDataValue::Bool(bool) => match primitive_bytes(data_type) {
+                    Some(2) => (*bool as u16).write_options(writer, endian, ()),
+                    Some(4) => (*bool as u32).write_options(writer, endian, ()),
+                    _ => (*bool as u8).write_options(writer, endian, ()),
+                },
                 InternalObjectDataValue::UBYTE(ubyte) => ubyte.write_options(writer, endian, ()),
+                InternalObjectDataValue::UWORD(uword) => uword.write_options(writer, endian, ()),
+                InternalObjectDataValue::SWORD(sword) => sword.write_options(writer, endian, ()),
                 InternalObjectDataValue::ULONG(ulong) => ulong.write_options(writer, endian, ()),
                 InternalObjectDataValue::SLONG(slong) => slong.write_options(writer, endian, ()),
+                InternalObjectDataValue::UINDEX(uindex) => uindex.write_options(writer, endian, ()),
                 InternalObjectDataValue::UQUAD(uquad) => uquad.write_options(writer, endian, ()),
                 InternalObjectDataValue::SQUAD(squad) => squad.write_options(writer, endian, ()),
                 InternalObjectDataValue::FLOAT(float) => float.write_options(writer, endian, ()),
+                InternalObjectDataValue::SFLOAT(sfloat) => f16::from_f32(*sfloat)
+                    .to_bits()
+                    .write_options(writer, endian, ()),
+                InternalObjectDataValue::DOUBLE(double) => double.write_options(writer, endian, ()),
+                InternalObjectDataValue::TIME(value) | InternalObjectDataValue::TICK(value) => {
+                    match primitive_bytes(data_type) {
+                        Some(4) => (*value as f32).write_options(writer, endian, ()),
+                        _ => value.write_options(writer, endian, ()),
+                    }
+                }
                 InternalObjectDataValue::Primitive(bytes) => {
                     bytes.write_options(writer, endian, ())
                 }
+                InternalObjectDataValue::UBYTEEnum(ubyte_enum) => {
+                    ubyte_enum.write_options(writer, endian, ())
+                }
+                InternalObjectDataValue::UWORDEnum(uword_enum) => {
+                    uword_enum.write_options(writer, endian, ())
+                }
                 InternalObjectDataValue::SLONGEnum(slong_enum) => {
                     slong_enum.write_options(writer, endian, ())
                 }
+                InternalObjectDataValue::SQUADEnum(squad_enum) => {
+                    squad_enum.write_options(writer, endian, ())
+                }
                 InternalObjectDataValue::Enum(bytes) => bytes.write_options(writer, endian, ()),
                 InternalObjectDataValue::Array(internal_object_data_values) => {
+                    let rows = match data_type.map(|x| &x.Type) {
+                        Some(DataTypeType::Array { rows, .. }) => *rows,
+                        _ => 1,
+                    };
                     for value in internal_object_data_values {
-                        write_value(value, writer, endian)?;
+                        match value {
+                            InternalObjectDataValue::Array(row) if rows != 1 => {
+                                for value in row {
+                                    write_value(
+                                        value,
+                                        element_type,
+                                        writer,
+                                        endian,
+                                        internal_types,
+                                        idents,
+                                    )?;
+                                }
+                            }
+                            value => write_value(
+                                value,
+                                element_type,
+                                writer,
+                                endian,
+                                internal_types,
+                                idents,
+                            )?,
+                        }
                     }
 
                     Ok(())
                 }
                 InternalObjectDataValue::Struct { Base, members } => {
+                    let (base_type, member_types) = match data_type {
+                        Some(DataType {
+                            Type: DataTypeType::Struct { Base, members },
+                            ..
+                        }) => ((*Base != -1).then_some(*Base as u32), Some(members)),
+                        _ => (None, None),
+                    };
+
                     if let Some(Base) = Base {
-                        write_value(Base, writer, endian)?;
+                        write_value(Base, base_type, writer, endian, internal_types, idents)?;
                     }
 
-                    for member in members {
-                        write_value(member, writer, endian)?;
+                    let Some(member_types) = member_types else {
+                        for (_, member) in members.iter() {
+                            write_value(member, None, writer, endian, internal_types, idents)?;
+                        }
+                        return Ok(());
+                    };
+
+                    let error = |writer: &mut W, message: String| -> binrw::Error {
+                        match writer.stream_position() {
+                            Ok(pos) => binrw::Error::Custom {
+                                pos,
+                                err: Box::new(message),
+                            },
+                            Err(e) => binrw::Error::Io(e),
+                        }
+                    };
+                    let type_name = data_type.map(|x| x.Name.as_str()).unwrap_or_default();
+                    // Members can be looked up by ident name or by ID
+                    let mut used = vec![false; members.len()];
+                    for member_type in member_types {
+                        let name = member_name(idents, member_type.ID);
+                        let id = member_type.ID.to_string();
+                        let Some(i) = (0..members.len())
+                            .find(|i| !used[*i] && (members[*i].0 == name || members[*i].0 == id))
+                        else {
+                            return Err(error(
+                                writer,
+                                format!("struct {} is missing member {}", type_name, name),
+                            ));
+                        };
+                        used[i] = true;
+                        write_value(
+                            &members[i].1,
+                            Some(member_type.Type),
+                            writer,
+                            endian,
+                            internal_types,
+                            idents,
+                        )?;
+                    }
+                    if let Some(i) = used.iter().position(|used| !used) {
+                        return Err(error(
+                            writer,
+                            format!("struct {} has no member {}", type_name, members[i].0),
+                        ));
                     }
 
                     Ok(())
@@ -459,11 +1703,26 @@ impl BinWrite for InternalObject {
                     SSARMagic.write_options(writer, endian, ())?;
                     (internal_object_data_values.len() as u32).write_options(writer, endian, ())?;
                     for value in internal_object_data_values {
-                        write_value(value, writer, endian)?;
+                        write_value(value, element_type, writer, endian, internal_types, idents)?;
                     }
 
                     Ok(())
                 }
+                InternalObjectDataValue::Vec3D { x, y, z } => {
+                    [*x, *y, *z].write_options(writer, endian, ())
+                }
+                InternalObjectDataValue::Placement3D {
+                    qx,
+                    qy,
+                    qz,
+                    qw,
+                    x,
+                    y,
+                    z,
+                } => [*qx, *qy, *qz, *qw, *x, *y, *z].write_options(writer, endian, ()),
+                InternalObjectDataValue::Matrix4x4(matrix) => {
+                    matrix.write_options(writer, endian, ())
+                }
                 InternalObjectDataValue::DynamicContainer(pointers) => {
                     DCONMagic.write_options(writer, endian, ())?;
                     (pointers.len() as u32).write_options(writer, endian, ())?;
@@ -471,57 +1730,2168 @@ impl BinWrite for InternalObject {
                         pointer.write_options(writer, endian, ())?;
                     }
 
+                    Ok(())
+                }
+                InternalObjectDataValue::DynamicContainerValues(internal_object_data_values) => {
+                    DCONMagic.write_options(writer, endian, ())?;
+                    (internal_object_data_values.len() as u32).write_options(writer, endian, ())?;
+                    for value in internal_object_data_values {
+                        write_value(value, element_type, writer, endian, internal_types, idents)?;
+                    }
+
                     Ok(())
                 }
             }
         }
 
-        write_value(&self.value, writer, endian)?;
+        write_value(
+            &self.value,
+            Some(self.Type),
+            writer,
+            endian,
+            &internal_types,
+            &idents,
+        )?;
 
         Ok(())
     }
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"OBJS")]
-#[br(import(internal_types: &InternalTypes))]
+#[br(import(internal_types: &InternalTypes, idents: &Idents, options: ValidationOptions))]
+#[bw(import(internal_types: &InternalTypes, idents: &Idents))]
 pub struct InternalObjects {
-    #[br(parse_with = parse_pascal_vec, args((internal_types,)))]
-    #[bw(write_with = write_pascal_vec)]
-    pub internal_object: Vec<InternalObject>,
+    #[br(parse_with = parse_internal_objects, args(internal_types, idents, options))]
+    #[bw(write_with = write_pascal_vec, args((internal_types, idents)))]
+    pub internal_object: ParsedVec<InternalObject>,
 }
 
-#[derive(BinRead, BinWrite, Serialize, Deserialize)]
+// With keep_partial_objects an object that can't be read ends the list, the
+// rest of OBJS is skipped up to the EDOB after it
+#[parser(reader, endian)]
+fn parse_internal_objects(
+    internal_types: &InternalTypes,
+    idents: &Idents,
+    options: ValidationOptions,
+) -> BinResult<ParsedVec<InternalObject>> {
+    if !options.keep_partial_objects {
+        return parse_validated_pascal_vec(
+            reader,
+            endian,
+            (options, (internal_types, idents, options)),
+        );
+    }
+
+    let count = u32::read_options(reader, endian, ())?;
+    let mut internal_objects = Vec::new();
+    for i in 0..count {
+        let pos = reader.stream_position()?;
+        match InternalObject::read_options(reader, endian, (internal_types, idents, options)) {
+            Ok(internal_object) => internal_objects.push(internal_object),
+            Err(e) => {
+                warn!(
+                    "stopped reading objects at object {} of {}: {}",
+                    i, count, e
+                );
+                // Edit objects are last so the final EDOB is the real one
+                reader.seek(SeekFrom::Start(pos))?;
+                let mut rest = Vec::new();
+                reader.read_to_end(&mut rest)?;
+                let Some(edob) = rest.windows(4).rposition(|x| x == b"EDOB") else {
+                    return Err(e);
+                };
+                reader.seek(SeekFrom::Start(pos + edob as u64))?;
+                break;
+            }
+        }
+    }
+    // The dropped objects aren't counted
+    Ok(ParsedVec::from(internal_objects))
+}
+
+// The layout of an edit object is unknown, each one is assumed to be length
+// prefixed and is kept as raw bytes so it survives a round trip
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[br(import_raw(options: ValidationOptions))]
+pub struct EditObject {
+    #[br(parse_with = parse_validated_pascal_bytes, args(options))]
+    #[bw(write_with = write_pascal_bytes)]
+    pub data: Vec<u8>,
+}
+
+#[derive(BinRead, BinWrite, Serialize, Deserialize, Clone)]
 #[brw(magic = b"EDOB")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct EditObjects {
-    #[br(parse_with = parse_pascal_vec, assert(edit_objects.is_empty()))]
+    #[br(parse_with = parse_validated_pascal_vec, args(options, options))]
     #[bw(write_with = write_pascal_vec)]
-    pub edit_objects: Vec<()>,
+    pub edit_objects: ParsedVec<EditObject>,
 }
 
 #[derive(BinRead, BinWrite, Serialize, Deserialize)]
 #[brw(magic = b"METAEND ")]
 pub struct Metaend;
 
+// From CTSEMeta::references
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reference {
+    // The internal object holding the reference
+    pub object: u32,
+    // A JSON pointer into the extracted JSON
+    pub path: String,
+    pub target: u32,
+    // Pointers can be set to -1, dynamic container entries can't
+    pub nullable: bool,
+}
+
+// From CTSEMeta::check_references
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(tag = "kind")]
+pub enum ReferenceIssue {
+    // A pointer or container entry whose object doesn't exist, the path is a
+    // JSON pointer into the extracted JSON
+    Dangling { path: String, target: u32 },
+    // Nothing points to the object, roots like the player profile are like
+    // this so it isn't a problem on its own
+    Orphan { object: u32 },
+}
+
+impl fmt::Display for ReferenceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dangling { path, target } => {
+                write!(
+                    f,
+                    "{}: points to object {} which doesn't exist",
+                    path, target
+                )
+            }
+            Self::Orphan { object } => write!(f, "object {} isn't referenced by anything", object),
+        }
+    }
+}
+
 #[binrw]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(remote = "Self")]
+#[br(import_raw(options: ValidationOptions))]
 pub struct CTSEMeta {
+    #[br(args_raw(options))]
     pub metadata: Metadata,
+    #[br(args_raw(options))]
     pub messages: Messages,
-    #[br(temp)]
-    #[bw(calc = Info::new(resource_files, idents, external_types, internal_types, external_objects, internal_objects))]
+    #[br(temp, args(metadata.version))]
+    #[bw(calc = Info::new(metadata.version, *edit_data_stripped, edit_object_types, edit_objects, resource_files, idents, external_types, internal_types, external_objects, internal_objects))]
     pub _info: Info,
+    // Shipped saves never have edit data
+    #[br(calc = _info.EditDataStripped.is_none_or(|stripped| stripped != 0))]
+    #[bw(ignore)]
+    #[serde(default = "edit_data_stripped_default")]
+    pub edit_data_stripped: bool,
+    #[br(args(metadata.version, options))]
+    #[bw(args(metadata.version))]
     pub resource_files: ResourceFiles,
+    #[br(args_raw(options))]
     pub idents: Idents,
+    #[br(args_raw(options))]
     pub external_types: ExternalTypes,
+    #[br(args_raw(options))]
     pub internal_types: InternalTypes,
+    #[br(args(metadata.version, options))]
+    #[bw(args(metadata.version))]
     pub external_objects: ExternalObjects,
+    #[br(args_raw(options))]
     pub internal_object_types: InternalObjectTypes,
+    #[br(args_raw(options))]
     pub edit_object_types: EditObjectTypes,
-    #[br(args(&internal_types))]
+    #[br(args(&internal_types, &idents, options))]
+    #[bw(args(internal_types, idents))]
     pub internal_objects: InternalObjects,
+    #[br(temp, calc = warn_table_problems(&idents, &internal_types, &internal_object_types, &internal_objects))]
+    #[bw(calc = ())]
+    _tables_checked: (),
+    #[br(args_raw(options))]
     pub edit_objects: EditObjects,
+    #[br(temp, parse_with = check_info, args(&_info, Info::new(metadata.version, edit_data_stripped, &edit_object_types, &edit_objects, &resource_files, &idents, &external_types, &internal_types, &external_objects, &internal_objects), options))]
+    #[bw(calc = ())]
+    _info_checked: (),
     #[br(temp)]
     #[bw(calc = Metaend)]
     _metaend: Metaend,
+    // Anything after METAEND, kept so it isn't lost when the save is created
+    // again
+    #[br(parse_with = parse_trailing, args(options))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailing: Vec<u8>,
+}
+
+fn warn_table_problems(
+    idents: &Idents,
+    internal_types: &InternalTypes,
+    internal_object_types: &InternalObjectTypes,
+    internal_objects: &InternalObjects,
+) {
+    for problem in check_tables(
+        &idents.idents,
+        &internal_types.types,
+        &internal_object_types.types,
+        &internal_objects.internal_object,
+    ) {
+        warn!("{}", problem);
+    }
+}
+
+// INFO is recalculated when writing so a wrong count is only a sign of
+// corruption, not something that has to be kept
+#[parser(reader)]
+fn check_info(info: &Info, actual: Info, options: ValidationOptions) -> BinResult<()> {
+    let mismatches = [
+        ("resource files", info.ResourceFiles, actual.ResourceFiles),
+        ("idents", info.Idents, actual.Idents),
+        ("types", info.Types, actual.Types),
+        ("objects", info.Objects, actual.Objects),
+    ]
+    .into_iter()
+    .filter(|(_, expected, found)| expected != found)
+    .map(|(name, expected, found)| {
+        format!("INFO says {} {} but there are {}", expected, name, found)
+    })
+    .collect::<Vec<_>>();
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    if options.strict {
+        return Err(binrw::Error::AssertFail {
+            pos: reader.stream_position()?,
+            message: mismatches.join(", "),
+        });
+    }
+    for mismatch in mismatches {
+        warn!("{}", mismatch);
+    }
+    Ok(())
+}
+
+#[parser(reader)]
+fn parse_trailing(options: ValidationOptions) -> BinResult<Vec<u8>> {
+    let pos = reader.stream_position()?;
+    let mut trailing = Vec::new();
+    reader.read_to_end(&mut trailing)?;
+    if trailing.is_empty() {
+        return Ok(trailing);
+    }
+    let message = format!("{} bytes after METAEND at {:#x}", trailing.len(), pos);
+    if options.strict {
+        return Err(binrw::Error::AssertFail { pos, message });
+    }
+    warn!("{}, keeping them", message);
+    Ok(trailing)
+}
+
+fn edit_data_stripped_default() -> bool {
+    true
+}
+
+impl Serialize for CTSEMeta {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Self::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CTSEMeta {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut ctsemeta = Self::deserialize(deserializer)?;
+        ctsemeta.add_missing_idents();
+        Ok(ctsemeta)
+    }
+}
+
+impl CTSEMeta {
+    // Reads a payload using the endianness from its cookie
+    pub fn from_bytes(data: &[u8]) -> BinResult<Self> {
+        let mut reader = Cursor::new(data);
+        let endian = detect_endian(&mut reader)?;
+        Self::read_options(&mut reader, endian, ValidationOptions::default())
+    }
+
+    pub fn to_bytes(&self, endian: Endian) -> BinResult<Vec<u8>> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_options(&mut writer, endian, ())?;
+        Ok(writer.into_inner())
+    }
+
+    // SHA-256 of the little endian payload, unlike a hash of the save file it
+    // doesn't change with the salt, signatures, or compression. Writing can
+    // fail for objects that don't match their types so this can too.
+    pub fn compute_checksum(&self) -> BinResult<[u8; 32]> {
+        Ok(Sha256::digest(self.to_bytes(Endian::Little)?).into())
+    }
+
+    // Gives IDENT values naming an ident that doesn't exist a new ident so
+    // they can be written
+    pub fn add_missing_idents(&mut self) {
+        fn collect<'a>(value: &'a InternalObjectDataValue, names: &mut Vec<&'a str>) {
+            match value {
+                InternalObjectDataValue::IDENT(IdentRef::Name(name)) => names.push(name),
+                InternalObjectDataValue::Array(values)
+                | InternalObjectDataValue::StaticStackArray(values)
+                | InternalObjectDataValue::DynamicContainerValues(values) => {
+                    values.iter().for_each(|value| collect(value, names))
+                }
+                InternalObjectDataValue::Struct { Base, members } => {
+                    if let Some(base) = Base {
+                        collect(base, names);
+                    }
+                    members.iter().for_each(|(_, value)| collect(value, names));
+                }
+                _ => {}
+            }
+        }
+
+        let mut names = Vec::new();
+        for object in &self.internal_objects.internal_object {
+            collect(&object.value, &mut names);
+        }
+        let mut next_id = self
+            .idents
+            .idents
+            .iter()
+            .map(|ident| ident.Ident + 1)
+            .max()
+            .unwrap_or_default();
+        let mut new_idents = Vec::new();
+        for name in names {
+            if self.ident_by_name(name).is_none()
+                && !new_idents.iter().any(|ident: &Ident| ident.Name == name)
+            {
+                new_idents.push(Ident {
+                    Ident: next_id,
+                    Name: name.to_owned(),
+                });
+                next_id += 1;
+            }
+        }
+        self.idents.idents.extend(new_idents);
+    }
+
+    pub fn has_edit_data(&self) -> bool {
+        !self.edit_data_stripped
+            || !self.edit_object_types.edit_object_types.is_empty()
+            || !self.edit_objects.edit_objects.is_empty()
+    }
+
+    // Removes the sections only the editor uses to get a runtime save
+    pub fn strip_edit_data(&mut self) {
+        self.edit_object_types.edit_object_types.clear();
+        self.edit_objects.edit_objects.clear();
+        self.edit_data_stripped = true;
+    }
+
+    fn ident_by_name(&self, name: &str) -> Option<u32> {
+        self.idents
+            .idents
+            .iter()
+            .find(|ident| ident.Name == name)
+            .map(|ident| ident.Ident)
+    }
+
+    // Every CString in the objects along with the object it is in, depth first
+    pub fn find_string_values(&self) -> Vec<(u32, &str)> {
+        fn collect<'a>(
+            id: u32,
+            value: &'a InternalObjectDataValue,
+            strings: &mut Vec<(u32, &'a str)>,
+        ) {
+            match value {
+                InternalObjectDataValue::CString(string) => strings.push((id, string)),
+                InternalObjectDataValue::Array(values)
+                | InternalObjectDataValue::StaticStackArray(values)
+                | InternalObjectDataValue::DynamicContainerValues(values) => {
+                    values.iter().for_each(|value| collect(id, value, strings))
+                }
+                InternalObjectDataValue::Struct { Base, members } => {
+                    if let Some(base) = Base {
+                        collect(id, base, strings);
+                    }
+                    members
+                        .iter()
+                        .for_each(|(_, value)| collect(id, value, strings));
+                }
+                _ => {}
+            }
+        }
+
+        let mut strings = Vec::new();
+        for object in &self.internal_objects.internal_object {
+            collect(object.Object, &object.value, &mut strings);
+        }
+        strings
+    }
+
+    // Every pointer and dynamic container entry in the internal objects, null
+    // pointers are skipped
+    pub fn references(&self) -> Vec<Reference> {
+        fn collect(path: String, value: &InternalObjectDataValue, targets: &mut Vec<Reference>) {
+            match value {
+                InternalObjectDataValue::Pointer(-1) => {}
+                InternalObjectDataValue::Pointer(pointer) => targets.push(Reference {
+                    object: 0,
+                    path: format!("{}/Pointer", path),
+                    target: *pointer as u32,
+                    nullable: true,
+                }),
+                InternalObjectDataValue::DynamicContainer(pointers) => {
+                    for (i, pointer) in pointers.iter().enumerate() {
+                        targets.push(Reference {
+                            object: 0,
+                            path: format!("{}/DynamicContainer/{}", path, i),
+                            target: *pointer,
+                            nullable: false,
+                        });
+                    }
+                }
+                InternalObjectDataValue::Array(values) => {
+                    collect_elements(format!("{}/Array", path), values, targets)
+                }
+                InternalObjectDataValue::StaticStackArray(values) => {
+                    collect_elements(format!("{}/StaticStackArray", path), values, targets)
+                }
+                InternalObjectDataValue::DynamicContainerValues(values) => {
+                    collect_elements(format!("{}/DynamicContainerValues", path), values, targets)
+                }
+                InternalObjectDataValue::Struct { Base, members } => {
+                    if let Some(base) = Base {
+                        collect(format!("{}/Struct/Base", path), base, targets);
+                    }
+                    for (name, value) in members.iter() {
+                        collect(
+                            format!("{}/Struct/members/{}", path, escape(name)),
+                            value,
+                            targets,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn collect_elements(
+            path: String,
+            values: &[InternalObjectDataValue],
+            targets: &mut Vec<Reference>,
+        ) {
+            for (i, value) in values.iter().enumerate() {
+                collect(format!("{}/{}", path, i), value, targets);
+            }
+        }
+
+        let mut targets = Vec::new();
+        for (i, object) in self.internal_objects.internal_object.iter().enumerate() {
+            let start = targets.len();
+            collect(
+                format!("/internal_objects/internal_object/{}/value", i),
+                &object.value,
+                &mut targets,
+            );
+            for reference in &mut targets[start..] {
+                reference.object = object.Object;
+            }
+        }
+        targets
+    }
+
+    // Every pointer and dynamic container entry that doesn't point to an
+    // internal or external object, then every internal object nothing points to
+    pub fn check_references(&self) -> Vec<ReferenceIssue> {
+        let targets = self.references();
+        let objects = self
+            .internal_objects
+            .internal_object
+            .iter()
+            .map(|object| object.Object)
+            .chain(
+                self.external_objects
+                    .external_objects
+                    .iter()
+                    .map(|object| object.Object),
+            )
+            .collect::<BTreeSet<_>>();
+        let mut issues = targets
+            .iter()
+            .filter(|reference| !objects.contains(&reference.target))
+            .map(|reference| ReferenceIssue::Dangling {
+                path: reference.path.clone(),
+                target: reference.target,
+            })
+            .collect::<Vec<_>>();
+        let referenced = targets
+            .into_iter()
+            .map(|reference| reference.target)
+            .collect::<BTreeSet<_>>();
+        issues.extend(
+            self.internal_objects
+                .internal_object
+                .iter()
+                .filter(|object| !referenced.contains(&object.Object))
+                .map(|object| ReferenceIssue::Orphan {
+                    object: object.Object,
+                }),
+        );
+        issues
+    }
+
+    // How many objects there are of each type, objects whose type isn't in
+    // the table are counted under the type ID
+    pub fn object_count_by_type(&self) -> HashMap<String, usize> {
+        let types = self.internal_types.by_id();
+        let mut counts = HashMap::new();
+        for object in &self.internal_objects.internal_object {
+            let name = types
+                .get(&object.Type)
+                .map(|data_type| data_type.Name.clone())
+                .unwrap_or_else(|| object.Type.to_string());
+            *counts.entry(name).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn object_by_name(&self, name: &str) -> Option<&InternalObject> {
+        let ident = self.ident_by_name(name)?;
+        self.internal_objects
+            .internal_object
+            .iter()
+            .find(|object| object.Object == ident)
+    }
+
+    pub fn object_by_name_mut(&mut self, name: &str) -> Option<&mut InternalObject> {
+        let ident = self.ident_by_name(name)?;
+        self.internal_objects
+            .internal_object
+            .iter_mut()
+            .find(|object| object.Object == ident)
+    }
+}
+
+// data/Synthetic.json, which the tests start from
+#[cfg(test)]
+pub(crate) fn synthetic() -> CTSEMeta {
+    serde_json::from_reader(std::io::BufReader::new(
+        std::fs::File::open("data/Synthetic.json").unwrap(),
+    ))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{BufReader, Cursor};
+
+    use binrw::{BinRead, BinWrite, Endian};
+
+    use crate::ctsemeta::{
+        CTSEMeta,
+        DataType,
+        DataTypeType,
+        DataTypeTypeStructMember,
+        ExternalObject,
+        ExternalObjects,
+        IdentRef,
+        Info,
+        InternalObject,
+        InternalObjectDataValue,
+        InternalObjectType,
+        Message,
+        Messages,
+        Metadata,
+        ReferenceIssue,
+        ResourceFile,
+        ResourceFiles,
+        synthetic,
+    };
+    use crate::helpers::{ParsedVec, ValidationOptions};
+
+    fn data_type(id: u32, name: &str, r#type: DataTypeType) -> DataType {
+        DataType {
+            DataType: id,
+            Name: name.to_owned(),
+            Format: 0,
+            Type: r#type,
+        }
+    }
+
+    // Synthetic with the types added and an object of each type and value,
+    // numbered from 2
+    fn with_objects(
+        types: impl IntoIterator<Item = DataType>,
+        objects: impl IntoIterator<Item = (u32, InternalObjectDataValue)>,
+    ) -> CTSEMeta {
+        let mut ctsemeta = synthetic();
+        ctsemeta.internal_types.types.extend(types);
+        for (object, (data_type, value)) in (2..).zip(objects) {
+            ctsemeta
+                .internal_object_types
+                .types
+                .push(InternalObjectType {
+                    Object: object,
+                    Type: data_type,
+                });
+            ctsemeta
+                .internal_objects
+                .internal_object
+                .push(InternalObject {
+                    Object: object,
+                    Type: data_type,
+                    value,
+                });
+        }
+        ctsemeta
+    }
+
+    // Where the last internal object ends, before EDOB and its count
+    fn objects_end(data: &[u8]) -> usize {
+        data.len() - b"EDOB".len() - 4 - b"METAEND ".len()
+    }
+
+    #[test]
+    fn parse_version_string() {
+        let metadata = Metadata {
+            version: 11,
+            version_string: Some("Talos 1.09 (Build 307622) for Windows".to_owned()),
+        };
+        let game_version = metadata.parse_version_string().unwrap();
+        assert_eq!(game_version.game, "Talos");
+        assert_eq!(game_version.version, semver::Version::new(1, 9, 0));
+        assert_eq!(game_version.version_text, "1.09");
+        assert_eq!(game_version.build, 307622);
+        assert_eq!(game_version.platform, "Windows");
+
+        let metadata = Metadata {
+            version: 11,
+            version_string: Some("not a version string".to_owned()),
+        };
+        assert!(metadata.parse_version_string().is_none());
+
+        let metadata = Metadata {
+            version: 1,
+            version_string: None,
+        };
+        assert!(metadata.parse_version_string().is_none());
+    }
+
+    #[test]
+    fn bump_version() {
+        let mut ctsemeta = synthetic();
+        let version = ctsemeta.metadata.version;
+        ctsemeta.metadata.version_string = Some("Talos 1.09 (Build 307622) for Windows".to_owned());
+        ctsemeta.metadata.bump_version();
+        assert_eq!(ctsemeta.metadata.version, version + 1);
+        assert!(ctsemeta.metadata.version_string.is_none());
+
+        // Still writes and reads back with the new version
+        let bytes = ctsemeta.to_bytes(Endian::Little).unwrap();
+        let read = CTSEMeta::from_bytes(&bytes).unwrap();
+        assert_eq!(read.metadata.version, version + 1);
+        assert_eq!(read.metadata.version_string.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn display_value() {
+        let ctsemeta = synthetic();
+        let value = &ctsemeta.internal_objects.internal_object[0].value;
+        assert_eq!(value.to_string(), "Struct, 4 members");
+        let InternalObjectDataValue::Struct { members, .. } = value else {
+            unreachable!()
+        };
+        assert_eq!(
+            members.get("m_strName").unwrap().to_string(),
+            "CString \"Cloud_1_01\""
+        );
+
+        let array =
+            InternalObjectDataValue::Array((0..6).map(InternalObjectDataValue::ULONG).collect());
+        assert_eq!(
+            array.to_string(),
+            "Array [ULONG 0, ULONG 1, ULONG 2, ULONG 3, ... 2 more]"
+        );
+        assert_eq!(
+            InternalObjectDataValue::Enum(vec![0xAB, 0x01]).to_string(),
+            "Enum ab01"
+        );
+    }
+
+    #[test]
+    fn double_round_trip() {
+        let ctsemeta = with_objects(
+            [data_type(
+                5,
+                "DOUBLE",
+                DataTypeType::Primitive { Bytes: 8, LBE: 0 },
+            )],
+            [(5, InternalObjectDataValue::DOUBLE(std::f64::consts::PI))],
+        );
+
+        for endian in [Endian::Little, Endian::Big] {
+            let data = ctsemeta.to_bytes(endian).unwrap();
+            let ctsemeta_again = CTSEMeta::from_bytes(&data).unwrap();
+            assert!(matches!(
+                ctsemeta_again.internal_objects.internal_object[2].value,
+                InternalObjectDataValue::DOUBLE(std::f64::consts::PI)
+            ));
+            assert_eq!(data, ctsemeta_again.to_bytes(endian).unwrap());
+        }
+    }
+
+    #[test]
+    fn multi_row_array_round_trip() {
+        let mut ctsemeta = with_objects(
+            [data_type(
+                5,
+                "ULONG[2][4]",
+                DataTypeType::Array {
+                    Of: 0,
+                    rows: 2,
+                    cols: 4,
+                },
+            )],
+            [(
+                5,
+                serde_json::from_value(serde_json::json!({ "Array": [
+                    { "Array": [{ "ULONG": 0 }, { "ULONG": 1 }, { "ULONG": 2 }, { "ULONG": 3 }] },
+                    { "Array": [{ "ULONG": 4 }, { "ULONG": 5 }, { "ULONG": 6 }, { "ULONG": 7 }] },
+                ]}))
+                .unwrap(),
+            )],
+        );
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        // The elements are stored row after row with nothing in between
+        let elements = (0..8u32).flat_map(u32::to_le_bytes).collect::<Vec<_>>();
+        assert!(data[..objects_end(&data)].ends_with(&elements));
+
+        let ctsemeta_again = CTSEMeta::from_bytes(&data).unwrap();
+        assert_eq!(
+            serde_json::to_value(&ctsemeta_again.internal_objects.internal_object[2].value)
+                .unwrap(),
+            serde_json::to_value(&ctsemeta.internal_objects.internal_object[2].value).unwrap()
+        );
+
+        // A row too short is caught before writing
+        let InternalObjectDataValue::Array(rows) =
+            &mut ctsemeta.internal_objects.internal_object[2].value
+        else {
+            unreachable!()
+        };
+        let InternalObjectDataValue::Array(row) = &mut rows[1] else {
+            unreachable!()
+        };
+        row.pop();
+        assert!(ctsemeta.to_bytes(Endian::Little).is_err());
+    }
+
+    #[test]
+    fn csynced_round_trip() {
+        let csynced = |name, members, value| {
+            with_objects(
+                [
+                    data_type(
+                        5,
+                        name,
+                        DataTypeType::Struct {
+                            Base: -1,
+                            members: ParsedVec::from(members),
+                        },
+                    ),
+                    data_type(6, "UWORD", DataTypeType::Primitive { Bytes: 2, LBE: 0 }),
+                ],
+                [(5, value)],
+            )
+        };
+
+        // Without members it is just the primitive in the name
+        let ctsemeta = csynced(
+            "CSyncedFLOAT",
+            Vec::new(),
+            InternalObjectDataValue::FLOAT(1.5),
+        );
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        assert!(data[..objects_end(&data)].ends_with(&1.5f32.to_le_bytes()));
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        assert!(matches!(
+            ctsemeta.internal_objects.internal_object[2].value,
+            InternalObjectDataValue::FLOAT(1.5)
+        ));
+
+        let ctsemeta = csynced(
+            "CSyncedUWORD",
+            Vec::new(),
+            InternalObjectDataValue::UWORD(7),
+        );
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        assert!(data[..objects_end(&data)].ends_with(&7u16.to_le_bytes()));
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        assert!(matches!(
+            ctsemeta.internal_objects.internal_object[2].value,
+            InternalObjectDataValue::UWORD(7)
+        ));
+
+        // A primitive that isn't in the types can't be read or written
+        let mut unknown = data.clone();
+        let name = unknown
+            .windows(b"CSyncedUWORD".len())
+            .position(|window| window == b"CSyncedUWORD")
+            .unwrap();
+        unknown[name..name + b"CSyncedSWORD".len()].copy_from_slice(b"CSyncedSWORD");
+        assert!(CTSEMeta::from_bytes(&unknown).is_err());
+        let ctsemeta = csynced(
+            "CSyncedSWORD",
+            Vec::new(),
+            InternalObjectDataValue::SWORD(7),
+        );
+        assert!(ctsemeta.to_bytes(Endian::Little).is_err());
+
+        // CSyncedSLONG as extracted before is still written
+        let ctsemeta = csynced(
+            "CSyncedSLONG",
+            Vec::new(),
+            InternalObjectDataValue::CSyncedSLONG(-2),
+        );
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        assert!(data[..objects_end(&data)].ends_with(&(-2i32).to_le_bytes()));
+
+        // With members it is a normal struct
+        let mut ctsemeta = csynced(
+            "CSyncedFLOAT",
+            vec![DataTypeTypeStructMember { ID: 2, Type: 2 }],
+            serde_json::from_value(serde_json::json!({ "Struct": {
+                "Base": null,
+                "members": { "m_fTime": { "FLOAT": 1.5 } }
+            }}))
+            .unwrap(),
+        );
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        let ctsemeta_again = CTSEMeta::from_bytes(&data).unwrap();
+        assert!(matches!(
+            ctsemeta_again.internal_objects.internal_object[2].value,
+            InternalObjectDataValue::Struct { .. }
+        ));
+
+        // The bare primitive doesn't fit the struct definition
+        ctsemeta.internal_objects.internal_object[2].value = InternalObjectDataValue::FLOAT(1.5);
+        assert!(ctsemeta.to_bytes(Endian::Little).is_err());
+    }
+
+    #[test]
+    fn recursive_type() {
+        let ctsemeta = with_objects(
+            [data_type(5, "CLoop", DataTypeType::TypeDef { For: 5 })],
+            [(0, InternalObjectDataValue::ULONG(0))],
+        );
+        let mut data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        assert!(CTSEMeta::from_bytes(&data).is_ok());
+
+        // Writing refuses the loop so point the object at it afterwards
+        let objects_end = objects_end(&data);
+        data[objects_end - 8..objects_end - 4].copy_from_slice(&5u32.to_le_bytes());
+        assert!(CTSEMeta::from_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn cstring_raw_round_trip() {
+        let mut ctsemeta = synthetic();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        // "Renée" in Windows-1252
+        *members.get_mut("m_strName").unwrap() =
+            InternalObjectDataValue::CStringRaw(b"Ren\xE9e".to_vec());
+
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let data = writer.into_inner();
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+
+        // The JSON shows the bytes rather than a mangled string
+        let json = serde_json::to_value(&ctsemeta).unwrap();
+        assert_eq!(
+            json["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"]["m_strName"],
+            serde_json::json!({ "CStringRaw": [0x52, 0x65, 0x6E, 0xE9, 0x65] })
+        );
+
+        let ctsemeta: CTSEMeta = serde_json::from_value(json).unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(data, writer.into_inner());
+    }
+
+    #[test]
+    fn no_validate_keeps_partial_objects() {
+        let mut writer = Cursor::new(Vec::new());
+        synthetic()
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let mut data = writer.into_inner();
+        // Give the second object's name a length far past the limit
+        let name = data.windows(10).position(|x| x == b"Cloud_1_02").unwrap();
+        data[name - 4..name].copy_from_slice(&0x100000u32.to_le_bytes());
+
+        let error = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("bytes length 1048576 exceeds"));
+
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::NONE,
+        )
+        .unwrap();
+        let objects = &ctsemeta.internal_objects.internal_object;
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].Object, 0);
+        assert!(ctsemeta.edit_objects.edit_objects.is_empty());
+    }
+
+    #[test]
+    fn non_finite_float_round_trip() {
+        let signaling_nan = f32::from_bits(0x7FA00000);
+        for (float, json) in [
+            (1.5, serde_json::json!(1.5)),
+            (f32::NAN, serde_json::json!("NaN")),
+            (f32::INFINITY, serde_json::json!("Infinity")),
+            (f32::NEG_INFINITY, serde_json::json!("-Infinity")),
+            (signaling_nan, serde_json::json!("0x7FA00000")),
+        ] {
+            let mut ctsemeta = synthetic();
+            let InternalObjectDataValue::Struct { members, .. } =
+                &mut ctsemeta.internal_objects.internal_object[0].value
+            else {
+                unreachable!()
+            };
+            *members.get_mut("m_fTime").unwrap() = InternalObjectDataValue::FLOAT(float);
+
+            let mut writer = Cursor::new(Vec::new());
+            ctsemeta
+                .write_options(&mut writer, Endian::Little, ())
+                .unwrap();
+            let ctsemeta = CTSEMeta::from_bytes(&writer.into_inner()).unwrap();
+            let text = serde_json::to_string(&ctsemeta).unwrap();
+            let value = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+            assert_eq!(
+                value["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"]["m_fTime"]
+                    ["FLOAT"],
+                json
+            );
+
+            let ctsemeta: CTSEMeta = serde_json::from_str(&text).unwrap();
+            let InternalObjectDataValue::Struct { members, .. } =
+                &ctsemeta.internal_objects.internal_object[0].value
+            else {
+                unreachable!()
+            };
+            let InternalObjectDataValue::FLOAT(parsed) = members.get("m_fTime").unwrap() else {
+                unreachable!()
+            };
+            assert_eq!(parsed.to_bits(), float.to_bits());
+        }
+
+        let double = serde_json::from_value::<InternalObjectDataValue>(
+            serde_json::json!({ "DOUBLE": "0x7FF4000000000000" }),
+        )
+        .unwrap();
+        let InternalObjectDataValue::DOUBLE(double) = double else {
+            unreachable!()
+        };
+        assert_eq!(double.to_bits(), 0x7FF4000000000000);
+        assert!(
+            serde_json::from_value::<InternalObjectDataValue>(
+                serde_json::json!({ "FLOAT": "nope" })
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn unknown_type_round_trip() {
+        let ctsemeta: CTSEMeta = serde_json::from_reader(BufReader::new(
+            File::open("data/SyntheticUnknownType.json").unwrap(),
+        ))
+        .unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let mut data = writer.into_inner();
+
+        // One unknown type is followed by a DTTY, the other by EXOB
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        let unknown = ctsemeta
+            .internal_types
+            .types
+            .iter()
+            .filter_map(|data_type| match &data_type.Type {
+                DataTypeType::Unknown { tag, data } => Some((*tag, data.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(unknown, [(6, vec![0, 0, 0, 0]), (12, vec![4, 0, 0, 0])]);
+        let old_names: DataTypeType =
+            serde_json::from_value(serde_json::json!({ "Unknown": { "magic": 6, "raw": [0] } }))
+                .unwrap();
+        assert!(matches!(old_names, DataTypeType::Unknown { tag: 6, data } if data == [0]));
+        assert_eq!(ctsemeta.internal_objects.internal_object.len(), 2);
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(data, writer.into_inner());
+
+        // Make the first object use the unknown type
+        let objs = data.windows(4).position(|x| x == b"OBJS").unwrap();
+        data[objs + 12..objs + 16].copy_from_slice(&5u32.to_le_bytes());
+        let error = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(
+            error.contains("type CStaticArray<ULONG> has unknown kind 6"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn known_type_kinds_are_not_unknown() {
+        // A Struct type followed by another type, so reading it as Unknown
+        // would succeed
+        let struct_type = |stmb: &[u8], members: u32| {
+            let mut data = b"DTTY".to_vec();
+            data.extend(5u32.to_le_bytes());
+            data.extend(6u32.to_le_bytes());
+            data.extend(b"CThing");
+            data.extend(0u32.to_le_bytes());
+            data.extend(5u32.to_le_bytes());
+            data.extend((-1i32).to_le_bytes());
+            data.extend(stmb);
+            data.extend(members.to_le_bytes());
+            for id in 0..members {
+                data.extend(id.to_le_bytes());
+                data.extend(0u32.to_le_bytes());
+            }
+            data.extend(b"DTTY");
+            data
+        };
+        let read = |data: &[u8], max_vec_count| {
+            DataType::read_options(
+                &mut Cursor::new(data),
+                Endian::Little,
+                ValidationOptions {
+                    max_vec_count,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert!(matches!(
+            read(&struct_type(b"STMB", 2), 2).unwrap().Type,
+            DataTypeType::Struct { .. }
+        ));
+        // More members than the limit allows
+        assert!(read(&struct_type(b"STMB", 3), 2).is_err());
+        // Cut off where the members should start
+        assert!(read(&struct_type(b"DTTY", 0), 2).is_err());
+    }
+
+    #[test]
+    fn dynamic_container_round_trip() {
+        // Of objects and of ULONG values
+        let ctsemeta = with_objects(
+            [(5, 4), (6, 0)]
+                .map(|(id, of)| data_type(id, "CDynamicContainer", DataTypeType::DynamicContainer { Of: of })),
+            [
+                (5, serde_json::json!({ "DynamicContainer": [0, 1] })),
+                (
+                    6,
+                    serde_json::json!({ "DynamicContainerValues": [{ "ULONG": 7 }, { "ULONG": 9 }] }),
+                ),
+            ]
+            .map(|(data_type, value)| (data_type, serde_json::from_value(value).unwrap())),
+        );
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        // Both are the same on disk
+        let objects_end = objects_end(&data);
+        let container = |values: [u32; 2]| {
+            let mut container = b"DCON".to_vec();
+            container.extend_from_slice(&2u32.to_le_bytes());
+            container.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            container
+        };
+        assert!(data[..objects_end].ends_with(&container([7, 9])));
+        assert!(data[..objects_end - container([7, 9]).len() - 8].ends_with(&container([0, 1])));
+
+        let ctsemeta_again = CTSEMeta::from_bytes(&data).unwrap();
+        for i in [2, 3] {
+            assert_eq!(
+                serde_json::to_value(&ctsemeta_again.internal_objects.internal_object[i].value)
+                    .unwrap(),
+                serde_json::to_value(&ctsemeta.internal_objects.internal_object[i].value).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn bool_round_trip() {
+        let ctsemeta = with_objects(
+            [data_type(
+                5,
+                "BOOL",
+                DataTypeType::Primitive { Bytes: 1, LBE: 0 },
+            )],
+            [true, false].map(|value| (5, InternalObjectDataValue::Bool(value))),
+        );
+
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        // Each object is its ID, its type, then the value
+        assert!(
+            data[..objects_end(&data)]
+                .ends_with(&[2, 0, 0, 0, 5, 0, 0, 0, 0x01, 3, 0, 0, 0, 5, 0, 0, 0, 0x00])
+        );
+
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        let values = ctsemeta.internal_objects.internal_object[2..]
+            .iter()
+            .map(|object| serde_json::to_value(&object.value).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                serde_json::json!({ "Bool": true }),
+                serde_json::json!({ "Bool": false })
+            ]
+        );
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        let json = std::fs::read_to_string("data/SyntheticPrimitives.json").unwrap();
+        let ctsemeta: CTSEMeta = serde_json::from_str(&json).unwrap();
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let data = writer.into_inner();
+        // Each object is its ID, its type, then the value, the BOOLs and TICK
+        // take the width of their type
+        let objects = [
+            &[2, 0, 0, 0, 5, 0, 0, 0, 0xFF, 0xFF][..],
+            &[3, 0, 0, 0, 6, 0, 0, 0, 0x00, 0x80],
+            &[4, 0, 0, 0, 7, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF],
+            &[5, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0],
+            &[6, 0, 0, 0, 8, 0, 0, 0, 2, 0, 0, 0],
+            &[7, 0, 0, 0, 9, 0, 0, 0, 0, 0],
+            &[8, 0, 0, 0, 10, 0, 0, 0],
+            &12.5f64.to_le_bytes(),
+            &[9, 0, 0, 0, 11, 0, 0, 0],
+            &0.25f32.to_le_bytes(),
+        ]
+        .concat();
+        assert!(data[..objects_end(&data)].ends_with(&objects));
+
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&ctsemeta.internal_objects).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()["internal_objects"]
+        );
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(data, writer.into_inner());
+    }
+
+    #[test]
+    fn enum_widths_round_trip() {
+        let json = std::fs::read_to_string("data/SyntheticEnums.json").unwrap();
+        let ctsemeta: CTSEMeta = serde_json::from_str(&json).unwrap();
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+        for endian in [Endian::Little, Endian::Big] {
+            let mut writer = Cursor::new(Vec::new());
+            ctsemeta.write_options(&mut writer, endian, ()).unwrap();
+            let data = writer.into_inner();
+            // Only the odd width is written as it is in the JSON
+            let values: [&[u8]; 5] = match endian {
+                Endian::Little => [
+                    &[200],
+                    &[0x34, 0x12],
+                    &[0xFE, 0xFF, 0xFF, 0xFF],
+                    &[0x10, 0x32, 0x54, 0x76, 0x98, 0xBA, 0xDC, 0xFE],
+                    &[1, 2, 3],
+                ],
+                Endian::Big => [
+                    &[200],
+                    &[0x12, 0x34],
+                    &[0xFF, 0xFF, 0xFF, 0xFE],
+                    &[0xFE, 0xDC, 0xBA, 0x98, 0x76, 0x54, 0x32, 0x10],
+                    &[1, 2, 3],
+                ],
+            };
+            let u32_bytes = |x: u32| match endian {
+                Endian::Little => x.to_le_bytes(),
+                Endian::Big => x.to_be_bytes(),
+            };
+            let objects = (2u32..)
+                .zip(values)
+                .flat_map(|(object, value)| {
+                    [&u32_bytes(object)[..], &u32_bytes(object + 3), value].concat()
+                })
+                .collect::<Vec<_>>();
+            assert!(data[..objects_end(&data)].ends_with(&objects));
+
+            let ctsemeta = CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                endian,
+                ValidationOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                serde_json::to_value(&ctsemeta.internal_objects).unwrap(),
+                serde_json::from_str::<serde_json::Value>(&json).unwrap()["internal_objects"]
+            );
+        }
+    }
+
+    #[test]
+    fn vec3d_round_trip() {
+        // The struct form, the array form, and an Array from before Vec3D
+        let values = [
+            serde_json::json!({ "Vec3D": { "x": 1.0, "y": -2.5, "z": 0.0 } }),
+            serde_json::json!({ "Vec3D": [1.0, -2.5, 0.0] }),
+            serde_json::json!({ "Array": [{ "FLOAT": 1.0 }, { "FLOAT": -2.5 }, { "FLOAT": 0.0 }] }),
+        ];
+        let ctsemeta = with_objects(
+            [data_type(
+                5,
+                "FLOAT3D",
+                DataTypeType::Array {
+                    Of: 2,
+                    rows: 1,
+                    cols: 3,
+                },
+            )],
+            values.map(|value| (5, serde_json::from_value(value).unwrap())),
+        );
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        let objects = (2u32..5)
+            .flat_map(|object| {
+                [
+                    &object.to_le_bytes()[..],
+                    &5u32.to_le_bytes(),
+                    &1.0f32.to_le_bytes(),
+                    &(-2.5f32).to_le_bytes(),
+                    &0.0f32.to_le_bytes(),
+                ]
+                .concat()
+            })
+            .collect::<Vec<_>>();
+        assert!(data[..objects_end(&data)].ends_with(&objects));
+
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        for object in &ctsemeta.internal_objects.internal_object[2..] {
+            assert_eq!(
+                serde_json::to_value(&object.value).unwrap(),
+                serde_json::json!({ "Vec3D": { "x": 1.0, "y": -2.5, "z": 0.0 } })
+            );
+        }
+    }
+
+    #[test]
+    fn placement3d_round_trip() {
+        // Negative zero, a denormal, and a NaN with a payload among them
+        let bits = [
+            0x80000000u32,
+            0x00000001,
+            0x7FA00000,
+            0x3F800000,
+            0x7149F2CA,
+            0xC49A522B,
+            0x00800000,
+        ];
+        let [qx, qy, qz, qw, x, y, z] = bits.map(f32::from_bits);
+        let placement = InternalObjectDataValue::Placement3D {
+            qx,
+            qy,
+            qz,
+            qw,
+            x,
+            y,
+            z,
+        };
+        let ctsemeta = with_objects(
+            [
+                data_type(
+                    5,
+                    "QVECT",
+                    DataTypeType::Array {
+                        Of: 2,
+                        rows: 1,
+                        cols: 7,
+                    },
+                ),
+                data_type(
+                    6,
+                    "CPlacement3D",
+                    DataTypeType::Primitive { Bytes: 28, LBE: 0 },
+                ),
+            ],
+            [(5, placement.clone()), (6, placement)],
+        );
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+
+        // Through the JSON and back to the binary format
+        let json = serde_json::to_string(&ctsemeta).unwrap();
+        let ctsemeta: CTSEMeta = serde_json::from_str(&json).unwrap();
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        let objects = [(2u32, 5u32), (3, 6)]
+            .iter()
+            .flat_map(|(object, type_id)| {
+                [object.to_le_bytes(), type_id.to_le_bytes()]
+                    .into_iter()
+                    .chain(bits.map(u32::to_le_bytes))
+                    .flatten()
+            })
+            .collect::<Vec<_>>();
+        assert!(data[..objects_end(&data)].ends_with(&objects));
+
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        for object in &ctsemeta.internal_objects.internal_object[2..] {
+            let InternalObjectDataValue::Placement3D {
+                qx,
+                qy,
+                qz,
+                qw,
+                x,
+                y,
+                z,
+            } = object.value
+            else {
+                panic!("not a Placement3D: {}", object.value);
+            };
+            assert_eq!([qx, qy, qz, qw, x, y, z].map(f32::to_bits), bits);
+        }
+
+        // The array form
+        let value: InternalObjectDataValue = serde_json::from_value(
+            serde_json::json!({ "Placement3D": [0.0, 0.0, 0.0, 1.0, 10.0, 20.0, 30.0] }),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&value).unwrap(),
+            serde_json::json!({ "Placement3D": {
+                "qx": 0.0, "qy": 0.0, "qz": 0.0, "qw": 1.0, "x": 10.0, "y": 20.0, "z": 30.0
+            } })
+        );
+    }
+
+    #[test]
+    fn matrix4x4_round_trip() {
+        let identity = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        // 30 degrees around Z, then moved
+        let (sin, cos) = 30f32.to_radians().sin_cos();
+        let rotation = [
+            [cos, -sin, 0.0, 10.0],
+            [sin, cos, 0.0, -20.0],
+            [0.0, 0.0, 1.0, 0.5],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        // The rows, the flat form, and an Array from before Matrix4x4
+        let values = [
+            serde_json::json!({ "Matrix4x4": identity }),
+            serde_json::json!({ "Matrix4x4": rotation }),
+            serde_json::json!({ "Matrix4x4": rotation.as_flattened() }),
+            serde_json::json!({ "Array": rotation
+                .as_flattened()
+                .iter()
+                .map(|value| serde_json::json!({ "FLOAT": value }))
+                .collect::<Vec<_>>() }),
+        ];
+        let ctsemeta = with_objects(
+            [data_type(
+                5,
+                "MATRIX",
+                DataTypeType::Array {
+                    Of: 2,
+                    rows: 1,
+                    cols: 16,
+                },
+            )],
+            values.map(|value| (5, serde_json::from_value(value).unwrap())),
+        );
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        // 16 FLOATs in a row, the same as the Array they replace
+        let objects = [identity, rotation, rotation, rotation]
+            .iter()
+            .zip(2u32..)
+            .flat_map(|(matrix, object)| {
+                [object.to_le_bytes(), 5u32.to_le_bytes()]
+                    .into_iter()
+                    .chain(
+                        matrix
+                            .as_flattened()
+                            .iter()
+                            .map(|value| value.to_le_bytes()),
+                    )
+                    .flatten()
+            })
+            .collect::<Vec<_>>();
+        assert!(data[..objects_end(&data)].ends_with(&objects));
+
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        let objects = &ctsemeta.internal_objects.internal_object;
+        assert_eq!(
+            serde_json::to_value(&objects[2].value).unwrap(),
+            serde_json::json!({ "Matrix4x4": identity })
+        );
+        for object in &objects[3..] {
+            let InternalObjectDataValue::Matrix4x4(matrix) = object.value else {
+                panic!("not a Matrix4x4: {}", object.value);
+            };
+            assert_eq!(matrix, *rotation.as_flattened());
+            assert_eq!(
+                serde_json::to_value(&object.value).unwrap(),
+                serde_json::json!({ "Matrix4x4": rotation })
+            );
+        }
+    }
+
+    #[test]
+    fn named_members_round_trip() {
+        let ctsemeta = synthetic();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let data = writer.into_inner();
+
+        // Members are keyed by ident name when read back
+        let mut json = serde_json::to_value(
+            CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                ValidationOptions::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let members =
+            &mut json["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"];
+        assert_eq!(members["m_ulCount"], serde_json::json!({ "ULONG": 42 }));
+
+        // Key order and numeric IDs in place of names don't change the binary
+        let members = members.as_object_mut().unwrap();
+        let name = members.remove("m_strName").unwrap();
+        let count = members.remove("m_ulCount").unwrap();
+        members.insert("1".to_owned(), count);
+        members.insert("m_strName".to_owned(), name);
+        let ctsemeta: CTSEMeta = serde_json::from_value(json.clone()).unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(data, writer.into_inner());
+
+        // Missing members can't be written
+        json["internal_objects"]["internal_object"][0]["value"]["Struct"]["members"]
+            .as_object_mut()
+            .unwrap()
+            .remove("m_fTime");
+        let ctsemeta: CTSEMeta = serde_json::from_value(json).unwrap();
+        let error = ctsemeta
+            .write_options(&mut Cursor::new(Vec::new()), Endian::Little, ())
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("struct CTalosProgress is missing member m_fTime")
+        );
+    }
+
+    #[test]
+    fn object_by_name() {
+        let mut ctsemeta = synthetic();
+        assert_eq!(ctsemeta.object_by_name("m_ulCount").unwrap().Object, 1);
+        assert!(ctsemeta.object_by_name("m_strMissing").is_none());
+
+        ctsemeta.object_by_name_mut("m_strName").unwrap().value = InternalObjectDataValue::ULONG(1);
+        assert!(matches!(
+            ctsemeta.internal_objects.internal_object[0].value,
+            InternalObjectDataValue::ULONG(1)
+        ));
+    }
+
+    #[test]
+    fn resource_files() {
+        let entries = [
+            ResourceFile {
+                File: 0,
+                Flags: 0,
+                FileName: "Content/Talos/Levels/Cloud_1_01.wld".to_owned(),
+            },
+            ResourceFile {
+                File: 1,
+                Flags: 2,
+                FileName: "Content/Talos/All.dat".to_owned(),
+            },
+        ];
+        let mut data = b"RFIL".to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for entry in &entries {
+            data.extend_from_slice(&entry.File.to_le_bytes());
+            data.extend_from_slice(&entry.Flags.to_le_bytes());
+            data.extend_from_slice(&(entry.FileName.len() as u32).to_le_bytes());
+            data.extend_from_slice(entry.FileName.as_bytes());
+        }
+
+        let resource_files = ResourceFiles::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            (11, ValidationOptions::default()),
+        )
+        .unwrap();
+        assert_eq!(resource_files.resource_files, entries);
+        let mut writer = Cursor::new(Vec::new());
+        resource_files
+            .write_options(&mut writer, Endian::Little, (11,))
+            .unwrap();
+        assert_eq!(writer.into_inner(), data);
+
+        // Through JSON as part of a whole save
+        let mut ctsemeta = synthetic();
+        ctsemeta.resource_files = resource_files;
+        let json = serde_json::to_value(&ctsemeta).unwrap();
+        assert_eq!(
+            json["resource_files"]["resource_files"][1]["FileName"],
+            "Content/Talos/All.dat"
+        );
+        let ctsemeta: CTSEMeta = serde_json::from_value(json).unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(ctsemeta.resource_files.resource_files, entries);
+
+        // No flags before version 7
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .resource_files
+            .write_options(&mut writer, Endian::Little, (6,))
+            .unwrap();
+        let data_v6 = writer.into_inner();
+        assert_eq!(data_v6.len(), data.len() - 2 * 4);
+        let resource_files = ResourceFiles::read_options(
+            &mut Cursor::new(&data_v6),
+            Endian::Little,
+            (6, ValidationOptions::default()),
+        )
+        .unwrap();
+        assert_eq!(resource_files.resource_files[1].Flags, 0);
+        assert_eq!(
+            resource_files.resource_files[1].FileName,
+            "Content/Talos/All.dat"
+        );
+    }
+
+    #[test]
+    fn write_type_check() {
+        let mut ctsemeta = synthetic();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[1].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_strName").unwrap() = InternalObjectDataValue::IDENT(IdentRef::Id(0));
+
+        let error = ctsemeta
+            .write_options(&mut Cursor::new(Vec::new()), Endian::Little, ())
+            .unwrap_err();
+        assert!(error.to_string().contains(
+            "object 1 at value/Struct/members/m_strName: found IDENT but type CString needs \
+             CString"
+        ));
+    }
+
+    #[test]
+    fn ident_round_trip() {
+        let ctsemeta = with_objects(
+            [data_type(
+                5,
+                "IDENT",
+                DataTypeType::Primitive { Bytes: 4, LBE: 0 },
+            )],
+            [(5, InternalObjectDataValue::IDENT(IdentRef::Id(3)))],
+        );
+
+        // Read back as the name
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        let ctsemeta = CTSEMeta::from_bytes(&data).unwrap();
+        let mut json = serde_json::to_value(&ctsemeta).unwrap();
+        let value = &mut json["internal_objects"]["internal_object"][2]["value"];
+        assert_eq!(value["IDENT"], "m_pNext");
+
+        // A name that isn't an ident yet gets a new one
+        value["IDENT"] = "m_strRenamed".into();
+        let ctsemeta: CTSEMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(ctsemeta.idents.idents.len(), 5);
+        let ctsemeta = CTSEMeta::from_bytes(&ctsemeta.to_bytes(Endian::Little).unwrap()).unwrap();
+        assert_eq!(ctsemeta.idents.idents[4].Ident, 4);
+        assert!(matches!(
+            &ctsemeta.internal_objects.internal_object[2].value,
+            InternalObjectDataValue::IDENT(IdentRef::Name(name)) if name == "m_strRenamed"
+        ));
+    }
+
+    #[test]
+    fn strip_edit_data() {
+        let mut ctsemeta = synthetic();
+        assert!(!ctsemeta.has_edit_data());
+
+        ctsemeta.edit_data_stripped = false;
+        assert!(ctsemeta.has_edit_data());
+        ctsemeta.strip_edit_data();
+        assert!(!ctsemeta.has_edit_data());
+
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert!(ctsemeta.edit_data_stripped);
+    }
+
+    #[test]
+    fn find_string_values() {
+        let mut ctsemeta = synthetic();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[1].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_strName").unwrap() =
+            InternalObjectDataValue::CString("Checkpoint_B".to_owned());
+
+        let strings = ctsemeta.find_string_values();
+        assert_eq!(strings.len(), 2);
+        assert_eq!(strings[1], (1, "Checkpoint_B"));
+    }
+
+    #[test]
+    fn check_references() {
+        let mut ctsemeta = synthetic();
+        // Object 0 points to 1 and nothing points to 0
+        assert_eq!(
+            ctsemeta.check_references(),
+            [ReferenceIssue::Orphan { object: 0 }]
+        );
+
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[1].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_pNext").unwrap() = InternalObjectDataValue::Pointer(7);
+        assert_eq!(
+            ctsemeta.check_references(),
+            [
+                ReferenceIssue::Dangling {
+                    path:
+                        "/internal_objects/internal_object/1/value/Struct/members/m_pNext/Pointer"
+                            .to_owned(),
+                    target: 7
+                },
+                ReferenceIssue::Orphan { object: 0 }
+            ]
+        );
+    }
+
+    #[test]
+    fn compute_checksum() {
+        let ctsemeta = synthetic();
+        let checksum = ctsemeta.compute_checksum().unwrap();
+
+        // The same objects read from a big endian payload hash the same
+        let big = CTSEMeta::from_bytes(&ctsemeta.to_bytes(Endian::Big).unwrap()).unwrap();
+        assert_eq!(big.compute_checksum().unwrap(), checksum);
+
+        let mut edited = synthetic();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut edited.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_ulCount").unwrap() = InternalObjectDataValue::ULONG(43);
+        assert_ne!(edited.compute_checksum().unwrap(), checksum);
+    }
+
+    #[test]
+    fn object_count_by_type() {
+        let mut ctsemeta = synthetic();
+        let counts = ctsemeta.object_count_by_type();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts["CTalosProgress"], 2);
+
+        ctsemeta.internal_objects.internal_object[1].Type = 99;
+        let counts = ctsemeta.object_count_by_type();
+        assert_eq!(counts["CTalosProgress"], 1);
+        assert_eq!(counts["99"], 1);
+    }
+
+    #[test]
+    fn edit_data_not_stripped_round_trip() {
+        // Editor saves without any edit objects still keep their flag
+        let mut ctsemeta = synthetic();
+        ctsemeta.edit_data_stripped = false;
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let data = writer.into_inner();
+        let info = data
+            .windows(4)
+            .position(|window| window == b"INFO")
+            .unwrap();
+        assert_eq!(data[info + 4..info + 8], 0u32.to_le_bytes());
+
+        let json = serde_json::to_string(
+            &CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                ValidationOptions::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let ctsemeta: CTSEMeta = serde_json::from_str(&json).unwrap();
+        assert!(!ctsemeta.edit_data_stripped);
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(data, writer.into_inner());
+
+        // Before version 8 there is no flag at all
+        let info = Info::new(
+            7,
+            false,
+            &ctsemeta.edit_object_types,
+            &ctsemeta.edit_objects,
+            &ctsemeta.resource_files,
+            &ctsemeta.idents,
+            &ctsemeta.external_types,
+            &ctsemeta.internal_types,
+            &ctsemeta.external_objects,
+            &ctsemeta.internal_objects,
+        );
+        let mut writer = Cursor::new(Vec::new());
+        info.write_options(&mut writer, Endian::Little, ()).unwrap();
+        assert_eq!(writer.get_ref().len(), b"INFO".len() + 4 * 4);
+        let info = Info::read_options(&mut Cursor::new(writer.into_inner()), Endian::Little, (7,))
+            .unwrap();
+        assert_eq!(info.EditDataStripped, None);
+    }
+
+    #[test]
+    fn info_counts() {
+        let mut writer = Cursor::new(Vec::new());
+        synthetic()
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let mut data = writer.into_inner();
+        let info = data
+            .windows(4)
+            .position(|window| window == b"INFO")
+            .unwrap();
+        data[info + 12..info + 16].copy_from_slice(&9u32.to_le_bytes());
+        data[info + 20..info + 24].copy_from_slice(&1u32.to_le_bytes());
+
+        // Only a warning normally since INFO is recalculated when writing
+        CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        let Err(error) = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions {
+                strict: true,
+                ..ValidationOptions::default()
+            },
+        ) else {
+            panic!("strict read should fail on the wrong counts")
+        };
+        let binrw::Error::AssertFail { message, .. } = error.root_cause() else {
+            panic!("{}", error)
+        };
+        assert_eq!(
+            *message,
+            format!(
+                "INFO says 9 idents but there are {}, INFO says 1 objects but there are 2",
+                synthetic().idents.idents.len()
+            )
+        );
+    }
+
+    #[test]
+    fn corrupt_string_length() {
+        let mut writer = Cursor::new(Vec::new());
+        synthetic()
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let mut data = writer.into_inner();
+        // The name length of the first ident, under the limit but far more
+        // than what is left
+        let idnt = data
+            .windows(4)
+            .position(|window| window == b"IDNT")
+            .unwrap();
+        data[idnt + 12..idnt + 16].copy_from_slice(&0x8000u32.to_le_bytes());
+
+        let error = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::NONE,
+        )
+        .err()
+        .unwrap();
+        let message = error.root_cause().to_string();
+        assert!(
+            message.contains(&format!(
+                "string length 32768 at offset {:#x} exceeds remaining {} bytes",
+                idnt + 12,
+                data.len() - idnt - 16
+            )),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut writer = Cursor::new(Vec::new());
+        synthetic()
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let end = writer.get_ref().len();
+
+        for trailing in [vec![0xAA], vec![0x55; 4096]] {
+            let mut data = writer.get_ref().clone();
+            data.extend_from_slice(&trailing);
+
+            let ctsemeta = CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                ValidationOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(ctsemeta.trailing, trailing);
+            let json = serde_json::to_string(&ctsemeta).unwrap();
+            let ctsemeta: CTSEMeta = serde_json::from_str(&json).unwrap();
+            let mut rewritten = Cursor::new(Vec::new());
+            ctsemeta
+                .write_options(&mut rewritten, Endian::Little, ())
+                .unwrap();
+            assert_eq!(rewritten.into_inner(), data);
+
+            let Err(error) = CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                ValidationOptions {
+                    strict: true,
+                    ..ValidationOptions::default()
+                },
+            ) else {
+                panic!("strict read should fail on trailing bytes")
+            };
+            let binrw::Error::AssertFail { pos, message } = error.root_cause() else {
+                panic!("{}", error)
+            };
+            assert_eq!(*pos, end as u64);
+            assert_eq!(
+                *message,
+                format!("{} bytes after METAEND at {:#x}", trailing.len(), end)
+            );
+        }
+
+        // Nothing extra shows up in the JSON of a normal save
+        let json = serde_json::to_value(synthetic()).unwrap();
+        assert!(json.get("trailing").is_none());
+    }
+
+    #[test]
+    fn edit_data_round_trip() {
+        for (path, stripped) in [
+            ("data/Synthetic.json", 1),
+            ("data/SyntheticEditData.json", 0),
+        ] {
+            let ctsemeta: CTSEMeta =
+                serde_json::from_reader(BufReader::new(File::open(path).unwrap())).unwrap();
+            assert_eq!(ctsemeta.has_edit_data(), stripped == 0);
+
+            let mut writer = Cursor::new(Vec::new());
+            ctsemeta
+                .write_options(&mut writer, Endian::Little, ())
+                .unwrap();
+            let data = writer.into_inner();
+            let info = data
+                .windows(4)
+                .position(|window| window == b"INFO")
+                .unwrap();
+            assert_eq!(data[info + 4..info + 8], (stripped as u32).to_le_bytes());
+
+            let read_back = CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                ValidationOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(read_back.edit_data_stripped, stripped == 1);
+            assert_eq!(
+                read_back.edit_object_types.edit_object_types,
+                ctsemeta.edit_object_types.edit_object_types
+            );
+            assert_eq!(
+                read_back.edit_objects.edit_objects,
+                ctsemeta.edit_objects.edit_objects
+            );
+        }
+
+        // Edit data overrides the flag
+        let mut ctsemeta: CTSEMeta = serde_json::from_reader(BufReader::new(
+            File::open("data/SyntheticEditData.json").unwrap(),
+        ))
+        .unwrap();
+        ctsemeta.edit_data_stripped = true;
+        assert!(ctsemeta.has_edit_data());
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert!(!ctsemeta.edit_data_stripped);
+    }
+
+    #[test]
+    fn messages() {
+        let mut data = b"MSGS".to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for message in [b"Hello".as_slice(), &[0x00, 0xFF, 0x10]] {
+            data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+            data.extend_from_slice(message);
+        }
+
+        let messages = Messages::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            messages.messages,
+            [
+                Message {
+                    data: b"Hello".to_vec()
+                },
+                Message {
+                    data: vec![0x00, 0xFF, 0x10]
+                },
+            ]
+        );
+        let mut writer = Cursor::new(Vec::new());
+        messages
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(writer.into_inner(), data);
+
+        // And through JSON as part of a whole save
+        let mut ctsemeta = synthetic();
+        ctsemeta.messages = messages;
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let data = writer.into_inner();
+        let json = serde_json::to_string(
+            &CTSEMeta::read_options(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                ValidationOptions::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let ctsemeta: CTSEMeta = serde_json::from_str(&json).unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        assert_eq!(writer.into_inner(), data);
+    }
+
+    #[test]
+    fn sfloat_round_trip() {
+        let ctsemeta = with_objects(
+            [data_type(
+                5,
+                "SFLOAT",
+                DataTypeType::Primitive { Bytes: 2, LBE: 0 },
+            )],
+            [(5, InternalObjectDataValue::SFLOAT(-2.5))],
+        );
+
+        let data = ctsemeta.to_bytes(Endian::Little).unwrap();
+        // Object ID, type ID, then the half float bits
+        assert!(data[..objects_end(&data)].ends_with(&[2, 0, 0, 0, 5, 0, 0, 0, 0x00, 0xC1]));
+
+        let ctsemeta_again = CTSEMeta::from_bytes(&data).unwrap();
+        let json = serde_json::to_value(&ctsemeta_again).unwrap();
+        assert_eq!(
+            json["internal_objects"]["internal_object"][2]["value"]["SFLOAT"],
+            -2.5
+        );
+    }
+
+    #[test]
+    fn external_objects() {
+        let entries = [
+            ExternalObject {
+                Object: 10,
+                File: 0,
+                ObtType: 0,
+                ResName: None,
+                ResID: 7,
+                Type: 4,
+            },
+            ExternalObject {
+                Object: 11,
+                File: 1,
+                ObtType: 1,
+                ResName: Some("Cloud_1_01".to_owned()),
+                ResID: 0,
+                Type: 4,
+            },
+        ];
+        let mut data = b"EXOB".to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for x in [10u32, 0, 0, 7, 4, 11, 1, 1] {
+            data.extend_from_slice(&x.to_le_bytes());
+        }
+        data.extend_from_slice(&10u32.to_le_bytes());
+        data.extend_from_slice(b"Cloud_1_01");
+        data.extend_from_slice(&4u32.to_le_bytes());
+
+        let external_objects = ExternalObjects::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            (11, ValidationOptions::default()),
+        )
+        .unwrap();
+        assert_eq!(external_objects.external_objects, entries);
+        let mut writer = Cursor::new(Vec::new());
+        external_objects
+            .write_options(&mut writer, Endian::Little, (11,))
+            .unwrap();
+        assert_eq!(writer.into_inner(), data);
+
+        // Before version 9 there is only a ResID
+        let mut writer = Cursor::new(Vec::new());
+        ExternalObjects {
+            external_objects: entries[..1].to_vec().into(),
+        }
+        .write_options(&mut writer, Endian::Little, (8,))
+        .unwrap();
+        assert_eq!(writer.get_ref().len(), 4 + 4 + 4 * 4);
+
+        // Pointers to external objects are valid and count towards Info
+        let mut ctsemeta = synthetic();
+        ctsemeta.external_objects = external_objects;
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[1].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_pNext").unwrap() = InternalObjectDataValue::Pointer(11);
+        assert!(crate::validate::validate(&ctsemeta).is_empty());
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let data = writer.into_inner();
+        let ctsemeta_again = CTSEMeta::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(ctsemeta_again.external_objects.external_objects, entries);
+        let info = data.windows(4).position(|x| x == b"INFO").unwrap();
+        assert_eq!(&data[info + 20..info + 24], &4u32.to_le_bytes());
+    }
 }
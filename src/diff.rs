@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum Difference {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        before: Value,
+        after: Value,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            Self::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            Self::Changed {
+                path,
+                before,
+                after,
+            } => write!(f, "~ {}: {} -> {}", path, before, after),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SaveDiff {
+    pub idents: Vec<Difference>,
+    pub types: Vec<Difference>,
+    pub objects: Vec<Difference>,
+}
+
+impl SaveDiff {
+    pub fn new(a: &CTSEMeta, b: &CTSEMeta) -> Self {
+        let idents = diff_tables(
+            "Idents",
+            a.idents
+                .idents
+                .iter()
+                .map(|x| (x.Ident, Value::from(x.Name.as_str()))),
+            b.idents
+                .idents
+                .iter()
+                .map(|x| (x.Ident, Value::from(x.Name.as_str()))),
+        );
+
+        let mut types = diff_tables(
+            "ExternalTypes",
+            a.external_types
+                .types
+                .iter()
+                .map(|x| (x.Type, Value::from(x.Name.as_str()))),
+            b.external_types
+                .types
+                .iter()
+                .map(|x| (x.Type, Value::from(x.Name.as_str()))),
+        );
+        types.extend(diff_tables(
+            "Types",
+            a.internal_types
+                .types
+                .iter()
+                .map(|x| (x.DataType, serde_json::to_value(x).unwrap_or_default())),
+            b.internal_types
+                .types
+                .iter()
+                .map(|x| (x.DataType, serde_json::to_value(x).unwrap_or_default())),
+        ));
+
+        let type_name = |ctsemeta: &CTSEMeta, id: u32| {
+            ctsemeta
+                .internal_types
+                .types
+                .iter()
+                .find(|x| x.DataType == id)
+                .map(|x| Value::from(x.Name.as_str()))
+                .unwrap_or_else(|| Value::from(id))
+        };
+        let objects_a = a
+            .internal_objects
+            .internal_object
+            .iter()
+            .map(|x| (x.Object, x))
+            .collect::<BTreeMap<_, _>>();
+        let objects_b = b
+            .internal_objects
+            .internal_object
+            .iter()
+            .map(|x| (x.Object, x))
+            .collect::<BTreeMap<_, _>>();
+        let mut objects = Vec::new();
+        for (id, object_a) in objects_a.iter() {
+            let path = format!("Objects[{}]", id);
+            match objects_b.get(id) {
+                Some(object_b) if object_a.Type != object_b.Type => {
+                    objects.push(Difference::Changed {
+                        path: format!("{}.Type", path),
+                        before: type_name(a, object_a.Type),
+                        after: type_name(b, object_b.Type),
+                    })
+                }
+                Some(object_b) => diff_value(&path, &object_a.value, &object_b.value, &mut objects),
+                None => objects.push(Difference::Removed {
+                    path,
+                    value: type_name(a, object_a.Type),
+                }),
+            }
+        }
+        for (id, object_b) in objects_b.iter() {
+            if !objects_a.contains_key(id) {
+                objects.push(Difference::Added {
+                    path: format!("Objects[{}]", id),
+                    value: type_name(b, object_b.Type),
+                });
+            }
+        }
+
+        Self {
+            idents,
+            types,
+            objects,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.idents.is_empty() && self.types.is_empty() && self.objects.is_empty()
+    }
+}
+
+impl fmt::Display for SaveDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+
+        for (name, differences) in [
+            ("idents", &self.idents),
+            ("types", &self.types),
+            ("objects", &self.objects),
+        ] {
+            if differences.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}:", name)?;
+            for difference in differences {
+                writeln!(f, "  {}", difference)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn diff_tables(
+    name: &str,
+    a: impl Iterator<Item = (u32, Value)>,
+    b: impl Iterator<Item = (u32, Value)>,
+) -> Vec<Difference> {
+    let a = a.collect::<BTreeMap<_, _>>();
+    let mut b = b.collect::<BTreeMap<_, _>>();
+    let mut differences = Vec::new();
+    for (id, value_a) in a {
+        let path = format!("{}[{}]", name, id);
+        match b.remove(&id) {
+            Some(value_b) if value_a != value_b => differences.push(Difference::Changed {
+                path,
+                before: value_a,
+                after: value_b,
+            }),
+            Some(_) => {}
+            None => differences.push(Difference::Removed {
+                path,
+                value: value_a,
+            }),
+        }
+    }
+    for (id, value_b) in b {
+        differences.push(Difference::Added {
+            path: format!("{}[{}]", name, id),
+            value: value_b,
+        });
+    }
+    differences
+}
+
+fn diff_value(
+    path: &str,
+    a: &InternalObjectDataValue,
+    b: &InternalObjectDataValue,
+    differences: &mut Vec<Difference>,
+) {
+    match (a, b) {
+        (
+            InternalObjectDataValue::Struct {
+                Base: base_a,
+                members: members_a,
+            },
+            InternalObjectDataValue::Struct {
+                Base: base_b,
+                members: members_b,
+            },
+        ) => {
+            diff_optional(
+                &format!("{}.Base", path),
+                base_a.as_deref(),
+                base_b.as_deref(),
+                differences,
+            );
+
+            // Members are matched by name so reordered JSON doesn't show up
+            for (name, member_a) in members_a.iter() {
+                diff_optional(
+                    &format!("{}.{}", path, name),
+                    Some(member_a),
+                    members_b.get(name),
+                    differences,
+                );
+            }
+            for (name, member_b) in members_b.iter() {
+                if members_a.get(name).is_none() {
+                    diff_optional(
+                        &format!("{}.{}", path, name),
+                        None,
+                        Some(member_b),
+                        differences,
+                    );
+                }
+            }
+        }
+        (InternalObjectDataValue::Array(a), InternalObjectDataValue::Array(b))
+        | (
+            InternalObjectDataValue::StaticStackArray(a),
+            InternalObjectDataValue::StaticStackArray(b),
+        )
+        | (
+            InternalObjectDataValue::DynamicContainerValues(a),
+            InternalObjectDataValue::DynamicContainerValues(b),
+        ) => {
+            for i in 0..a.len().max(b.len()) {
+                diff_optional(&format!("{}[{}]", path, i), a.get(i), b.get(i), differences);
+            }
+        }
+        (
+            InternalObjectDataValue::DynamicContainer(a),
+            InternalObjectDataValue::DynamicContainer(b),
+        ) => {
+            for i in 0..a.len().max(b.len()) {
+                let path = format!("{}.DynamicContainer[{}]", path, i);
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) if a != b => differences.push(Difference::Changed {
+                        path,
+                        before: Value::from(*a),
+                        after: Value::from(*b),
+                    }),
+                    (Some(a), None) => differences.push(Difference::Removed {
+                        path,
+                        value: Value::from(*a),
+                    }),
+                    (None, Some(b)) => differences.push(Difference::Added {
+                        path,
+                        value: Value::from(*b),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        _ => {
+            let (a, b) = (to_value(a), to_value(b));
+            if a == b {
+                return;
+            }
+            // Name the variant in the path when it didn't change so the values
+            // are readable on their own
+            match (a, b) {
+                (Value::Object(a), Value::Object(b)) if a.len() == 1 && a.keys().eq(b.keys()) => {
+                    let (variant, before) = a.into_iter().next().unwrap_or_default();
+                    let after = b.into_iter().next().map(|x| x.1).unwrap_or_default();
+                    differences.push(Difference::Changed {
+                        path: format!("{}.{}", path, variant),
+                        before,
+                        after,
+                    })
+                }
+                (before, after) => differences.push(Difference::Changed {
+                    path: path.to_owned(),
+                    before,
+                    after,
+                }),
+            }
+        }
+    }
+}
+
+fn diff_optional(
+    path: &str,
+    a: Option<&InternalObjectDataValue>,
+    b: Option<&InternalObjectDataValue>,
+    differences: &mut Vec<Difference>,
+) {
+    match (a, b) {
+        (Some(a), Some(b)) => diff_value(path, a, b, differences),
+        (Some(a), None) => differences.push(Difference::Removed {
+            path: path.to_owned(),
+            value: to_value(a),
+        }),
+        (None, Some(b)) => differences.push(Difference::Added {
+            path: path.to_owned(),
+            value: to_value(b),
+        }),
+        (None, None) => {}
+    }
+}
+
+fn to_value(value: &InternalObjectDataValue) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctsemeta::{Ident, InternalObjectDataValue, synthetic};
+    use crate::diff::SaveDiff;
+
+    #[test]
+    fn diff() {
+        let a = synthetic();
+        let mut b = synthetic();
+        assert!(SaveDiff::new(&a, &b).is_empty());
+
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut b.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        members[0].1 = InternalObjectDataValue::CString("Cloud_1_02".to_owned());
+        b.internal_objects.internal_object.edit(Vec::pop);
+        b.idents.idents.push(Ident {
+            Ident: 4,
+            Name: "m_fNew".to_owned(),
+        });
+
+        let diff = SaveDiff::new(&a, &b);
+        assert_eq!(
+            diff.to_string(),
+            "idents:\n  + Idents[4]: \"m_fNew\"\nobjects:\n  ~ Objects[0].m_strName.CString: \
+             \"Cloud_1_01\" -> \"Cloud_1_02\"\n  - Objects[1]: \"CTalosProgress\"\n"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use encoding_rs::Encoding;
+
+use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+
+// CStrings are stored as a byte length and the bytes, and read as UTF-8 when
+// they're valid UTF-8. Localized installs can write them in a Windows code page
+// instead, these convert between the two.
+
+// None if the bytes aren't valid in the encoding
+pub fn decode_bytes(bytes: &[u8], encoding: &'static Encoding) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|string| string.into_owned())
+}
+
+// None if the string has characters the encoding can't represent
+pub fn encode_string(string: &str, encoding: &'static Encoding) -> Option<Vec<u8>> {
+    let (bytes, _, unmappable) = encoding.encode(string);
+    (!unmappable).then(|| bytes.into_owned())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct EncodingError {
+    pub object: u32,
+    pub string: String,
+    pub encoding: &'static str,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "object {} has {:?} which can't be written in {}",
+            self.object, self.string, self.encoding
+        )
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+fn for_each_value(
+    value: &mut InternalObjectDataValue,
+    f: &mut impl FnMut(&mut InternalObjectDataValue),
+) {
+    match value {
+        InternalObjectDataValue::Array(values)
+        | InternalObjectDataValue::StaticStackArray(values)
+        | InternalObjectDataValue::DynamicContainerValues(values) => {
+            values.iter_mut().for_each(|value| for_each_value(value, f))
+        }
+        InternalObjectDataValue::Struct { Base, members } => {
+            if let Some(base) = Base {
+                for_each_value(base, f);
+            }
+            members
+                .iter_mut()
+                .for_each(|(_, value)| for_each_value(value, f));
+        }
+        value => f(value),
+    }
+}
+
+impl CTSEMeta {
+    // Reads the bytes of every CString as the encoding instead of UTF-8, ones
+    // that aren't valid in it are left as they are
+    pub fn decode_strings(&mut self, encoding: &'static Encoding) {
+        for object in self.internal_objects.internal_object.iter_mut() {
+            for_each_value(&mut object.value, &mut |value| {
+                let bytes = match value {
+                    InternalObjectDataValue::CString(string) => string.as_bytes(),
+                    InternalObjectDataValue::CStringRaw(bytes) => bytes.as_slice(),
+                    _ => return,
+                };
+                if let Some(string) = decode_bytes(bytes, encoding) {
+                    *value = InternalObjectDataValue::CString(string);
+                }
+            });
+        }
+    }
+
+    // The reverse of decode_strings. Nothing is changed if any CString can't be
+    // written in the encoding.
+    pub fn encode_strings(
+        &mut self,
+        encoding: &'static Encoding,
+    ) -> Result<(), Vec<EncodingError>> {
+        let mut errors = Vec::new();
+        let mut encoded = Vec::new();
+        for object in self.internal_objects.internal_object.iter_mut() {
+            let id = object.Object;
+            for_each_value(&mut object.value, &mut |value| {
+                if let InternalObjectDataValue::CString(string) = value {
+                    match encode_string(string, encoding) {
+                        Some(bytes) => encoded.push(bytes),
+                        None => errors.push(EncodingError {
+                            object: id,
+                            string: string.clone(),
+                            encoding: encoding.name(),
+                        }),
+                    }
+                }
+            });
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        // Same walk order as above
+        let mut encoded = encoded.into_iter();
+        for object in self.internal_objects.internal_object.iter_mut() {
+            for_each_value(&mut object.value, &mut |value| {
+                if let InternalObjectDataValue::CString(_) = value {
+                    let bytes = encoded.next().expect("one encoding per CString");
+                    *value = match String::from_utf8(bytes) {
+                        Ok(string) => InternalObjectDataValue::CString(string),
+                        Err(e) => InternalObjectDataValue::CStringRaw(e.into_bytes()),
+                    };
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use binrw::Endian;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue, synthetic};
+    use crate::encoding::{EncodingError, decode_bytes, encode_string};
+
+    fn name(ctsemeta: &CTSEMeta) -> &InternalObjectDataValue {
+        let InternalObjectDataValue::Struct { members, .. } =
+            &ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        members.get("m_strName").unwrap()
+    }
+
+    fn set_name(ctsemeta: &mut CTSEMeta, value: InternalObjectDataValue) {
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_strName").unwrap() = value;
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        assert_eq!(decode_bytes(b"Ren\xE9e", WINDOWS_1252).unwrap(), "Renée");
+        assert_eq!(encode_string("Renée", WINDOWS_1252).unwrap(), b"Ren\xE9e");
+        assert!(encode_string("日本語", WINDOWS_1252).is_none());
+        let shift_jis = encode_string("日本語", SHIFT_JIS).unwrap();
+        assert_eq!(decode_bytes(&shift_jis, SHIFT_JIS).unwrap(), "日本語");
+        assert!(decode_bytes(b"\x82", SHIFT_JIS).is_none());
+    }
+
+    #[test]
+    fn encoded_strings_round_trip() {
+        let mut ctsemeta = synthetic();
+        set_name(
+            &mut ctsemeta,
+            InternalObjectDataValue::CStringRaw(b"Ren\xE9e".to_vec()),
+        );
+        let bytes = ctsemeta.to_bytes(Endian::Little).unwrap();
+
+        let mut decoded = CTSEMeta::from_bytes(&bytes).unwrap();
+        decoded.decode_strings(WINDOWS_1252);
+        assert!(matches!(
+            name(&decoded),
+            InternalObjectDataValue::CString(string) if string == "Renée"
+        ));
+
+        decoded.encode_strings(WINDOWS_1252).unwrap();
+        assert_eq!(decoded.to_bytes(Endian::Little).unwrap(), bytes);
+
+        set_name(
+            &mut decoded,
+            InternalObjectDataValue::CString("日本語".to_owned()),
+        );
+        assert_eq!(
+            decoded.encode_strings(WINDOWS_1252).unwrap_err(),
+            [EncodingError {
+                object: 0,
+                string: "日本語".to_owned(),
+                encoding: "windows-1252",
+            }]
+        );
+        // Left alone on failure
+        assert!(matches!(
+            name(&decoded),
+            InternalObjectDataValue::CString(string) if string == "日本語"
+        ));
+    }
+}
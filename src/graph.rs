@@ -0,0 +1,243 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+
+use anyhow::{Result, bail};
+
+use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+use crate::logging::warn;
+
+pub struct Edge {
+    pub from: u32,
+    pub to: u32,
+    // Where in the source object the pointer is, e.g. m_pNext or m_apItems[3]
+    pub label: String,
+}
+
+pub struct ObjectGraph {
+    pub nodes: Vec<(u32, String)>,
+    // Targets in one of the resource files
+    pub external: Vec<(u32, String)>,
+    // Targets that no object has
+    pub dangling: BTreeSet<u32>,
+    pub edges: Vec<Edge>,
+}
+
+fn collect_pointers(
+    value: &InternalObjectDataValue,
+    path: &str,
+    pointers: &mut Vec<(u32, String)>,
+) {
+    let join = |name: &str| {
+        if path.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{}", path, name)
+        }
+    };
+    match value {
+        InternalObjectDataValue::Pointer(pointer) if *pointer != -1 => {
+            pointers.push((*pointer as u32, path.to_owned()))
+        }
+        InternalObjectDataValue::Struct { Base, members } => {
+            if let Some(base) = Base {
+                collect_pointers(base, &join("Base"), pointers);
+            }
+            for (name, member) in members.iter() {
+                collect_pointers(member, &join(name), pointers);
+            }
+        }
+        InternalObjectDataValue::Array(values)
+        | InternalObjectDataValue::StaticStackArray(values)
+        | InternalObjectDataValue::DynamicContainerValues(values) => {
+            for (i, value) in values.iter().enumerate() {
+                collect_pointers(value, &format!("{}[{}]", path, i), pointers);
+            }
+        }
+        InternalObjectDataValue::DynamicContainer(values) => {
+            for (i, value) in values.iter().enumerate() {
+                pointers.push((*value, format!("{}[{}]", path, i)));
+            }
+        }
+        _ => {}
+    }
+}
+
+impl ObjectGraph {
+    // Only objects reachable from root are included when it is given. Nodes are
+    // visited breadth first and anything past max_nodes is left out.
+    pub fn new(ctsemeta: &CTSEMeta, root: Option<u32>, max_nodes: Option<usize>) -> Result<Self> {
+        let types = ctsemeta.internal_types.by_id();
+        let objects = ctsemeta
+            .internal_objects
+            .internal_object
+            .iter()
+            .map(|object| (object.Object, object))
+            .collect::<HashMap<_, _>>();
+
+        let external_objects = ctsemeta
+            .external_objects
+            .external_objects
+            .iter()
+            .map(|object| (object.Object, object))
+            .collect::<HashMap<_, _>>();
+        let type_name = |id: u32| {
+            types
+                .get(&id)
+                .map(|data_type| data_type.Name.clone())
+                .unwrap_or_else(|| id.to_string())
+        };
+
+        let mut queue = match root {
+            Some(root) if !objects.contains_key(&root) => bail!("no object with ID {}", root),
+            Some(root) => VecDeque::from([root]),
+            None => ctsemeta
+                .internal_objects
+                .internal_object
+                .iter()
+                .map(|object| object.Object)
+                .collect(),
+        };
+        let max_nodes = max_nodes.unwrap_or(usize::MAX);
+
+        let mut visited = BTreeSet::new();
+        let mut graph = Self {
+            nodes: Vec::new(),
+            external: Vec::new(),
+            dangling: BTreeSet::new(),
+            edges: Vec::new(),
+        };
+        let mut pointers = Vec::new();
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if graph.nodes.len() >= max_nodes {
+                warn!("graph truncated to {} nodes", max_nodes);
+                break;
+            }
+
+            let object = objects[&id];
+            graph.nodes.push((id, type_name(object.Type)));
+
+            pointers.clear();
+            collect_pointers(&object.value, "", &mut pointers);
+            for (to, label) in pointers.drain(..) {
+                if objects.contains_key(&to) {
+                    if root.is_some() {
+                        queue.push_back(to);
+                    }
+                } else if let Some(object) = external_objects.get(&to) {
+                    if !graph.external.iter().any(|(id, _)| *id == to) {
+                        graph.external.push((to, type_name(object.Type)));
+                    }
+                } else {
+                    graph.dangling.insert(to);
+                }
+                graph.edges.push(Edge {
+                    from: id,
+                    to,
+                    label,
+                });
+            }
+        }
+
+        // Drop edges to objects that were cut off by max_nodes
+        let included = graph
+            .nodes
+            .iter()
+            .chain(&graph.external)
+            .map(|(id, _)| *id)
+            .collect::<BTreeSet<_>>();
+        graph
+            .edges
+            .retain(|edge| included.contains(&edge.to) || graph.dangling.contains(&edge.to));
+
+        Ok(graph)
+    }
+}
+
+impl fmt::Display for ObjectGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph objects {{")?;
+        writeln!(f, "    node [shape=box];")?;
+        for (id, type_name) in &self.nodes {
+            writeln!(
+                f,
+                "    {} [label={:?}];",
+                id,
+                format!("#{}: {}", id, type_name)
+            )?;
+        }
+        for (id, type_name) in &self.external {
+            writeln!(
+                f,
+                "    {} [label={:?}, style=dashed];",
+                id,
+                format!("#{}: {} (external)", id, type_name)
+            )?;
+        }
+        for id in &self.dangling {
+            writeln!(
+                f,
+                "    {} [label={:?}, color=red, fontcolor=red, style=dashed];",
+                id,
+                format!("#{}: missing", id)
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                f,
+                "    {} -> {} [label={:?}];",
+                edge.from, edge.to, edge.label
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+    use crate::graph::ObjectGraph;
+
+    #[test]
+    fn graph() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+
+        let graph = ObjectGraph::new(&ctsemeta, None, None).unwrap();
+        assert_eq!(
+            graph.to_string(),
+            "digraph objects {\n    node [shape=box];\n    0 [label=\"#0: CTalosProgress\"];\n    \
+             1 [label=\"#1: CTalosProgress\"];\n    0 -> 1 [label=\"m_pNext\"];\n}\n"
+        );
+
+        // Object 1 points nowhere so the subgraph from it is just itself
+        let graph = ObjectGraph::new(&ctsemeta, Some(1), None).unwrap();
+        assert_eq!(graph.nodes.len(), 1);
+        assert!(graph.edges.is_empty());
+        assert!(ObjectGraph::new(&ctsemeta, Some(7), None).is_err());
+
+        let graph = ObjectGraph::new(&ctsemeta, None, Some(1)).unwrap();
+        assert_eq!(graph.nodes.len(), 1);
+        assert!(graph.edges.is_empty());
+
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[1].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_pNext").unwrap() = InternalObjectDataValue::Pointer(9);
+        let graph = ObjectGraph::new(&ctsemeta, Some(0), None).unwrap();
+        assert!(
+            graph
+                .to_string()
+                .contains("9 [label=\"#9: missing\", color=red")
+        );
+        assert!(graph.to_string().contains("1 -> 9 [label=\"m_pNext\"]"));
+    }
+}
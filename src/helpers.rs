@@ -1,8 +1,244 @@
-use binrw::{BinRead, BinWrite, args, parser, writer};
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::{Deref, DerefMut};
+
+use binrw::{BinRead, BinWrite, Endian, args, parser, writer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Corrupt saves can have garbage length prefixes, so cap them to error out
+// instead of trying to allocate gigabytes. Use the bounded parsers to pick a
+// different limit.
+pub const MAX_PASCAL_STRING_LENGTH: usize = 0x10000;
+pub const MAX_PASCAL_VEC_COUNT: usize = 0x100000;
+
+// A Pascal vec along with the count it was read with. The count is written back
+// as it was, so a count that doesn't match the items survives a round trip.
+// Changing the length through the methods here moves the count by as much.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedVec<T> {
+    pub count_prefix: u32,
+    pub items: Vec<T>,
+}
+
+impl<T> ParsedVec<T> {
+    pub fn new() -> Self {
+        Self::from(Vec::new())
+    }
+
+    // Any change to the length made by f is applied to the count too
+    pub fn edit<R>(&mut self, f: impl FnOnce(&mut Vec<T>) -> R) -> R {
+        let len = self.items.len();
+        let result = f(&mut self.items);
+        let count_prefix = self.count_prefix as i64 + self.items.len() as i64 - len as i64;
+        self.count_prefix = count_prefix.clamp(0, u32::MAX as i64) as u32;
+        result
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.edit(|items| items.push(item))
+    }
+
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        self.edit(|items| items.extend(iter))
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&T) -> bool) {
+        self.edit(|items| items.retain(f))
+    }
+
+    // Nothing is left for a different count to describe so it goes back to 0
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.count_prefix = 0;
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T> Default for ParsedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<T>> for ParsedVec<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            count_prefix: items.len() as u32,
+            items,
+        }
+    }
+}
+
+impl<T> FromIterator<T> for ParsedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T> Deref for ParsedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<T> DerefMut for ParsedVec<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ParsedVec<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ParsedVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter_mut()
+    }
+}
+
+impl<T> IntoIterator for ParsedVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<T: PartialEq, U> PartialEq<U> for ParsedVec<T>
+where
+    U: AsRef<[T]> + ?Sized,
+{
+    fn eq(&self, other: &U) -> bool {
+        self.items == other.as_ref()
+    }
+}
+
+// The JSON is just the items, the count is taken from them when it is read
+impl<T: Serialize> Serialize for ParsedVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ParsedVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from)
+    }
+}
+
+// Limits for reading a CTSEMETA, passed down to every length prefixed field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationOptions {
+    pub max_string_length: usize,
+    pub max_vec_count: usize,
+    // Keep the objects read before one that fails instead of erroring
+    pub keep_partial_objects: bool,
+    // Fail on inconsistencies that are otherwise only warned about, like INFO
+    // counts that don't match the tables
+    pub strict: bool,
+}
+
+impl ValidationOptions {
+    // For saves that are known to be corrupt, see --no-validate
+    pub const NONE: Self = Self {
+        max_string_length: usize::MAX,
+        max_vec_count: usize::MAX,
+        keep_partial_objects: true,
+        strict: false,
+    };
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            max_string_length: MAX_PASCAL_STRING_LENGTH,
+            max_vec_count: MAX_PASCAL_VEC_COUNT,
+            keep_partial_objects: false,
+            strict: false,
+        }
+    }
+}
+
+// Bytes left after the current position, None when the reader can't seek to
+// the end to find out
+fn remaining_len<R: Read + Seek>(reader: &mut R) -> Option<u64> {
+    let pos = reader.stream_position().ok()?;
+    let end = reader.seek(SeekFrom::End(0)).ok()?;
+    reader.seek(SeekFrom::Start(pos)).ok()?;
+    Some(end.saturating_sub(pos))
+}
+
+// Items that take at least a byte can't outnumber what is left in the stream,
+// that catches garbage counts well under the maximum. Zero sized items, like
+// memberless structs, fit any count so only the maximum applies to them.
+fn check_pascal_count<R: Read + Seek>(
+    reader: &mut R,
+    count: usize,
+    max: usize,
+    zero_sized: bool,
+    pos: u64,
+    what: &str,
+) -> binrw::BinResult<()> {
+    if count > max {
+        return Err(binrw::Error::Custom {
+            pos,
+            err: Box::new(format!(
+                "{} length {} exceeds the maximum of {}",
+                what, count, max
+            )),
+        });
+    }
+    if let Some(remaining) =
+        remaining_len(reader).filter(|&remaining| !zero_sized && count as u64 > remaining)
+    {
+        return Err(binrw::Error::Custom {
+            pos,
+            err: Box::new(format!(
+                "{} length {} at offset {:#x} exceeds remaining {} bytes",
+                what, count, pos, remaining
+            )),
+        });
+    }
+    Ok(())
+}
 
 #[parser(reader, endian)]
 pub fn parse_pascal_string() -> binrw::BinResult<String> {
+    parse_bounded_pascal_string::<MAX_PASCAL_STRING_LENGTH, _>(reader, endian, ())
+}
+
+#[parser(reader, endian)]
+pub fn parse_bounded_pascal_string<const MAX: usize>() -> binrw::BinResult<String> {
+    parse_pascal_string_with_max(reader, endian, MAX)
+}
+
+#[parser(reader, endian)]
+pub fn parse_validated_pascal_string(options: ValidationOptions) -> binrw::BinResult<String> {
+    parse_pascal_string_with_max(reader, endian, options.max_string_length)
+}
+
+fn parse_pascal_string_with_max<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+    max: usize,
+) -> binrw::BinResult<String> {
+    let count_pos = reader.stream_position()?;
     let count = u32::read_options(reader, endian, ())? as usize;
+    check_pascal_count(reader, count, max, false, count_pos, "string")?;
     let pos = reader.stream_position()?;
     let utf8 = Vec::<u8>::read_options(reader, endian, args! { count, inner: () })?;
     let string = String::from_utf8(utf8).map_err(|e| binrw::Error::Custom {
@@ -12,7 +248,35 @@ pub fn parse_pascal_string() -> binrw::BinResult<String> {
     Ok(string)
 }
 
-// Weird signature to work with binrw type_hint functions
+#[parser(reader, endian)]
+pub fn parse_pascal_bytes() -> binrw::BinResult<Vec<u8>> {
+    parse_validated_pascal_bytes(reader, endian, (ValidationOptions::default(),))
+}
+
+#[parser(reader, endian)]
+pub fn parse_validated_pascal_bytes(options: ValidationOptions) -> binrw::BinResult<Vec<u8>> {
+    let count_pos = reader.stream_position()?;
+    let count = u32::read_options(reader, endian, ())? as usize;
+    check_pascal_count(
+        reader,
+        count,
+        options.max_string_length,
+        false,
+        count_pos,
+        "bytes",
+    )?;
+    Vec::<u8>::read_options(reader, endian, args! { count, inner: () })
+}
+
+#[writer(writer, endian)]
+pub fn write_pascal_bytes(value: &Vec<u8>) -> binrw::BinResult<()> {
+    (value.len() as u32).write_options(writer, endian, ())?;
+    value.write(writer)?;
+    Ok(())
+}
+
+// Weird signature to work with binrw type_hint functions. Written as UTF-8 with
+// the length in bytes, which is what parse_pascal_string expects.
 #[writer(writer, endian)]
 pub fn write_pascal_string(value: &(impl AsRef<str> + ?Sized)) -> binrw::BinResult<()> {
     (value.as_ref().len() as u32).write_options(writer, endian, ())?;
@@ -21,21 +285,345 @@ pub fn write_pascal_string(value: &(impl AsRef<str> + ?Sized)) -> binrw::BinResu
 }
 
 #[parser(reader, endian)]
-pub fn parse_pascal_vec<T>(args: T::Args<'_>) -> binrw::BinResult<Vec<T>>
+pub fn parse_pascal_vec<T>(args: T::Args<'_>) -> binrw::BinResult<ParsedVec<T>>
 where
     for<'a> T: BinRead<Args<'a>: Clone> + 'a,
 {
-    let count = u32::read_options(reader, endian, ())? as usize;
-    let vec = Vec::<T>::read_options(reader, endian, args! { count, inner: args })?;
-    Ok(vec)
+    parse_bounded_pascal_vec::<T, MAX_PASCAL_VEC_COUNT, _>(reader, endian, (args,))
+}
+
+#[parser(reader, endian)]
+pub fn parse_bounded_pascal_vec<T, const MAX: usize>(
+    args: T::Args<'_>,
+) -> binrw::BinResult<ParsedVec<T>>
+where
+    for<'a> T: BinRead<Args<'a>: Clone> + 'a,
+{
+    parse_pascal_vec_with_max(reader, endian, MAX, args)
+}
+
+#[parser(reader, endian)]
+pub fn parse_validated_pascal_vec<T>(
+    options: ValidationOptions,
+    args: T::Args<'_>,
+) -> binrw::BinResult<ParsedVec<T>>
+where
+    for<'a> T: BinRead<Args<'a>: Clone> + 'a,
+{
+    parse_pascal_vec_with_max(reader, endian, options.max_vec_count, args)
+}
+
+fn parse_pascal_vec_with_max<R: Read + Seek, T>(
+    reader: &mut R,
+    endian: Endian,
+    max: usize,
+    args: T::Args<'_>,
+) -> binrw::BinResult<ParsedVec<T>>
+where
+    for<'a> T: BinRead<Args<'a>: Clone> + 'a,
+{
+    let count_pos = reader.stream_position()?;
+    let count_prefix = u32::read_options(reader, endian, ())?;
+    let count = count_prefix as usize;
+    // Types that read nothing have no size
+    let zero_sized = size_of::<T>() == 0;
+    check_pascal_count(reader, count, max, zero_sized, count_pos, "vec")?;
+    let items = Vec::<T>::read_options(reader, endian, args! { count, inner: args })?;
+    Ok(ParsedVec {
+        count_prefix,
+        items,
+    })
 }
 
 #[writer(writer, endian)]
-pub fn write_pascal_vec<T>(value: &Vec<T>) -> binrw::BinResult<()>
+pub fn write_pascal_vec<T>(value: &ParsedVec<T>, args: T::Args<'_>) -> binrw::BinResult<()>
 where
-    for<'a> T: BinWrite<Args<'a> = ()> + 'a,
+    for<'a> T: BinWrite<Args<'a>: Clone> + 'a,
 {
-    (value.len() as u32).write_options(writer, endian, ())?;
-    value.write_options(writer, endian, ())?;
+    value.count_prefix.write_options(writer, endian, ())?;
+    value.items.write_options(writer, endian, args)?;
     Ok(())
 }
+
+// The CTSEMETA magic is followed by 0x1234ABCD written in the file's endianness
+pub fn detect_endian<R: Read + Seek>(reader: &mut R) -> binrw::BinResult<Endian> {
+    let start = reader.stream_position()?;
+    let mut header = [0u8; 12];
+    let result = reader.read_exact(&mut header);
+    reader.seek(SeekFrom::Start(start))?;
+    result?;
+
+    match header[8..12] {
+        [0xCD, 0xAB, 0x34, 0x12] => Ok(Endian::Little),
+        [0x12, 0x34, 0xAB, 0xCD] => Ok(Endian::Big),
+        _ => Err(binrw::Error::BadMagic {
+            pos: start + 8,
+            found: Box::new(u32::from_le_bytes([
+                header[8], header[9], header[10], header[11],
+            ])),
+        }),
+    }
+}
+
+// Where two buffers stop agreeing, a buffer that is a prefix of the other
+// differs at its end
+pub fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .or_else(|| (a.len() != b.len()).then(|| a.len().min(b.len())))
+}
+
+// The 16 byte aligned rows around offset as hex, like "0x10: 49 4e 46 4f"
+pub fn hex_context(data: &[u8], offset: usize) -> String {
+    let start = (offset / 16).saturating_sub(1) * 16;
+    let end = ((offset / 16 + 2) * 16).min(data.len());
+    (start..end)
+        .step_by(16)
+        .map(|row| {
+            let bytes = data[row..(row + 16).min(end)]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("{:#x}: {}", row, bytes)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::Endian;
+
+    use crate::helpers::{
+        MAX_PASCAL_STRING_LENGTH,
+        ParsedVec,
+        ValidationOptions,
+        detect_endian,
+        first_difference,
+        hex_context,
+        parse_bounded_pascal_string,
+        parse_pascal_string,
+        parse_pascal_vec,
+        parse_validated_pascal_bytes,
+        parse_validated_pascal_string,
+        parse_validated_pascal_vec,
+        write_pascal_string,
+        write_pascal_vec,
+    };
+
+    #[test]
+    fn pascal_length_guard() {
+        let mut data = 0xFFFFFFFFu32.to_le_bytes().to_vec();
+        data.extend_from_slice(b"Talos");
+
+        let error = parse_pascal_string(&mut Cursor::new(&data), Endian::Little, ()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("string length 4294967295 exceeds")
+        );
+
+        let error =
+            parse_pascal_vec::<u32, _>(&mut Cursor::new(&data), Endian::Little, ((),)).unwrap_err();
+        assert!(error.to_string().contains("vec length 4294967295 exceeds"));
+
+        let mut data = 5u32.to_le_bytes().to_vec();
+        data.extend_from_slice(b"Talos");
+        assert_eq!(
+            parse_pascal_string(&mut Cursor::new(&data), Endian::Little, ()).unwrap(),
+            "Talos"
+        );
+        assert!(
+            parse_bounded_pascal_string::<4, _>(&mut Cursor::new(&data), Endian::Little, ())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn pascal_string_byte_length() {
+        // The length is in UTF-8 bytes, not chars
+        for string in ["日本語", "Renée", "🦀", ""] {
+            let mut writer = Cursor::new(Vec::new());
+            write_pascal_string(string, &mut writer, Endian::Little, ()).unwrap();
+            let data = writer.into_inner();
+            assert_eq!(data[..4], (string.len() as u32).to_le_bytes());
+            assert_eq!(&data[4..], string.as_bytes());
+            assert_eq!(
+                parse_pascal_string(&mut Cursor::new(&data), Endian::Little, ()).unwrap(),
+                string
+            );
+        }
+    }
+
+    #[test]
+    fn remaining_length_guard() {
+        // Lifting the limits doesn't let a count run past the end
+        let none = (ValidationOptions::NONE,);
+        let mut data = b"pad!".to_vec();
+        data.extend_from_slice(&0xFFFFFFFFu32.to_le_bytes());
+        data.extend_from_slice(b"Talos");
+        let mut reader = Cursor::new(&data);
+        reader.set_position(4);
+        let error = parse_validated_pascal_string(&mut reader, Endian::Little, none).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("string length 4294967295 at offset 0x4 exceeds remaining 5 bytes")
+        );
+
+        let mut data = 6u32.to_le_bytes().to_vec();
+        data.extend_from_slice(b"Talos");
+        let error = parse_validated_pascal_bytes(&mut Cursor::new(&data), Endian::Little, none)
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("bytes length 6 at offset 0x0 exceeds remaining 5 bytes")
+        );
+        let error = parse_validated_pascal_vec::<u8, _>(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            (ValidationOptions::NONE, ()),
+        )
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("vec length 6 at offset 0x0 exceeds remaining 5 bytes")
+        );
+
+        // Zero sized items fit any count
+        let vec = parse_validated_pascal_vec::<(), _>(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            (ValidationOptions::NONE, ()),
+        )
+        .unwrap();
+        assert_eq!(vec.len(), 6);
+    }
+
+    #[test]
+    fn validation_options() {
+        let length = MAX_PASCAL_STRING_LENGTH + 1;
+        let mut data = (length as u32).to_le_bytes().to_vec();
+        data.resize(4 + length, b'a');
+
+        let default = (ValidationOptions::default(),);
+        assert!(
+            parse_validated_pascal_string(&mut Cursor::new(&data), Endian::Little, default)
+                .is_err()
+        );
+        assert!(
+            parse_validated_pascal_bytes(&mut Cursor::new(&data), Endian::Little, default).is_err()
+        );
+        let none = (ValidationOptions::NONE,);
+        assert_eq!(
+            parse_validated_pascal_string(&mut Cursor::new(&data), Endian::Little, none)
+                .unwrap()
+                .len(),
+            length
+        );
+        assert_eq!(
+            parse_validated_pascal_bytes(&mut Cursor::new(&data), Endian::Little, none)
+                .unwrap()
+                .len(),
+            length
+        );
+
+        let options = ValidationOptions {
+            max_vec_count: 1,
+            ..ValidationOptions::default()
+        };
+        let data = [2u32, 1, 2].map(u32::to_le_bytes).concat();
+        assert!(
+            parse_validated_pascal_vec::<u32, _>(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                (options, ())
+            )
+            .is_err()
+        );
+        assert_eq!(
+            parse_validated_pascal_vec::<u32, _>(
+                &mut Cursor::new(&data),
+                Endian::Little,
+                (ValidationOptions::NONE, ())
+            )
+            .unwrap(),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn parsed_vec() {
+        let data = [2u32, 1, 2].map(u32::to_le_bytes).concat();
+        let mut vec =
+            parse_pascal_vec::<u32, _>(&mut Cursor::new(&data), Endian::Little, ((),)).unwrap();
+        assert_eq!(vec.count_prefix, 2);
+        assert_eq!(vec, [1, 2]);
+
+        // A count that doesn't match is written back as it was
+        vec.count_prefix = 5;
+        vec.push(3);
+        vec.retain(|x| *x != 1);
+        assert_eq!(vec.count_prefix, 5);
+        assert_eq!(vec, [2, 3]);
+        let mut writer = Cursor::new(Vec::new());
+        write_pascal_vec(&vec, &mut writer, Endian::Little, ((),)).unwrap();
+        assert_eq!(
+            writer.into_inner(),
+            [5u32, 2, 3].map(u32::to_le_bytes).concat()
+        );
+
+        // The JSON is only the items
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, "[2,3]");
+        let vec: ParsedVec<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(vec.count_prefix, 2);
+
+        let mut vec = ParsedVec::from(vec![1u32]);
+        vec.clear();
+        assert_eq!(vec.count_prefix, 0);
+    }
+
+    #[test]
+    fn detect_endian_from_cookie() {
+        let mut data = b"CTSEMETA".to_vec();
+        data.extend_from_slice(&0x1234ABCDu32.to_le_bytes());
+        let mut reader = Cursor::new(&data);
+        assert_eq!(detect_endian(&mut reader).unwrap(), Endian::Little);
+        // The reader is left where it was
+        assert_eq!(reader.position(), 0);
+
+        data[8..12].copy_from_slice(&0x1234ABCDu32.to_be_bytes());
+        assert_eq!(detect_endian(&mut Cursor::new(&data)).unwrap(), Endian::Big);
+
+        data[8..12].copy_from_slice(b"nope");
+        assert!(detect_endian(&mut Cursor::new(&data)).is_err());
+        assert!(detect_endian(&mut Cursor::new(b"CTSE")).is_err());
+    }
+
+    #[test]
+    fn differences() {
+        assert_eq!(first_difference(b"abc", b"abc"), None);
+        assert_eq!(first_difference(b"abc", b"abd"), Some(2));
+        assert_eq!(first_difference(b"ab", b"abc"), Some(2));
+
+        let data = (0..40).collect::<Vec<u8>>();
+        assert_eq!(
+            hex_context(&data, 18),
+            "0x0: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n\
+             0x10: 10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f\n\
+             0x20: 20 21 22 23 24 25 26 27"
+        );
+        // A buffer that ends early shows the rows before its end
+        assert_eq!(
+            hex_context(&data, 40),
+            "0x10: 10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f\n0x20: 20 21 22 23 24 25 26 27"
+        );
+    }
+}
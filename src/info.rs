@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Cursor, Read};
+
+use anyhow::Result;
+use binrw::{BinRead, Endian};
+use flate2::bufread::GzDecoder;
+use serde::Serialize;
+
+use crate::ctsemeta::{Info, Messages, Metadata};
+use crate::helpers::{ValidationOptions, detect_endian};
+use crate::signature_stream::{
+    ExtraFieldCT,
+    KeyRing,
+    SignatureCoverage,
+    SignatureStreamHeader,
+    VerifyOptions,
+    parse_signature_stream,
+    read_gz_extra_field,
+};
+
+#[derive(Serialize)]
+pub struct SaveInfo {
+    pub gz: bool,
+    pub extra_field_ct: Option<ExtraFieldCT>,
+    pub signature_stream: SignatureStreamHeader,
+    pub signature_coverage: SignatureCoverage,
+    pub payload_size: usize,
+    pub metadata: Option<Metadata>,
+    pub info: Option<Info>,
+}
+
+impl SaveInfo {
+    // Works on both gz and raw signature streams, only the CTSEMETA headers are
+    // parsed so this works even if the objects don't
+    pub fn read(
+        file: &[u8],
+        endian: Endian,
+        key_ring: &KeyRing,
+        memory_stream_name: Option<&String>,
+        userid: Option<&String>,
+    ) -> Result<Self> {
+        // gzip magic
+        let gz = file.starts_with(&[0x1F, 0x8B]);
+        let (extra_field_ct, signature_stream) = if gz {
+            let extra_field_ct = read_gz_extra_field(&mut Cursor::new(file)).ok();
+            let mut signature_stream = Vec::new();
+            GzDecoder::new(file).read_to_end(&mut signature_stream)?;
+            (extra_field_ct, Cow::Owned(signature_stream))
+        } else {
+            (None, Cow::Borrowed(file))
+        };
+
+        let (signature_stream, data) = parse_signature_stream(
+            &mut signature_stream.as_ref(),
+            endian,
+            key_ring,
+            memory_stream_name,
+            userid,
+        )?;
+
+        let mut reader = Cursor::new(&data);
+        // The payload can differ from the signature stream, trust its cookie
+        let endian = detect_endian(&mut reader).unwrap_or(endian);
+        let (metadata, info) =
+            match Metadata::read_options(&mut reader, endian, ValidationOptions::default()) {
+                Ok(metadata) => {
+                    let info =
+                        Messages::read_options(&mut reader, endian, ValidationOptions::default())
+                            .and_then(|_| {
+                                Info::read_options(&mut reader, endian, (metadata.version,))
+                            })
+                            .ok();
+                    (Some(metadata), info)
+                }
+                Err(_) => (None, None),
+            };
+
+        Ok(Self {
+            gz,
+            extra_field_ct,
+            signature_coverage: SignatureCoverage::new(
+                &signature_stream,
+                VerifyOptions {
+                    memory_stream_name: memory_stream_name.map(String::as_str),
+                    userid: userid.map(String::as_str),
+                },
+            ),
+            signature_stream,
+            payload_size: data.len(),
+            metadata,
+            info,
+        })
+    }
+}
+
+impl fmt::Display for SaveInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |x: bool| if x { "yes" } else { "no" };
+
+        write!(f, "container: {}", if self.gz { "gz" } else { "raw" })?;
+        if let Some(extra_field_ct) = self.extra_field_ct.as_ref() {
+            write!(
+                f,
+                " (compressed size: {}, decompressed size: {})",
+                extra_field_ct.compressed_size(),
+                extra_field_ct.decompressed_size()
+            )?;
+        }
+        writeln!(f)?;
+
+        let header = &self.signature_stream;
+        writeln!(f, "signature stream version: {}", header.version)?;
+        writeln!(f, "block size: {:#x}", header.block_size)?;
+        writeln!(
+            f,
+            "hash method: {:?} ({})",
+            header.hash_method,
+            u32::from(header.hash_method)
+        )?;
+        writeln!(f, "salt: {:#010x}", header.salt)?;
+        match header.sign_key_name.as_ref() {
+            Some(sign_key_name) => {
+                writeln!(
+                    f,
+                    "signed with: {} ({} byte signatures)",
+                    sign_key_name, header.signature_size
+                )?;
+                writeln!(
+                    f,
+                    "this save's signature covers: {}",
+                    self.signature_coverage
+                )?;
+            }
+            None => writeln!(f, "signed with: unsigned")?,
+        }
+
+        match self.metadata.as_ref() {
+            Some(metadata) => {
+                writeln!(f, "payload: {} bytes, CTSEMETA", self.payload_size)?;
+                writeln!(f, "CTSEMETA version: {}", metadata.version)?;
+                if let Some(version_string) = metadata.version_string.as_ref() {
+                    writeln!(f, "version string: {}", version_string)?;
+                }
+                if let Some(info) = self.info.as_ref() {
+                    if let Some(edit_data_stripped) = info.EditDataStripped {
+                        writeln!(f, "edit data stripped: {}", yes_no(edit_data_stripped != 0))?;
+                    }
+                    writeln!(f, "resource files: {}", info.ResourceFiles)?;
+                    writeln!(f, "idents: {}", info.Idents)?;
+                    writeln!(f, "types: {}", info.Types)?;
+                    writeln!(f, "objects: {}", info.Objects)?;
+                }
+            }
+            None => writeln!(f, "payload: {} bytes, unknown format", self.payload_size)?,
+        }
+
+        Ok(())
+    }
+}
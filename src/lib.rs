@@ -0,0 +1,22 @@
+pub mod annotate;
+pub mod ctsem;
+pub mod ctsemeta;
+pub mod diff;
+pub mod encoding;
+pub mod graph;
+pub mod helpers;
+pub mod info;
+pub mod list;
+pub mod locate;
+mod logging;
+pub mod merge;
+pub mod output_file;
+pub mod path;
+pub mod signature_stream;
+pub mod split;
+pub mod steam_id;
+pub mod strip;
+pub mod template;
+pub mod typed;
+pub mod types;
+pub mod validate;
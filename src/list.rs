@@ -0,0 +1,144 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::ctsemeta::CTSEMeta;
+
+const PREVIEW_LENGTH: usize = 60;
+
+#[derive(Serialize)]
+pub struct ObjectSummary {
+    pub object: u32,
+    pub type_id: u32,
+    pub type_name: Option<String>,
+    // The ident with the same ID as the object, named objects have one
+    pub ident_name: Option<String>,
+    pub summary: String,
+}
+
+impl fmt::Display for ObjectSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} ({}): {}",
+            self.object,
+            self.type_name.as_deref().unwrap_or("<unknown type>"),
+            self.type_id,
+            self.summary
+        )
+    }
+}
+
+// Only objects whose type name and ident name contain the filters are listed
+pub fn list_objects(
+    ctsemeta: &CTSEMeta,
+    filter_type: Option<&str>,
+    filter_ident: Option<&str>,
+) -> Vec<ObjectSummary> {
+    let types = ctsemeta.internal_types.by_id();
+    let idents = ctsemeta.idents.by_id();
+    let matches = |name: Option<&String>, filter: Option<&str>| match filter {
+        Some(filter) => name.is_some_and(|name| name.contains(filter)),
+        None => true,
+    };
+    ctsemeta
+        .internal_objects
+        .internal_object
+        .iter()
+        .map(|object| ObjectSummary {
+            object: object.Object,
+            type_id: object.Type,
+            type_name: types.get(&object.Type).map(|x| x.Name.clone()),
+            ident_name: idents.get(&object.Object).map(|x| (*x).to_owned()),
+            summary: object.value.to_string(),
+        })
+        .filter(|summary| {
+            matches(summary.type_name.as_ref(), filter_type)
+                && matches(summary.ident_name.as_ref(), filter_ident)
+        })
+        .collect()
+}
+
+// Aligned table with a header, values are cut to PREVIEW_LENGTH characters
+pub fn render_objects(objects: &[ObjectSummary]) -> String {
+    let header = [
+        "Object-ID",
+        "Type-ID",
+        "Type-Name",
+        "Ident-Name",
+        "Value-Preview",
+    ];
+    let rows = objects
+        .iter()
+        .map(|object| {
+            [
+                object.object.to_string(),
+                object.type_id.to_string(),
+                object.type_name.clone().unwrap_or_default(),
+                object.ident_name.clone().unwrap_or_default(),
+                object.summary.chars().take(PREVIEW_LENGTH).collect(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = header.map(|x| x.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(header.map(|x| x.to_owned())).chain(rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+    use crate::list::{list_objects, render_objects};
+
+    #[test]
+    fn list() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        ctsemeta.idents.idents[1].Name = "TalosProgress".to_owned();
+
+        let objects = list_objects(&ctsemeta, None, None);
+        assert_eq!(objects.len(), 2);
+        assert_eq!(
+            objects[0].to_string(),
+            "#0 CTalosProgress (4): Struct, 4 members"
+        );
+        assert_eq!(objects[1].ident_name.as_deref(), Some("TalosProgress"));
+
+        assert_eq!(list_objects(&ctsemeta, Some("Talos"), None).len(), 2);
+        assert!(list_objects(&ctsemeta, Some("CString"), None).is_empty());
+        assert_eq!(list_objects(&ctsemeta, None, Some("Progress")).len(), 1);
+
+        ctsemeta.internal_objects.internal_object[0].value =
+            InternalObjectDataValue::CString("x".repeat(100));
+        assert_eq!(
+            render_objects(&list_objects(&ctsemeta, None, None)),
+            format!(
+                "Object-ID | Type-ID | Type-Name      | Ident-Name    | Value-Preview\n0         \
+                 | 4       | CTalosProgress | m_strName     | CString \"{}\n1         | 4       \
+                 | CTalosProgress | TalosProgress | Struct, 4 members\n",
+                "x".repeat(51)
+            )
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::signature_stream::{starts_with_gz_magic, starts_with_signature_stream_magic};
+use crate::steam_id::steam_userid;
+
+// Saves are never nested deeper than this below a game's folders
+const MAX_DEPTH: usize = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Game {
+    #[default]
+    Talos,
+    Talos2,
+    Sam3,
+    SamHd,
+}
+
+impl Game {
+    pub const ALL: [Self; 4] = [Self::Talos, Self::Talos2, Self::Sam3, Self::SamHd];
+
+    pub fn steam_app_ids(self) -> &'static [u32] {
+        match self {
+            Self::Talos => &[257510],
+            Self::Talos2 => &[835960],
+            Self::Sam3 => &[41070],
+            Self::SamHd => &[41000, 41010],
+        }
+    }
+
+    // Folder names under steamapps/common and GOG's games folder
+    pub fn install_dirs(self) -> &'static [&'static str] {
+        match self {
+            Self::Talos => &["The Talos Principle"],
+            Self::Talos2 => &["The Talos Principle 2"],
+            Self::Sam3 => &["Serious Sam 3"],
+            Self::SamHd => &[
+                "Serious Sam HD The First Encounter",
+                "Serious Sam HD The Second Encounter",
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Talos => write!(f, "talos"),
+            Self::Talos2 => write!(f, "talos2"),
+            Self::Sam3 => write!(f, "sam3"),
+            Self::SamHd => write!(f, "samhd"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    Gz,
+    Raw,
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gz => write!(f, "gz"),
+            Self::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+// Where locate starts looking, tests lay these out in a temporary folder
+pub trait SearchRoots {
+    // Steam installs, the folders with userdata and steamapps in them
+    fn steam(&self) -> Vec<PathBuf>;
+    // Folders games are installed in outside of Steam, like GOG's
+    fn games(&self) -> Vec<PathBuf>;
+}
+
+// The usual places on Windows, Linux, and macOS, found through environment
+// variables. Ones that don't exist are skipped when searching.
+pub struct SystemRoots;
+
+impl SearchRoots for SystemRoots {
+    fn steam(&self) -> Vec<PathBuf> {
+        let mut roots = Vec::new();
+        for variable in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = std::env::var_os(variable) {
+                roots.push(PathBuf::from(dir).join("Steam"));
+            }
+        }
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            roots.push(home.join(".steam/steam"));
+            roots.push(home.join(".local/share/Steam"));
+            // Flatpak
+            roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+            roots.push(home.join("Library/Application Support/Steam"));
+        }
+        roots
+    }
+
+    fn games(&self) -> Vec<PathBuf> {
+        let mut roots = vec![PathBuf::from("C:\\GOG Games")];
+        for variable in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = std::env::var_os(variable) {
+                roots.push(PathBuf::from(dir).join("GOG Galaxy").join("Games"));
+            }
+        }
+        if let Some(home) = std::env::var_os("HOME") {
+            roots.push(PathBuf::from(home).join("GOG Games"));
+        }
+        roots
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub game: Game,
+    pub size: u64,
+    pub container: Container,
+    // From userdata/<account>/ in the path
+    pub steam_account: Option<u32>,
+    // The account as the signer's userid
+    pub userid: Option<String>,
+}
+
+// The account ID from the closest userdata/<digits> ancestor of a path in a
+// Steam install. Both separators are split on so Windows paths work anywhere.
+pub fn steam_account_from_path(path: &Path) -> Option<u32> {
+    let components = path.to_str()?.split(['/', '\\']).collect::<Vec<_>>();
+    components
+        .windows(2)
+        .rev()
+        .find(|pair| pair[0] == "userdata" && pair[1].bytes().all(|byte| byte.is_ascii_digit()))
+        .and_then(|pair| pair[1].parse().ok())
+}
+
+// The userid the game signs a save in a Steam account's folder with
+pub fn userid_from_path(path: &Path) -> Option<String> {
+    steam_account_from_path(path).map(steam_userid)
+}
+
+// Steam libraries other than the install itself, from the "path" lines of
+// libraryfolders.vdf
+fn steam_libraries(steam: &Path) -> Vec<PathBuf> {
+    let Ok(vdf) = std::fs::read_to_string(steam.join("steamapps/libraryfolders.vdf")) else {
+        return Vec::new();
+    };
+    vdf.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('"').skip(1).step_by(2);
+            (fields.next()? == "path").then_some(())?;
+            Some(PathBuf::from(fields.next()?.replace("\\\\", "\\")))
+        })
+        .collect()
+}
+
+fn detect_container(path: &Path) -> Option<Container> {
+    let mut prefix = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(12)
+        .read_to_end(&mut prefix)
+        .ok()?;
+    if starts_with_gz_magic(&prefix) {
+        Some(Container::Gz)
+    } else if starts_with_signature_stream_magic(&prefix) {
+        Some(Container::Raw)
+    } else {
+        None
+    }
+}
+
+fn find_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                find_files(&path, depth + 1, files);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+// Every file that starts like a save in the folders the games keep them in.
+// That's Steam's userdata/<account>/<app>/ and the game's install folder in
+// every Steam library and GOG folder, and Proton's prefix for the game.
+pub fn locate(roots: &impl SearchRoots, games: &[Game]) -> Vec<Candidate> {
+    let steam = roots.steam();
+    let libraries = steam
+        .iter()
+        .flat_map(|steam| std::iter::once(steam.clone()).chain(steam_libraries(steam)))
+        .collect::<Vec<_>>();
+
+    let mut seen = BTreeSet::new();
+    let mut candidates = Vec::new();
+    for &game in games {
+        let mut dirs = Vec::new();
+        for steam in &steam {
+            let Ok(accounts) = std::fs::read_dir(steam.join("userdata")) else {
+                continue;
+            };
+            let mut accounts = accounts
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect::<Vec<_>>();
+            accounts.sort();
+            for account in accounts {
+                for app in game.steam_app_ids() {
+                    dirs.push(account.join(app.to_string()));
+                }
+            }
+        }
+        for library in &libraries {
+            for install in game.install_dirs() {
+                dirs.push(library.join("steamapps/common").join(install));
+            }
+            for app in game.steam_app_ids() {
+                dirs.push(
+                    library
+                        .join("steamapps/compatdata")
+                        .join(app.to_string())
+                        .join("pfx/drive_c/users/steamuser"),
+                );
+            }
+        }
+        for games_dir in roots.games() {
+            for install in game.install_dirs() {
+                dirs.push(games_dir.join(install));
+            }
+        }
+
+        let mut files = Vec::new();
+        for dir in dirs {
+            find_files(&dir, 0, &mut files);
+        }
+        for path in files {
+            // The same file through a symlinked Steam install
+            if !seen.insert(std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                continue;
+            }
+            let Some(container) = detect_container(&path) else {
+                continue;
+            };
+            let steam_account = steam_account_from_path(&path);
+            candidates.push(Candidate {
+                size: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+                game,
+                container,
+                steam_account,
+                userid: steam_account.map(steam_userid),
+                path,
+            });
+        }
+    }
+    candidates
+}
+
+pub fn render_candidates(candidates: &[Candidate]) -> String {
+    let header = ["Game", "Size", "Container", "Userid", "Path"];
+    let rows = candidates
+        .iter()
+        .map(|candidate| {
+            [
+                candidate.game.to_string(),
+                candidate.size.to_string(),
+                candidate.container.to_string(),
+                candidate.userid.clone().unwrap_or_default(),
+                candidate.path.display().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = header.map(|x| x.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(header.map(|x| x.to_owned())).chain(rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::locate::{
+        Container,
+        Game,
+        SearchRoots,
+        locate,
+        steam_account_from_path,
+        userid_from_path,
+    };
+    use crate::steam_id::steam_userid;
+
+    struct FakeRoots {
+        root: PathBuf,
+    }
+
+    impl SearchRoots for FakeRoots {
+        fn steam(&self) -> Vec<PathBuf> {
+            vec![self.root.join("Steam")]
+        }
+
+        fn games(&self) -> Vec<PathBuf> {
+            vec![self.root.join("GOG Games")]
+        }
+    }
+
+    fn write(root: &Path, path: &str, contents: &[u8]) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn userid() {
+        assert_eq!(steam_userid(0x75d8dea), "1100001075d8dea");
+        assert_eq!(
+            steam_account_from_path(Path::new(
+                "Steam/userdata/123456789/257510/local/PlayerProfile.dat"
+            )),
+            Some(123456789)
+        );
+        assert_eq!(
+            steam_account_from_path(Path::new("Steam/userdata/anonymous/257510")),
+            None
+        );
+        assert_eq!(
+            steam_account_from_path(Path::new("GOG Games/PlayerProfile.dat")),
+            None
+        );
+
+        assert_eq!(
+            userid_from_path(Path::new(
+                "C:\\Program Files (x86)\\Steam\\userdata\\123456789\\257510\\local\\PlayerProfile.dat"
+            ))
+            .as_deref(),
+            Some("1100001075bcd15")
+        );
+        assert_eq!(
+            userid_from_path(Path::new(
+                "/home/user/.local/share/Steam/userdata/123456789/257510/local/PlayerProfile.dat"
+            ))
+            .as_deref(),
+            Some("1100001075bcd15")
+        );
+        // The closest one wins
+        assert_eq!(
+            userid_from_path(Path::new(
+                "/backups/userdata/1/userdata/2/PlayerProfile.dat"
+            ))
+            .as_deref(),
+            Some("110000100000002")
+        );
+        assert_eq!(
+            userid_from_path(Path::new("/home/user/saves/PlayerProfile.dat")),
+            None
+        );
+    }
+
+    #[test]
+    fn locate_in_fake_layout() {
+        let root = std::env::temp_dir().join(format!("sse_locate_{}", std::process::id()));
+        let gz = [0x1F, 0x8B, 8, 0];
+        let raw = b"SIGSTRM12GIS\x05\0\0\0";
+        write(
+            &root,
+            "Steam/userdata/123456789/257510/local/PlayerProfile.dat",
+            &gz,
+        );
+        write(
+            &root,
+            "Steam/userdata/123456789/257510/local/Settings.txt",
+            b"not a save",
+        );
+        write(&root, "Steam/userdata/123456789/41070/local/Game.dat", &gz);
+        write(
+            &root,
+            "Library/steamapps/common/The Talos Principle/Content/Talos/All.dat",
+            raw,
+        );
+        write(
+            &root,
+            "Steam/steamapps/libraryfolders.vdf",
+            format!(
+                "\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n",
+                root.join("Library").display()
+            )
+            .as_bytes(),
+        );
+        write(
+            &root,
+            "Library/steamapps/compatdata/257510/pfx/drive_c/users/steamuser/Documents/Talos/PlayerProfile.dat",
+            &gz,
+        );
+        write(
+            &root,
+            "GOG Games/The Talos Principle/Content/Talos/DLC.dat",
+            raw,
+        );
+        let roots = FakeRoots { root: root.clone() };
+
+        let candidates = locate(&roots, &[Game::Talos]);
+        let found = candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.path.strip_prefix(&root).unwrap().to_owned(),
+                    candidate.container,
+                    candidate.userid.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    PathBuf::from("Steam/userdata/123456789/257510/local/PlayerProfile.dat"),
+                    Container::Gz,
+                    Some("1100001075bcd15"),
+                ),
+                (
+                    PathBuf::from(
+                        "Library/steamapps/common/The Talos Principle/Content/Talos/All.dat"
+                    ),
+                    Container::Raw,
+                    None,
+                ),
+                (
+                    PathBuf::from(
+                        "Library/steamapps/compatdata/257510/pfx/drive_c/users/steamuser/Documents/Talos/PlayerProfile.dat"
+                    ),
+                    Container::Gz,
+                    None,
+                ),
+                (
+                    PathBuf::from("GOG Games/The Talos Principle/Content/Talos/DLC.dat"),
+                    Container::Raw,
+                    None,
+                ),
+            ]
+        );
+        assert_eq!(candidates[0].size, 4);
+        assert_eq!(candidates[0].steam_account, Some(123456789));
+
+        let candidates = locate(&roots, &Game::ALL);
+        assert_eq!(candidates.len(), 5);
+        assert_eq!(candidates[4].game, Game::Sam3);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,24 @@
+// The lib logs through tracing with the tracing feature so events carry
+// structured fields for JSON exporters, and through log otherwise. Without a
+// tracing subscriber the events are still forwarded to log.
+#[cfg(not(feature = "tracing"))]
+pub(crate) use log::warn;
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::warn;
+
+// Like warn! and debug! but with fields before the message, e.g.
+// event!(warn, block_index = 3; "invalid signature"). The fields are only
+// recorded with the tracing feature.
+macro_rules! event {
+    ($level:ident, $($field:ident = $value:expr),+; $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($field = $value),+, $($arg)+);
+        #[cfg(not(feature = "tracing"))]
+        {
+            $(let _ = &$value;)+
+            log::$level!($($arg)+);
+        }
+    }};
+}
+
+pub(crate) use event;
@@ -1,252 +1,2755 @@
+use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufWriter, Cursor};
-use std::path::PathBuf;
+use std::io::{BufRead, Cursor, Read, Seek, StdoutLock, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use binrw::io::BufReader;
 use binrw::{BinRead, BinWrite, Endian};
 use clap::{Parser, Subcommand, ValueEnum};
-use ctsemeta::CTSEMeta;
-use signature_stream::{
+use encoding_rs::Encoding;
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use rsa::rand_core::CryptoRngCore;
+use serious_save_editor::annotate::{
+    exact_floats,
+    string_int64,
+    strip_annotations,
+    to_annotated_value,
+};
+use serious_save_editor::ctsemeta::{CTSEMeta, Metadata, ReferenceIssue};
+use serious_save_editor::diff::SaveDiff;
+use serious_save_editor::graph::ObjectGraph;
+use serious_save_editor::helpers::{
+    ValidationOptions,
+    detect_endian,
+    first_difference,
+    hex_context,
+};
+use serious_save_editor::info::SaveInfo;
+use serious_save_editor::list::{list_objects, render_objects};
+use serious_save_editor::locate::{Game, SystemRoots, locate, render_candidates, userid_from_path};
+use serious_save_editor::output_file::{OutputFile, OutputOptions};
+use serious_save_editor::path::{get_value, set_value};
+use serious_save_editor::signature_stream::{
+    GzOptions,
+    HashMethod,
     KeyRing,
+    MAX_DECOMPRESSED_SIZE,
     SIGN_KEY_GAME_LOCAL_NAME,
-    SignOptions,
-    parse_gz_signature_stream_data,
+    SignOptionsBuilder,
+    SignOptionsWithHash,
+    SignatureStreamError,
+    SignatureStreamReader,
+    SignatureStreamVersion,
+    VerifyOptions,
+    is_multi_member_gz,
+    parse_gz_signature_stream_data_with_max_size,
+    parse_gz_signature_stream_header,
+    parse_multi_gz_signature_stream_data_with_max_size,
     parse_signature_stream_data,
-    write_gz_signature_stream_data,
-    write_signature_stream_data,
+    parse_signature_stream_header,
+    probe_signature_requirements,
+    read_gz_signature_stream_with_max_size,
+    starts_with_gz_magic,
+    starts_with_signature_stream_magic,
+    write_gz_signature_stream,
+    write_gz_signature_stream_data_with_rng,
+    write_signature_stream_data_with_rng,
 };
+use serious_save_editor::steam_id::{UseridFormat, normalize_userid};
+use serious_save_editor::template::{Schema, create_template};
+use serious_save_editor::types::{explain_type, find_type, list_types, render_types};
+use serious_save_editor::validate::{Problem, validate};
+use sha2::{Digest, Sha256};
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ClapEndian {
+    #[clap(alias = "b")]
+    Big,
+    #[clap(alias = "l")]
+    Little,
+}
+
+impl From<ClapEndian> for Endian {
+    fn from(value: ClapEndian) -> Self {
+        match value {
+            ClapEndian::Big => Self::Big,
+            ClapEndian::Little => Self::Little,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ClapGame {
+    Talos,
+    Talos2,
+    Sam3,
+    Samhd,
+}
+
+impl From<ClapGame> for Game {
+    fn from(value: ClapGame) -> Self {
+        match value {
+            ClapGame::Talos => Self::Talos,
+            ClapGame::Talos2 => Self::Talos2,
+            ClapGame::Sam3 => Self::Sam3,
+            ClapGame::Samhd => Self::SamHd,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ClapUseridFormat {
+    Auto,
+    Hex,
+    Steamid64,
+    Steamid3,
+    Account,
+}
+
+impl From<ClapUseridFormat> for UseridFormat {
+    fn from(value: ClapUseridFormat) -> Self {
+        match value {
+            ClapUseridFormat::Auto => Self::Auto,
+            ClapUseridFormat::Hex => Self::Hex,
+            ClapUseridFormat::Steamid64 => Self::SteamId64,
+            ClapUseridFormat::Steamid3 => Self::SteamId3,
+            ClapUseridFormat::Account => Self::AccountId,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Container {
+    Gz,
+    Raw,
+}
+
+// What a save's signatures are checked against
+#[derive(clap::Args)]
+struct SignatureArgs {
+    #[arg(short, long)]
+    memory_stream_name: Option<String>,
+    #[arg(short, long)]
+    userid: Option<String>,
+    /// Defaults to little endian for the signature stream and to the
+    /// CTSEMETA endianness cookie for the payload
+    #[clap(value_enum)]
+    #[arg(short, long)]
+    endian: Option<ClapEndian>,
+    #[arg(short, long)]
+    no_guess_memory_stream_name: bool,
+}
+
+// How to read a save, and write it back for the subcommands that change it
+#[derive(clap::Args)]
+struct SaveArgs {
+    #[command(flatten)]
+    signature: SignatureArgs,
+    #[arg(long)]
+    no_gz: bool,
+}
+
+// How to write a save from a payload, the memory stream name is only guessed
+// when asked for
+#[derive(clap::Args)]
+struct NewSaveArgs {
+    #[arg(short, long)]
+    memory_stream_name: Option<String>,
+    #[arg(short, long)]
+    userid: Option<String>,
+    /// Defaults to little endian for the signature stream and to the
+    /// CTSEMETA endianness cookie for the payload
+    #[clap(value_enum)]
+    #[arg(short, long)]
+    endian: Option<ClapEndian>,
+    #[arg(short, long)]
+    guess_memory_stream_name: bool,
+    #[arg(long)]
+    no_gz: bool,
+}
+
+#[derive(clap::Args)]
+struct SignArgs {
+    #[arg(long)]
+    no_sign: bool,
+    #[arg(short, long, default_value_t = SignatureStreamVersion::LATEST, value_parser = parse_signature_stream_version)]
+    signature_stream_version: SignatureStreamVersion,
+    #[arg(short, long, default_value_t = SIGN_KEY_GAME_LOCAL_NAME.to_string())]
+    key_name: String,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[clap(alias = "x")]
+    Extract(ExtractArgs),
+    #[clap(alias = "c")]
+    Create(CreateArgs),
+    /// Convert a save between little and big endian and re-sign it
+    ConvertEndian(ConvertEndianArgs),
+    /// Wrap or unwrap the gz container without re-signing the signature stream
+    Recompress(RecompressArgs),
+    /// Print the signature stream and CTSEMETA headers of a save
+    Info(InfoArgs),
+    /// Compare the parsed contents of two saves
+    Diff(DiffArgs),
+    /// Wrap a raw CTSEMETA payload in a signed signature stream without
+    /// parsing it
+    SignOnly(SignOnlyArgs),
+    /// Print a one line summary of every object in a save
+    ListObjects(ListObjectsArgs),
+    /// Print the types a save declares
+    ListTypes(ListTypesArgs),
+    /// Print the fully expanded layout of one type
+    ExplainType(ExplainTypeArgs),
+    /// Write the types a save declares as JSON for create-template
+    ExportSchema(ExportSchemaArgs),
+    /// Write extracted JSON with one object of a type, every value zeroed, for
+    /// making a save from scratch with create
+    CreateTemplate(CreateTemplateArgs),
+    /// Copy the objects of one save into another and re-sign it
+    MergeSave(MergeSaveArgs),
+    /// Remove an object, null the pointers to it, and re-sign the save
+    StripObject(StripObjectArgs),
+    /// Write the objects of one type and every other object to two saves,
+    /// merge-save puts them back together
+    SplitByType(SplitByTypeArgs),
+    /// Check every signature in a save, exits with 5 if any is invalid
+    Verify(VerifyArgs),
+    /// Check extracted JSON against its type definitions
+    ///
+    /// Saves are read and checked for tables that refer to types or objects
+    /// they don't declare too, which can only happen to saves made by other
+    /// tools
+    Validate(ValidateArgs),
+    /// Write the pointer graph between objects in Graphviz DOT format
+    Graph(GraphArgs),
+    /// Find objects with a string containing the pattern, ignoring case
+    Search(SearchArgs),
+    /// Print the value at a path like objects/12/m_strName
+    Get(GetArgs),
+    /// Change the value at a path and re-sign the save
+    Set(SetArgs),
+    /// Open a save as JSON in an editor and write it back in place, the
+    /// original is kept as <name>.bak
+    Edit(EditArgs),
+    /// List the saves in the folders Steam and GOG installs keep them in,
+    /// with the userid for ones in a Steam account's folder
+    ///
+    /// --game only looks for that game's saves
+    Locate(LocateArgs),
+}
+
+#[derive(clap::Args)]
+struct ExtractArgs {
+    /// The save to extract followed by the output path, or any number of
+    /// saves with --output-dir. - is stdin or stdout
+    #[arg(required_unless_present = "stdin", num_args = 1..)]
+    player_profile: Vec<PathBuf>,
+    /// Extract every save into this directory in parallel, the outputs are
+    /// named after the inputs
+    #[arg(long, conflicts_with_all = ["show_version", "get_object", "header_only", "stats", "stdin", "stdout"])]
+    output_dir: Option<PathBuf>,
+    /// The paths are a directory of saves and a directory to extract them
+    /// into in parallel, each output is the save's name with --suffix
+    #[arg(long, conflicts_with_all = ["output_dir", "show_version", "get_object", "header_only", "stats", "check_round_trip", "checksum", "stdin", "stdout"])]
+    batch: bool,
+    /// Added to the save names with --batch, defaults to .json, .ctsem,
+    /// or .bin for the raw payload
+    #[arg(long, requires = "batch")]
+    suffix: Option<String>,
+    /// Stop starting new saves after the first failure with --batch or
+    /// --output-dir
+    #[arg(long)]
+    fail_fast: bool,
+    /// Read the save from stdin, the only path is then the output
+    #[arg(long)]
+    stdin: bool,
+    /// Write the extracted save to stdout
+    #[arg(long)]
+    stdout: bool,
+    #[command(flatten)]
+    save: SaveArgs,
+    /// Don't take the userid from a Steam userdata/<account>/ folder in
+    /// the save's path
+    #[arg(long)]
+    no_guess_userid: bool,
+    #[arg(short, long)]
+    json: bool,
+    /// Write the compact .ctsem text format instead of JSON, create
+    /// --pretty reads it back
+    #[arg(long, conflicts_with = "json")]
+    pretty: bool,
+    /// Print the game version the save was written by instead of extracting
+    #[arg(long)]
+    show_version: bool,
+    /// Print the signature stream header as JSON instead of extracting,
+    /// nothing after the header is read
+    #[arg(long, conflicts_with_all = ["show_version", "get_object", "stats"])]
+    header_only: bool,
+    /// Print the JSON of the object with this ident name instead of
+    /// extracting
+    #[arg(long)]
+    get_object: Option<String>,
+    /// Print how many objects there are of each type instead of extracting
+    #[arg(long, conflicts_with_all = ["show_version", "get_object"])]
+    stats: bool,
+    /// Check that the save is written back byte for byte the same instead
+    /// of extracting, exits with 7 if it isn't. 2 means the file couldn't
+    /// be read like for every other subcommand
+    #[arg(long, conflicts_with_all = ["show_version", "get_object", "stats", "header_only", "output_dir"])]
+    check_round_trip: bool,
+    /// Print the SHA-256 of the payload like sha256sum instead of
+    /// extracting, it stays the same when the game saves without changes
+    #[arg(long, conflicts_with_all = ["show_version", "get_object", "stats", "header_only", "check_round_trip", "output_dir"])]
+    checksum: bool,
+    /// Read CStrings in this encoding instead of UTF-8, like windows-1252
+    /// for saves from some localized installs
+    #[arg(long, value_parser = parse_encoding)]
+    encoding: Option<&'static Encoding>,
+    /// Add the type name of the object each pointer points to to the JSON,
+    /// create ignores these
+    #[arg(long)]
+    annotate: bool,
+    /// Write FLOATs as {"f": 1.5, "bits": 1069547520} so they survive
+    /// editing tools that round numbers, create prefers the bits
+    #[arg(long)]
+    exact_floats: bool,
+    /// Write UQUADs and SQUADs as decimal strings so tools that read
+    /// numbers as doubles don't round them, create accepts either
+    #[arg(long)]
+    string_int64: bool,
+    /// Lift the string and list length limits for saves known to be
+    /// corrupt, objects after one that can't be read are dropped with a
+    /// warning
+    #[arg(long)]
+    no_validate: bool,
+    /// Fail instead of warning when the save is inconsistent with itself,
+    /// like INFO counts that don't match the tables
+    #[arg(long, conflicts_with = "no_validate")]
+    strict: bool,
+    /// Check the save like the validate subcommand does, the output is
+    /// still written but problems exit with 6
+    #[arg(long, conflicts_with = "no_validate")]
+    validate: bool,
+}
+
+#[derive(clap::Args)]
+struct CreateArgs {
+    /// - is stdin
+    player_profile_extracted: Option<PathBuf>,
+    /// - is stdout
+    player_profile: Option<PathBuf>,
+    /// Read the extracted save from stdin, the only path is then the output
+    #[arg(long)]
+    stdin: bool,
+    /// Write the save to stdout
+    #[arg(long)]
+    stdout: bool,
+    /// The paths are a directory of extracted saves and a directory to
+    /// create them in in parallel, only names ending with --suffix are
+    /// used and each output is the name without it
+    #[arg(long, conflicts_with_all = ["stdin", "stdout", "dry_run", "checksum"])]
+    batch: bool,
+    /// Taken off the extracted save names with --batch, defaults to .json,
+    /// .ctsem, or .bin for the raw payload
+    #[arg(long, requires = "batch")]
+    suffix: Option<String>,
+    /// Stop starting new saves after the first failure with --batch
+    #[arg(long, requires = "batch")]
+    fail_fast: bool,
+    #[command(flatten)]
+    save: NewSaveArgs,
+    /// Don't take the userid from a Steam userdata/<account>/ folder in
+    /// the save's path
+    #[arg(long)]
+    no_guess_userid: bool,
+    #[command(flatten)]
+    sign: SignArgs,
+    #[arg(short, long)]
+    json: bool,
+    /// Read the .ctsem text format written by extract --pretty
+    #[arg(long, conflicts_with = "json")]
+    pretty: bool,
+    /// Write CStrings in this encoding instead of UTF-8, the same one
+    /// given to extract
+    #[arg(long, value_parser = parse_encoding)]
+    encoding: Option<&'static Encoding>,
+    /// sha1, tiger, or sha256, the game writes sha1 but reads all three
+    #[arg(long, default_value_t)]
+    hash_method: HashMethod,
+    /// Build the save in memory and print its size and SHA-256 instead of
+    /// writing it
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the SHA-256 of the payload like sha256sum after writing, the
+    /// same as extract --checksum prints for the new save
+    #[arg(long, conflicts_with_all = ["stdout", "dry_run"])]
+    checksum: bool,
+    /// Write a version 1 signature stream with no signature fields at
+    /// all, only some local/dev configurations of the game accept these
+    #[arg(long, conflicts_with = "signature_stream_version")]
+    strip_signatures: bool,
+    /// Don't check the JSON against the type definitions before writing
+    #[arg(long)]
+    no_validate: bool,
+    /// Remove the editor only sections, for turning editor exports into
+    /// runtime saves
+    #[arg(long)]
+    strip_edit_data: bool,
+    /// Write the save with the next CTSEMETA version and no version string,
+    /// for checking whether the game accepts it
+    #[arg(long)]
+    increment_version: bool,
+    /// Add this to every object and ident ID and the pointers to them, so
+    /// the objects don't collide with another save's when merging by hand
+    #[arg(long)]
+    object_id_offset: Option<u32>,
+}
+
+#[derive(clap::Args)]
+struct ConvertEndianArgs {
+    input: PathBuf,
+    output: PathBuf,
+    #[clap(value_enum)]
+    #[arg(short, long)]
+    target_endian: ClapEndian,
+    /// Endianness of the input signature stream, the payload endianness is
+    /// detected from the CTSEMETA cookie when possible
+    #[clap(value_enum)]
+    #[arg(long, default_value_t = ClapEndian::Little)]
+    source_endian: ClapEndian,
+    #[arg(short, long)]
+    memory_stream_name: Option<String>,
+    #[arg(short, long)]
+    userid: Option<String>,
+    #[arg(short, long)]
+    guess_memory_stream_name: bool,
+    #[command(flatten)]
+    sign: SignArgs,
+    #[arg(long)]
+    no_gz: bool,
+}
+
+#[derive(clap::Args)]
+struct RecompressArgs {
+    input: PathBuf,
+    output: PathBuf,
+    #[clap(value_enum)]
+    #[arg(long)]
+    to: Container,
+}
+
+#[derive(clap::Args)]
+struct InfoArgs {
+    player_profile: PathBuf,
+    #[command(flatten)]
+    signature: SignatureArgs,
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct DiffArgs {
+    player_profile_a: PathBuf,
+    player_profile_b: PathBuf,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct SignOnlyArgs {
+    input: PathBuf,
+    output: PathBuf,
+    #[command(flatten)]
+    save: NewSaveArgs,
+    #[arg(short, long, default_value_t = SignatureStreamVersion::LATEST, value_parser = parse_signature_stream_version)]
+    signature_stream_version: SignatureStreamVersion,
+    #[arg(short, long, default_value_t = SIGN_KEY_GAME_LOCAL_NAME.to_string())]
+    key_name: String,
+}
+
+#[derive(clap::Args)]
+struct ListObjectsArgs {
+    player_profile: PathBuf,
+    /// Only list objects whose type name contains this
+    #[arg(short, long, alias = "filter")]
+    filter_type: Option<String>,
+    /// Only list objects whose ident name contains this
+    #[arg(long)]
+    filter_ident: Option<String>,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct ListTypesArgs {
+    player_profile: PathBuf,
+    /// How many levels of nested struct members to expand
+    #[arg(short, long, default_value_t = 1)]
+    depth: usize,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct ExplainTypeArgs {
+    player_profile: PathBuf,
+    type_name_or_id: String,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct ExportSchemaArgs {
+    player_profile: PathBuf,
+    /// Print to stdout when not given
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    save: SaveArgs,
+}
+
+#[derive(clap::Args)]
+struct CreateTemplateArgs {
+    schema: PathBuf,
+    type_name_or_id: String,
+    /// Print to stdout when not given
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct MergeSaveArgs {
+    base: PathBuf,
+    donor: PathBuf,
+    output: PathBuf,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[command(flatten)]
+    sign: SignArgs,
+}
+
+#[derive(clap::Args)]
+struct StripObjectArgs {
+    player_profile: PathBuf,
+    object: u32,
+    output: PathBuf,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[command(flatten)]
+    sign: SignArgs,
+}
+
+#[derive(clap::Args)]
+struct SplitByTypeArgs {
+    player_profile: PathBuf,
+    type_name: String,
+    matching_output: PathBuf,
+    rest_output: PathBuf,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[command(flatten)]
+    sign: SignArgs,
+}
+
+#[derive(clap::Args)]
+struct VerifyArgs {
+    player_profile: PathBuf,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct ValidateArgs {
+    /// Extracted JSON or a save
+    player_profile_extracted: PathBuf,
+    #[command(flatten)]
+    signature: SignatureArgs,
+}
+
+#[derive(clap::Args)]
+struct GraphArgs {
+    player_profile: PathBuf,
+    /// Print to stdout when not given
+    output: Option<PathBuf>,
+    /// Only include objects reachable from this object
+    #[arg(long)]
+    root: Option<u32>,
+    #[arg(long)]
+    max_nodes: Option<usize>,
+    #[command(flatten)]
+    save: SaveArgs,
+}
+
+#[derive(clap::Args)]
+struct SearchArgs {
+    player_profile: PathBuf,
+    #[arg(long)]
+    string: String,
+    #[command(flatten)]
+    save: SaveArgs,
+}
+
+#[derive(clap::Args)]
+struct GetArgs {
+    player_profile: PathBuf,
+    path: String,
+    #[command(flatten)]
+    save: SaveArgs,
+}
+
+#[derive(clap::Args)]
+struct SetArgs {
+    player_profile: PathBuf,
+    path: String,
+    value: String,
+    /// Write the edited save here instead of overwriting the input
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    save: SaveArgs,
+    #[command(flatten)]
+    sign: SignArgs,
+}
+
+#[derive(clap::Args)]
+struct EditArgs {
+    player_profile: PathBuf,
+    /// Run this instead of $VISUAL or $EDITOR, through the shell so it can
+    /// have arguments, the JSON path is added as the last argument
+    #[arg(long)]
+    editor: Option<String>,
+    #[command(flatten)]
+    save: SaveArgs,
+    /// Don't take the userid from a Steam userdata/<account>/ folder in
+    /// the save's path
+    #[arg(long)]
+    no_guess_userid: bool,
+    #[command(flatten)]
+    sign: SignArgs,
+}
+
+#[derive(clap::Args)]
+struct LocateArgs {
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Commands,
+    /// json writes one JSON object per log line with structured fields, it
+    /// needs the tracing feature
+    #[clap(value_enum)]
+    #[arg(long, global = true, default_value = "text")]
+    log_format: LogFormat,
+    /// Refuse gzipped saves that decompress to more than this many bytes
+    #[arg(long, global = true, default_value_t = MAX_DECOMPRESSED_SIZE)]
+    max_size: u64,
+    /// A JSON object of save file names and their memory stream names, like
+    /// {"MyMod_Save.dat": "<memory stream:MyMod_Save.dat>"}, checked before
+    /// the built in guesses
+    #[arg(long, global = true)]
+    memory_stream_name_db: Option<PathBuf>,
+    /// Write this header salt and seed the signing rng with it, so the same
+    /// input always makes the same file. Decimal or 0x hex, only for tests
+    /// and diffing since a fixed salt weakens replay protection
+    #[arg(long, global = true, value_parser = parse_salt)]
+    salt: Option<u32>,
+    /// Write outputs directly instead of to a temporary file that replaces
+    /// them once complete
+    #[arg(long, global = true)]
+    no_atomic: bool,
+    /// Keep a file being replaced as <name>.bak
+    #[arg(long, global = true)]
+    backup: bool,
+    /// How many saves to process at once, defaults to one per core
+    #[arg(short = 'J', long, global = true)]
+    jobs: Option<usize>,
+    /// The game the saves are from, picks the memory stream names guessed
+    /// from file names. Defaults to talos
+    #[clap(value_enum)]
+    #[arg(long, global = true)]
+    game: Option<ClapGame>,
+    /// How to read --userid, auto tells the formats apart by shape
+    #[clap(value_enum)]
+    #[arg(long, global = true, default_value = "auto")]
+    userid_format: ClapUseridFormat,
+}
+
+impl Commands {
+    fn userid_mut(&mut self) -> Option<&mut Option<String>> {
+        let signature = match self {
+            Self::Extract(ExtractArgs { save, .. })
+            | Self::Diff(DiffArgs { save, .. })
+            | Self::ListObjects(ListObjectsArgs { save, .. })
+            | Self::ListTypes(ListTypesArgs { save, .. })
+            | Self::ExplainType(ExplainTypeArgs { save, .. })
+            | Self::ExportSchema(ExportSchemaArgs { save, .. })
+            | Self::MergeSave(MergeSaveArgs { save, .. })
+            | Self::StripObject(StripObjectArgs { save, .. })
+            | Self::SplitByType(SplitByTypeArgs { save, .. })
+            | Self::Verify(VerifyArgs { save, .. })
+            | Self::Graph(GraphArgs { save, .. })
+            | Self::Search(SearchArgs { save, .. })
+            | Self::Get(GetArgs { save, .. })
+            | Self::Set(SetArgs { save, .. })
+            | Self::Edit(EditArgs { save, .. }) => &mut save.signature,
+            Self::Info(InfoArgs { signature, .. })
+            | Self::Validate(ValidateArgs { signature, .. }) => signature,
+            Self::Create(CreateArgs { save, .. }) | Self::SignOnly(SignOnlyArgs { save, .. }) => {
+                return Some(&mut save.userid);
+            }
+            Self::ConvertEndian(ConvertEndianArgs { userid, .. }) => return Some(userid),
+            Self::Recompress(_) | Self::CreateTemplate(_) | Self::Locate(_) => return None,
+        };
+        Some(&mut signature.userid)
+    }
+}
+
+fn parse_encoding(s: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(s.as_bytes())
+        .ok_or_else(|| format!("unknown encoding {:?}, like windows-1252 or shift_jis", s))
+}
+
+fn parse_salt(s: &str) -> Result<u32, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| {
+        format!(
+            "salt must be a 32 bit decimal or 0x hex number, got {:?}",
+            s
+        )
+    })
+}
+
+fn parse_signature_stream_version(s: &str) -> Result<SignatureStreamVersion, String> {
+    let version = s
+        .parse::<u32>()
+        .map_err(|_| "version must be between 1 and 5".to_owned())?;
+    SignatureStreamVersion::try_from(version).map_err(|e| e.to_string())
+}
+
+// From --memory-stream-name-db, keyed by file name, and the game whose
+// guesses are used for file names that aren't in it
+#[derive(Default)]
+struct MemoryStreamNames {
+    names: HashMap<String, String>,
+    game: Game,
+}
+
+impl MemoryStreamNames {
+    // Entries that could never be used are warned about and skipped
+    fn load(path: &Path) -> Result<Self> {
+        let db: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let mut names = HashMap::new();
+        for (file_name, memory_stream_name) in db {
+            if file_name.contains(['/', '\\']) {
+                warn!(
+                    "{}: {:?} is a path, only file names are looked up",
+                    path.display(),
+                    file_name
+                );
+                continue;
+            }
+            match memory_stream_name {
+                serde_json::Value::String(memory_stream_name) => {
+                    names.insert(file_name, memory_stream_name);
+                }
+                value => warn!(
+                    "{}: the memory stream name for {:?} isn't a string: {}",
+                    path.display(),
+                    file_name,
+                    value
+                ),
+            }
+        }
+        Ok(Self {
+            names,
+            game: Game::default(),
+        })
+    }
+
+    fn guess(&self, file_name: Option<&OsStr>) -> Option<String> {
+        file_name
+            .and_then(OsStr::to_str)
+            .and_then(|file_name| self.names.get(file_name).cloned())
+            .or_else(|| try_guess_memory_stream_name(file_name, self.game))
+    }
+}
+
+// Says what to pass when the signature covers a memory stream name or userid
+// that wasn't given, only the header is read. Reading the save reports the
+// error if it isn't one.
+fn hint_signature_requirements(
+    path: &Path,
+    endian: Endian,
+    options: VerifyOptions,
+    memory_stream_names: &MemoryStreamNames,
+) {
+    let Ok(file) = File::open(path) else {
+        return;
+    };
+    let Ok(requirements) = probe_signature_requirements(&mut BufReader::new(file), endian) else {
+        return;
+    };
+    let missing = requirements.missing(options);
+    if missing.memory_stream_name {
+        let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+        let mut names = Vec::new();
+        for (_, memory_stream_name) in memory_stream_name_guesses(memory_stream_names.game) {
+            let name = memory_stream_name.replace("{file}", file_name);
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if names.is_empty() {
+            warn!("this save's signature covers a memory stream name, pass it with -m");
+        } else {
+            warn!(
+                "this save's signature covers a memory stream name, pass it with -m, likely ones are {}",
+                names
+                    .iter()
+                    .map(|name| format!("{:?}", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+    if missing.userid {
+        warn!(
+            "this save's signature covers a userid, pass the Steam ID of the account that wrote it with -u"
+        );
+    }
+}
+
+// The userid for a save in a Steam account's userdata folder. Relative paths
+// are made absolute first so a save in the current directory is found too.
+fn guess_userid(path: &Path) -> Option<String> {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+    let userid = userid_from_path(&path)?;
+    info!("using userid {} from {}", userid, path.display());
+    Some(userid)
+}
+
+// What a file name has to contain and the memory stream name of the saves that
+// do, {file} is replaced with the file name. Saves the game writes are memory
+// streams named after the file, the ones it ships are content paths.
+type MemoryStreamNameGuesses = &'static [(&'static [&'static str], &'static str)];
+
+const TALOS_MEMORY_STREAM_NAMES: MemoryStreamNameGuesses = &[
+    (
+        &["PlayerProfile", "unrestricted"],
+        "<memory stream:PlayerProfile_unrestricted.dat>",
+    ),
+    (&["PlayerProfile"], "<memory stream:PlayerProfile.dat>"),
+    (&["AutoSave"], "<memory stream:AutoSave.dat>"),
+    (&["QuickSave"], "<memory stream:QuickSave.dat>"),
+    (&["SaveGame"], "<memory stream:{file}>"),
+    (&["All"], "Content/Talos/All.dat"),
+    (&["DLC"], "Content/Talos/DLC.dat"),
+];
+
+const SAM3_MEMORY_STREAM_NAMES: MemoryStreamNameGuesses = &[
+    (&["PlayerProfile"], "<memory stream:PlayerProfile.dat>"),
+    (&["AutoSave"], "<memory stream:AutoSave.dat>"),
+    (&["QuickSave"], "<memory stream:QuickSave.dat>"),
+    (&["SaveGame"], "<memory stream:{file}>"),
+    (&["All"], "Content/SeriousSam3/All.dat"),
+    (&["DLC"], "Content/SeriousSam3/DLC.dat"),
+];
+
+const SAMHD_MEMORY_STREAM_NAMES: MemoryStreamNameGuesses = &[
+    (&["PlayerProfile"], "<memory stream:PlayerProfile.dat>"),
+    (&["AutoSave"], "<memory stream:AutoSave.dat>"),
+    (&["QuickSave"], "<memory stream:QuickSave.dat>"),
+    (&["SaveGame"], "<memory stream:{file}>"),
+    (&["All"], "Content/SeriousSamHD/All.dat"),
+    (&["DLC"], "Content/SeriousSamHD/DLC.dat"),
+];
+
+fn memory_stream_name_guesses(game: Game) -> MemoryStreamNameGuesses {
+    match game {
+        Game::Talos => TALOS_MEMORY_STREAM_NAMES,
+        // An Unreal Engine game, its saves aren't signature streams
+        Game::Talos2 => &[],
+        Game::Sam3 => SAM3_MEMORY_STREAM_NAMES,
+        Game::SamHd => SAMHD_MEMORY_STREAM_NAMES,
+    }
+}
+
+// The first entry in the game's table that matches wins
+fn try_guess_memory_stream_name(file_name: Option<&OsStr>, game: Game) -> Option<String> {
+    let file_name = file_name?.to_str()?;
+    memory_stream_name_guesses(game)
+        .iter()
+        .find(|(patterns, _)| patterns.iter().all(|pattern| file_name.contains(pattern)))
+        .map(|(_, memory_stream_name)| memory_stream_name.replace("{file}", file_name))
+}
+
+// Splits the positional paths into the input and output, None is stdin or
+// stdout. A path of - is the same as --stdin or --stdout.
+fn stdio_paths(
+    paths: impl IntoIterator<Item = PathBuf>,
+    stdin: bool,
+    mut stdout: bool,
+    needs_output: bool,
+) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
+    let is_stdio = |path: &PathBuf| path.as_os_str() == "-";
+    let mut paths = paths.into_iter();
+    let input = if stdin {
+        None
+    } else {
+        Some(
+            paths
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("no input path, pass one or use --stdin"))?,
+        )
+        .filter(|path| !is_stdio(path))
+    };
+    let output = if stdout {
+        if let Some(path) = paths.next().filter(|path| !is_stdio(path)) {
+            warn!("--stdout was given, not writing to {}", path.display());
+        }
+        None
+    } else {
+        match paths.next() {
+            Some(path) if is_stdio(&path) => {
+                stdout = true;
+                None
+            }
+            path => path,
+        }
+    };
+    if needs_output && !stdout && output.is_none() {
+        anyhow::bail!("no output path, pass one or use --stdout");
+    }
+    if let Some(path) = paths.next() {
+        anyhow::bail!("unexpected path {}", path.display());
+    }
+    Ok((input, output))
+}
+
+fn batch_suffix(json: bool, pretty: bool) -> &'static str {
+    if json {
+        ".json"
+    } else if pretty {
+        ".ctsem"
+    } else {
+        ".bin"
+    }
+}
+
+// Pairs every file in input_dir with its output in output_dir. Extracting adds
+// the suffix to the name and skips names that already have it, creating takes
+// it off and skips names without it.
+fn batch_paths(
+    input_dir: &Path,
+    output_dir: &Path,
+    suffix: &str,
+    extracting: bool,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut jobs = Vec::new();
+    for entry in std::fs::read_dir(input_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str() else {
+            warn!("skipping {}, its name isn't UTF-8", entry.path().display());
+            continue;
+        };
+        let output_name = if extracting {
+            if !suffix.is_empty() && name.ends_with(suffix) {
+                continue;
+            }
+            format!("{}{}", name, suffix)
+        } else {
+            match name.strip_suffix(suffix) {
+                Some(stem) if !stem.is_empty() => stem.to_owned(),
+                _ => continue,
+            }
+        };
+        jobs.push((entry.path(), output_dir.join(output_name)));
+    }
+    jobs.sort();
+    std::fs::create_dir_all(output_dir)?;
+    Ok(jobs)
+}
+
+// Runs f on every input and output in parallel. A failure doesn't stop the
+// others unless fail_fast, then saves that haven't started are skipped. How
+// each save went is printed at the end.
+fn run_batch(
+    jobs: &[(PathBuf, PathBuf)],
+    fail_fast: bool,
+    action: &str,
+    f: impl Fn(&Path, &Path) -> Result<()> + Sync,
+) -> Result<()> {
+    let failed = AtomicBool::new(false);
+    let results = jobs
+        .par_iter()
+        .map(|(input, output)| {
+            if fail_fast && failed.load(Ordering::Relaxed) {
+                return None;
+            }
+            let result = f(input, output);
+            if result.is_err() {
+                failed.store(true, Ordering::Relaxed);
+            }
+            Some(result)
+        })
+        .collect::<Vec<_>>();
+
+    let mut failures = 0;
+    let mut skipped = 0;
+    for ((input, output), result) in jobs.iter().zip(&results) {
+        match result {
+            Some(Ok(())) => println!("{}: ok, wrote {}", input.display(), output.display()),
+            Some(Err(e)) => {
+                failures += 1;
+                println!("{}: failed, {:#}", input.display(), e);
+            }
+            None => {
+                skipped += 1;
+                println!("{}: skipped", input.display());
+            }
+        }
+    }
+    if failures > 0 {
+        anyhow::bail!(
+            "{} of {} saves failed to {}, {} skipped",
+            failures,
+            jobs.len(),
+            action,
+            skipped
+        );
+    }
+    Ok(())
+}
+
+fn read_input(path: Option<&Path>) -> Result<Vec<u8>> {
+    match path {
+        Some(path) => Ok(std::fs::read(path)?),
+        None => {
+            let mut data = Vec::new();
+            std::io::stdin().lock().read_to_end(&mut data)?;
+            Ok(data)
+        }
+    }
+}
+
+// A file written through OutputFile or stdout, finish commits the file
+enum Output {
+    File(OutputFile),
+    Stdout(StdoutLock<'static>),
+}
+
+impl Output {
+    fn finish(self) -> Result<()> {
+        match self {
+            Self::File(file) => file.commit()?,
+            Self::Stdout(mut stdout) => stdout.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::File(file) => file.write(buf),
+            Self::Stdout(stdout) => stdout.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::File(file) => file.flush(),
+            Self::Stdout(stdout) => stdout.flush(),
+        }
+    }
+}
+
+fn write_output(path: Option<&Path>, output_options: OutputOptions) -> Result<Output> {
+    Ok(match path {
+        Some(path) => Output::File(OutputFile::create(path, output_options)?),
+        None => Output::Stdout(std::io::stdout().lock()),
+    })
+}
+
+fn read_save(
+    path: &Path,
+    no_gz: bool,
+    max_size: u64,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<&String>,
+    userid: Option<&String>,
+) -> Result<Box<[u8]>> {
+    read_save_from(
+        &mut BufReader::new(File::open(path)?),
+        no_gz,
+        max_size,
+        endian,
+        key_ring,
+        memory_stream_name,
+        userid,
+    )
+}
+
+fn read_save_from<R: BufRead>(
+    reader: &mut R,
+    no_gz: bool,
+    max_size: u64,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<&String>,
+    userid: Option<&String>,
+) -> Result<Box<[u8]>> {
+    if no_gz {
+        return Ok(parse_signature_stream_data(
+            reader,
+            endian,
+            key_ring,
+            memory_stream_name,
+            userid,
+        )?);
+    }
+    // Some tools write gzip files in more than one member
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input)?;
+    let data = if is_multi_member_gz(&mut input.as_slice(), max_size)? {
+        parse_multi_gz_signature_stream_data_with_max_size(
+            &mut input.as_slice(),
+            endian,
+            key_ring,
+            memory_stream_name,
+            userid,
+            max_size,
+        )?
+    } else {
+        parse_gz_signature_stream_data_with_max_size(
+            &mut input.as_slice(),
+            endian,
+            key_ring,
+            memory_stream_name,
+            userid,
+            max_size,
+        )?
+    };
+    Ok(data)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_save(
+    path: &Path,
+    no_gz: bool,
+    endian: Endian,
+    sign_options: Option<&SignOptionsWithHash<str, String, String>>,
+    signature_stream_version: SignatureStreamVersion,
+    data: &[u8],
+    salt: Option<u32>,
+    output_options: OutputOptions,
+) -> Result<()> {
+    let mut file = OutputFile::create(path, output_options)?;
+    write_save_to(
+        &mut file,
+        no_gz,
+        endian,
+        sign_options,
+        signature_stream_version,
+        data,
+        salt,
+    )?;
+    Ok(file.commit()?)
+}
+
+fn write_save_to<W: Write + Seek>(
+    writer: &mut W,
+    no_gz: bool,
+    endian: Endian,
+    sign_options: Option<&SignOptionsWithHash<str, String, String>>,
+    signature_stream_version: SignatureStreamVersion,
+    data: &[u8],
+    salt: Option<u32>,
+) -> Result<()> {
+    let mut rng: Box<dyn CryptoRngCore> = match salt {
+        Some(salt) => Box::new(StdRng::seed_from_u64(salt.into())),
+        None => Box::new(rand::thread_rng()),
+    };
+    if no_gz {
+        write_signature_stream_data_with_rng(
+            writer,
+            endian,
+            sign_options,
+            signature_stream_version,
+            data,
+            &mut rng,
+            salt,
+        )?;
+    } else {
+        write_gz_signature_stream_data_with_rng(
+            writer,
+            endian,
+            sign_options,
+            signature_stream_version,
+            &GzOptions::default(),
+            data,
+            &mut rng,
+            salt,
+        )?;
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn check_valid(ctsemeta: &CTSEMeta) -> Result<()> {
+    report_problems(&validate(ctsemeta))
+}
+
+fn report_problems(problems: &[Problem]) -> Result<()> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    for problem in problems {
+        log::error!("{}", problem);
+    }
+    Err(Failure::Validation(problems.len()).into())
+}
+
+// Exit codes scripts can rely on, anything else is 1
+const EXIT_IO: u8 = 2;
+const EXIT_NOT_A_SAVE: u8 = 3;
+const EXIT_PARSE: u8 = 4;
+const EXIT_VERIFICATION: u8 = 5;
+const EXIT_VALIDATION: u8 = 6;
+const EXIT_ROUND_TRIP: u8 = 7;
+
+// Failures whose details were already logged
+#[derive(Debug)]
+enum Failure {
+    Verification,
+    Validation(usize),
+    RoundTrip,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verification => write!(f, "signature verification failed"),
+            Self::Validation(problems) => write!(f, "{} problems found", problems),
+            Self::RoundTrip => write!(f, "the save isn't written back the same"),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn io_exit_code(e: &std::io::Error) -> u8 {
+    match e.kind() {
+        // flate2 reports a bad gzip header or stream this way
+        std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => EXIT_NOT_A_SAVE,
+        _ => EXIT_IO,
+    }
+}
+
+fn signature_stream_exit_code(e: &SignatureStreamError) -> u8 {
+    match e {
+        SignatureStreamError::Io(e) => io_exit_code(e),
+        SignatureStreamError::Parse(_)
+        | SignatureStreamError::BadMagic
+        | SignatureStreamError::InvalidVersion(_)
+        | SignatureStreamError::UnknownHashMethod(_)
+        | SignatureStreamError::EmptyBlocks
+        | SignatureStreamError::TruncatedBlock(_)
+        | SignatureStreamError::NotGzip
+        | SignatureStreamError::NoGzExtraField
+        | SignatureStreamError::NoCTExtraField
+        | SignatureStreamError::TooLarge(_) => EXIT_NOT_A_SAVE,
+        _ => 1,
+    }
+}
+
+// The first error in the chain with a known category decides
+fn exit_code(e: &anyhow::Error) -> u8 {
+    for cause in e.chain() {
+        if let Some(failure) = cause.downcast_ref::<Failure>() {
+            return match failure {
+                Failure::Verification => EXIT_VERIFICATION,
+                Failure::Validation(_) => EXIT_VALIDATION,
+                Failure::RoundTrip => EXIT_ROUND_TRIP,
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<SignatureStreamError>() {
+            return signature_stream_exit_code(e);
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            // SignatureStreamReader errors come wrapped in an io::Error
+            if let Some(e) = e
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<SignatureStreamError>())
+            {
+                return signature_stream_exit_code(e);
+            }
+            return io_exit_code(e);
+        }
+        if let Some(e) = cause.downcast_ref::<binrw::Error>() {
+            return match e {
+                binrw::Error::Io(_) => EXIT_IO,
+                _ => EXIT_PARSE,
+            };
+        }
+        let json_error = cause.downcast_ref::<serde_json::Error>().or_else(|| {
+            cause
+                .downcast_ref::<serde_path_to_error::Error<serde_json::Error>>()
+                .map(serde_path_to_error::Error::inner)
+        });
+        if let Some(e) = json_error {
+            return if e.is_io() { EXIT_IO } else { EXIT_PARSE };
+        }
+    }
+    1
+}
+
+fn detect_payload_endian(data: &[u8], endian: Option<Endian>) -> Endian {
+    endian.unwrap_or_else(|| detect_endian(&mut Cursor::new(data)).unwrap_or(Endian::Little))
+}
+
+fn convert_endian(data: &[u8], source_endian: Endian, target_endian: Endian) -> Result<Vec<u8>> {
+    let mut reader = Cursor::new(data);
+    let source_endian = detect_endian(&mut reader).unwrap_or(source_endian);
+    let ctsemeta =
+        CTSEMeta::read_options(&mut reader, source_endian, ValidationOptions::default())?;
+
+    let mut writer = Cursor::new(Vec::new());
+    ctsemeta.write_options(&mut writer, target_endian, ())?;
+    Ok(writer.into_inner())
+}
+
+// Logs never go to stdout so it can be piped
+#[cfg(not(feature = "tracing"))]
+fn init_logging(log_format: LogFormat) -> Result<()> {
+    if matches!(log_format, LogFormat::Json) {
+        anyhow::bail!("--log-format json needs the tracing feature");
+    }
+    env_logger::Builder::from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "warn"),
+    )
+    .target(env_logger::Target::Stderr)
+    .init();
+    Ok(())
+}
+
+// log records from here and dependencies are forwarded to tracing
+#[cfg(feature = "tracing")]
+fn init_logging(log_format: LogFormat) -> Result<()> {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match log_format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    // Usage errors would be 2 from clap, that's EXIT_IO here
+    let cli = match Args::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn run(mut cli: Args) -> Result<()> {
+    init_logging(cli.log_format)?;
+    // Every subcommand gets the userid in the form the signature hashes
+    if let Some(userid) = cli.command.userid_mut().and_then(Option::as_mut) {
+        let normalized = normalize_userid(userid, cli.userid_format.into())?;
+        if normalized != *userid {
+            info!("using userid {} for {}", normalized, userid);
+            *userid = normalized;
+        }
+    }
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+    let mut memory_stream_names = cli
+        .memory_stream_name_db
+        .as_deref()
+        .map(MemoryStreamNames::load)
+        .transpose()?
+        .unwrap_or_default();
+    memory_stream_names.game = cli.game.map_or(Game::default(), Game::from);
+    let globals = Globals {
+        max_size: cli.max_size,
+        salt: cli.salt,
+        output_options: OutputOptions {
+            no_atomic: cli.no_atomic,
+            backup: cli.backup,
+        },
+        memory_stream_names,
+        key_ring: KeyRing::default(),
+        game: cli.game.map(Game::from),
+    };
+
+    match cli.command {
+        Commands::Extract(args) => args.run(&globals),
+        Commands::Create(args) => args.run(&globals),
+        Commands::ConvertEndian(args) => args.run(&globals),
+        Commands::Recompress(args) => args.run(&globals),
+        Commands::Info(args) => args.run(&globals),
+        Commands::Diff(args) => args.run(&globals),
+        Commands::SignOnly(args) => args.run(&globals),
+        Commands::ListObjects(args) => args.run(&globals),
+        Commands::ListTypes(args) => args.run(&globals),
+        Commands::ExplainType(args) => args.run(&globals),
+        Commands::ExportSchema(args) => args.run(&globals),
+        Commands::CreateTemplate(args) => args.run(&globals),
+        Commands::MergeSave(args) => args.run(&globals),
+        Commands::StripObject(args) => args.run(&globals),
+        Commands::SplitByType(args) => args.run(&globals),
+        Commands::Verify(args) => args.run(&globals),
+        Commands::Validate(args) => args.run(&globals),
+        Commands::Graph(args) => args.run(&globals),
+        Commands::Search(args) => args.run(&globals),
+        Commands::Get(args) => args.run(&globals),
+        Commands::Set(args) => args.run(&globals),
+        Commands::Edit(args) => args.run(&globals),
+        Commands::Locate(args) => args.run(&globals),
+    }
+}
+
+// What the global arguments set up for every subcommand
+struct Globals {
+    max_size: u64,
+    salt: Option<u32>,
+    output_options: OutputOptions,
+    memory_stream_names: MemoryStreamNames,
+    key_ring: KeyRing<'static>,
+    game: Option<Game>,
+}
+
+impl SignatureArgs {
+    fn endian(&self) -> Option<Endian> {
+        self.endian.map(Endian::from)
+    }
+
+    fn stream_endian(&self) -> Endian {
+        self.endian().unwrap_or(Endian::Little)
+    }
+
+    // The memory stream name given, otherwise one guessed from the file name
+    fn memory_stream_name(
+        &self,
+        file_name: Option<&OsStr>,
+        memory_stream_names: &MemoryStreamNames,
+    ) -> Option<String> {
+        self.memory_stream_name.clone().or_else(|| {
+            (!self.no_guess_memory_stream_name)
+                .then(|| memory_stream_names.guess(file_name))
+                .flatten()
+        })
+    }
+}
+
+// Reads a save and parses the payload, its endianness is returned too so it
+// can be written back the same way
+fn read_ctsemeta(path: &Path, save: &SaveArgs, globals: &Globals) -> Result<(CTSEMeta, Endian)> {
+    let signature = &save.signature;
+    let memory_stream_name =
+        signature.memory_stream_name(path.file_name(), &globals.memory_stream_names);
+    let signature_stream_data = read_save(
+        path,
+        save.no_gz,
+        globals.max_size,
+        signature.stream_endian(),
+        &globals.key_ring,
+        memory_stream_name.as_ref(),
+        signature.userid.as_ref(),
+    )?;
+    let payload_endian = detect_payload_endian(&signature_stream_data, signature.endian());
+    let ctsemeta = CTSEMeta::read_options(
+        &mut Cursor::new(&signature_stream_data),
+        payload_endian,
+        ValidationOptions::default(),
+    )?;
+    Ok((ctsemeta, payload_endian))
+}
+
+// Writes a payload back the way the save was read, signed unless --no-sign
+fn write_signed(
+    path: &Path,
+    data: &[u8],
+    sign: &SignArgs,
+    save: &SaveArgs,
+    memory_stream_name: Option<&String>,
+    globals: &Globals,
+    output_options: OutputOptions,
+) -> Result<()> {
+    let sign_options_builder = SignOptionsBuilder::new()
+        .key_ring(&globals.key_ring)
+        .sign_key_name(&sign.key_name)
+        .memory_stream_name(memory_stream_name)
+        .userid(save.signature.userid.as_ref());
+    let sign_options = (!sign.no_sign)
+        .then(|| sign_options_builder.build())
+        .transpose()?;
+    write_save(
+        path,
+        save.no_gz,
+        save.signature.stream_endian(),
+        sign_options.as_ref(),
+        sign.signature_stream_version,
+        data,
+        globals.salt,
+        output_options,
+    )
+}
+
+impl ExtractArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let Self {
+            player_profile,
+            output_dir,
+            batch,
+            suffix,
+            fail_fast,
+            stdin,
+            stdout,
+            save: SaveArgs { signature, no_gz },
+            no_guess_userid,
+            json,
+            pretty,
+            show_version,
+            header_only,
+            get_object,
+            stats,
+            check_round_trip,
+            checksum,
+            encoding,
+            annotate,
+            exact_floats: exact,
+            string_int64: int64_strings,
+            no_validate,
+            strict,
+            validate,
+        } = self;
+        let Globals {
+            max_size,
+            output_options,
+            ref memory_stream_names,
+            ref key_ring,
+            ..
+        } = *globals;
+        let endian = signature.endian();
+        let validation_options = if no_validate {
+            ValidationOptions::NONE
+        } else {
+            ValidationOptions {
+                strict,
+                ..ValidationOptions::default()
+            }
+        };
+        let stream_endian = endian.unwrap_or(Endian::Little);
+        let read = |player_profile: Option<&Path>| {
+            let memory_stream_name = signature.memory_stream_name(
+                player_profile.and_then(Path::file_name),
+                memory_stream_names,
+            );
+            let userid = signature.userid.clone().or_else(|| {
+                (!no_guess_userid)
+                    .then(|| guess_userid(player_profile?))
+                    .flatten()
+            });
+            if let Some(player_profile) = player_profile {
+                hint_signature_requirements(
+                    player_profile,
+                    stream_endian,
+                    VerifyOptions {
+                        memory_stream_name: memory_stream_name.as_deref(),
+                        userid: userid.as_deref(),
+                    },
+                    memory_stream_names,
+                );
+            }
+            match player_profile {
+                Some(player_profile) => read_save(
+                    player_profile,
+                    no_gz,
+                    max_size,
+                    stream_endian,
+                    key_ring,
+                    memory_stream_name.as_ref(),
+                    userid.as_ref(),
+                ),
+                None => read_save_from(
+                    &mut std::io::stdin().lock(),
+                    no_gz,
+                    max_size,
+                    stream_endian,
+                    key_ring,
+                    memory_stream_name.as_ref(),
+                    userid.as_ref(),
+                ),
+            }
+        };
+        let extract = |player_profile: Option<&Path>, player_profile_extracted: Option<&Path>| {
+            let signature_stream_data = read(player_profile)?;
+            let payload_endian = detect_payload_endian(&signature_stream_data, endian);
+            let ctsemeta = (json || pretty || validate)
+                .then(|| {
+                    CTSEMeta::read_options(
+                        &mut Cursor::new(&signature_stream_data),
+                        payload_endian,
+                        validation_options,
+                    )
+                })
+                .transpose();
+            // Without validation the payload is still worth having even if
+            // not even the objects before the corruption can be read
+            let ctsemeta = match ctsemeta {
+                Err(e) if no_validate => {
+                    warn!("failed to parse the payload, writing it raw instead: {}", e);
+                    None
+                }
+                ctsemeta => ctsemeta?,
+            };
+            let problems = match &ctsemeta {
+                Some(ctsemeta) if validate => ctsemeta.full_validate(),
+                _ => Vec::new(),
+            };
+            if let Some(mut ctsemeta) = ctsemeta.filter(|_| json || pretty) {
+                if let Some(encoding) = encoding {
+                    ctsemeta.decode_strings(encoding);
+                }
+                let mut writer = write_output(player_profile_extracted, output_options)?;
+                if pretty {
+                    writer.write_all(ctsemeta.to_ctsem()?.as_bytes())?;
+                } else if annotate || exact || int64_strings {
+                    let mut value = if annotate {
+                        to_annotated_value(&ctsemeta)?
+                    } else {
+                        serde_json::to_value(&ctsemeta)?
+                    };
+                    if exact {
+                        exact_floats(&mut value);
+                    }
+                    if int64_strings {
+                        string_int64(&mut value);
+                    }
+                    serde_json::to_writer_pretty(&mut writer, &value)?;
+                } else {
+                    serde_json::to_writer_pretty(&mut writer, &ctsemeta)?;
+                }
+                writer.finish()?;
+            } else {
+                let mut writer = write_output(player_profile_extracted, output_options)?;
+                writer.write_all(&signature_stream_data)?;
+                writer.finish()?;
+            }
+            report_problems(&problems)
+        };
+
+        if batch {
+            let [input_dir, output_dir] =
+                <[PathBuf; 2]>::try_from(player_profile).map_err(|_| {
+                    anyhow::anyhow!("--batch takes a directory of saves and an output directory")
+                })?;
+            let suffix = suffix.unwrap_or_else(|| batch_suffix(json, pretty).to_owned());
+            let jobs = batch_paths(&input_dir, &output_dir, &suffix, true)?;
+            return run_batch(&jobs, fail_fast, "extract", |input, output| {
+                extract(Some(input), Some(output))
+            });
+        }
+
+        if let Some(output_dir) = output_dir {
+            let extension = if json {
+                "json"
+            } else if pretty {
+                "ctsem"
+            } else {
+                "dat"
+            };
+            let jobs = player_profile
+                .iter()
+                .map(|player_profile| {
+                    let stem = player_profile.file_stem().unwrap_or_default();
+                    (
+                        player_profile.clone(),
+                        output_dir.join(stem).with_extension(extension),
+                    )
+                })
+                .collect::<Vec<_>>();
+            return run_batch(&jobs, fail_fast, "extract", |input, output| {
+                extract(Some(input), Some(output))
+            });
+        }
+
+        let prints = show_version
+            || header_only
+            || get_object.is_some()
+            || stats
+            || check_round_trip
+            || checksum;
+        if !stdin && player_profile.len() > 2 {
+            anyhow::bail!(
+                "expected a save and an output path, use --output-dir to extract several saves"
+            );
+        }
+        let (player_profile, player_profile_extracted) =
+            stdio_paths(player_profile, stdin, stdout, !prints)?;
+
+        if header_only {
+            let mut reader: Box<dyn BufRead> = match player_profile {
+                Some(player_profile) => Box::new(BufReader::new(File::open(player_profile)?)),
+                None => Box::new(std::io::stdin().lock()),
+            };
+            let header = if no_gz {
+                parse_signature_stream_header(&mut reader, stream_endian)?
+            } else {
+                parse_gz_signature_stream_header(&mut reader, stream_endian)?
+            };
+            println!("{}", serde_json::to_string_pretty(&header)?);
+            return Ok(());
+        }
+
+        if prints {
+            let signature_stream_data = read(player_profile.as_deref())?;
+            let payload_endian = detect_payload_endian(&signature_stream_data, endian);
+            if show_version {
+                let metadata = Metadata::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    payload_endian,
+                    validation_options,
+                )?;
+                match metadata.parse_version_string() {
+                    Some(game_version) => {
+                        println!("game: {}", game_version.game);
+                        println!("version: {}", game_version.version_text);
+                        println!("build: {}", game_version.build);
+                        println!("platform: {}", game_version.platform);
+                    }
+                    None => {
+                        warn!(
+                            "unrecognised version string {:?}",
+                            metadata.version_string.unwrap_or_default()
+                        );
+                    }
+                }
+            } else if let Some(name) = get_object {
+                let ctsemeta = CTSEMeta::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    payload_endian,
+                    validation_options,
+                )?;
+                let object = ctsemeta
+                    .object_by_name(&name)
+                    .ok_or_else(|| anyhow::anyhow!("no object named {:?}", name))?;
+                println!("{}", serde_json::to_string_pretty(object)?);
+            } else if stats {
+                let ctsemeta = CTSEMeta::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    payload_endian,
+                    validation_options,
+                )?;
+                let mut counts = ctsemeta
+                    .object_count_by_type()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                let width = counts
+                    .iter()
+                    .map(|(name, _)| name.len())
+                    .max()
+                    .unwrap_or_default();
+                for (name, count) in counts {
+                    println!("{:<width$} {}", name, count);
+                }
+            } else if check_round_trip {
+                let ctsemeta = CTSEMeta::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    payload_endian,
+                    validation_options,
+                )?;
+                let mut writer = Cursor::new(Vec::new());
+                ctsemeta.write_options(&mut writer, payload_endian, ())?;
+                let rewritten = writer.into_inner();
+                let Some(offset) = first_difference(&signature_stream_data, &rewritten) else {
+                    println!("round trip ok, {} bytes", rewritten.len());
+                    return Ok(());
+                };
+                println!(
+                    "round trip differs at {:#x}, the save is {} bytes and the rewrite {}",
+                    offset,
+                    signature_stream_data.len(),
+                    rewritten.len()
+                );
+                println!("save:\n{}", hex_context(&signature_stream_data, offset));
+                println!("rewrite:\n{}", hex_context(&rewritten, offset));
+                return Err(Failure::RoundTrip.into());
+            } else if checksum {
+                let ctsemeta = CTSEMeta::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    payload_endian,
+                    validation_options,
+                )?;
+                let name = player_profile
+                    .as_deref()
+                    .map_or("-".into(), Path::to_string_lossy);
+                println!("{}  {}", hex(&ctsemeta.compute_checksum()?), name);
+            }
+            return Ok(());
+        }
+
+        extract(
+            player_profile.as_deref(),
+            player_profile_extracted.as_deref(),
+        )?;
+        Ok(())
+    }
+}
+
+impl CreateArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let Self {
+            player_profile_extracted,
+            player_profile,
+            stdin,
+            stdout,
+            batch,
+            suffix,
+            fail_fast,
+            save:
+                NewSaveArgs {
+                    memory_stream_name,
+                    userid,
+                    endian,
+                    guess_memory_stream_name,
+                    no_gz,
+                },
+            no_guess_userid,
+            sign:
+                SignArgs {
+                    no_sign,
+                    signature_stream_version,
+                    key_name,
+                },
+            json,
+            pretty,
+            encoding,
+            hash_method,
+            dry_run,
+            checksum,
+            strip_signatures,
+            no_validate,
+            strip_edit_data,
+            increment_version,
+            object_id_offset,
+        } = self;
+        let Globals {
+            salt,
+            output_options,
+            ref memory_stream_names,
+            ref key_ring,
+            ..
+        } = *globals;
+        let endian = endian.map(Endian::from);
+        let stream_endian = endian.unwrap_or(Endian::Little);
+        // Version 1 has no memory stream name, userid, or signature fields
+        let signature_stream_version = if strip_signatures {
+            SignatureStreamVersion::V1
+        } else {
+            signature_stream_version
+        };
+        let payload = |player_profile_extracted: Option<&Path>| -> Result<Vec<u8>> {
+            Ok(if json || pretty {
+                let input = read_input(player_profile_extracted)?;
+                let mut ctsemeta = if pretty {
+                    CTSEMeta::from_ctsem(std::str::from_utf8(&input)?)?
+                } else {
+                    let mut value: serde_json::Value = serde_json::from_slice(&input)?;
+                    strip_annotations(&mut value);
+                    // The path points at the bad value in big saves
+                    serde_path_to_error::deserialize(value)?
+                };
+                if let Some(encoding) = encoding {
+                    if let Err(errors) = ctsemeta.encode_strings(encoding) {
+                        for error in &errors {
+                            log::error!("{}", error);
+                        }
+                        anyhow::bail!(
+                            "{} strings can't be written in {}",
+                            errors.len(),
+                            encoding.name()
+                        );
+                    }
+                }
+                if !no_validate {
+                    check_valid(&ctsemeta)?;
+                }
+                if let Some(offset) = object_id_offset {
+                    ctsemeta.offset_ids(offset)?;
+                }
+                if strip_edit_data {
+                    ctsemeta.strip_edit_data();
+                }
+                if increment_version {
+                    ctsemeta.metadata.bump_version();
+                }
+
+                let mut signature_stream_data = Cursor::new(Vec::new());
+                ctsemeta.write_options(&mut signature_stream_data, stream_endian, ())?;
+                signature_stream_data.into_inner()
+            } else if strip_edit_data || increment_version || object_id_offset.is_some() {
+                let signature_stream_data = read_input(player_profile_extracted)?;
+                let payload_endian = detect_payload_endian(&signature_stream_data, endian);
+                let mut ctsemeta = CTSEMeta::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    payload_endian,
+                    ValidationOptions::default(),
+                )?;
+                if let Some(offset) = object_id_offset {
+                    ctsemeta.offset_ids(offset)?;
+                }
+                if strip_edit_data {
+                    ctsemeta.strip_edit_data();
+                }
+                if increment_version {
+                    ctsemeta.metadata.bump_version();
+                }
+
+                let mut signature_stream_data = Cursor::new(Vec::new());
+                ctsemeta.write_options(&mut signature_stream_data, payload_endian, ())?;
+                signature_stream_data.into_inner()
+            } else {
+                read_input(player_profile_extracted)?
+            })
+        };
+
+        if batch {
+            let (Some(input_dir), Some(output_dir)) = (player_profile_extracted, player_profile)
+            else {
+                anyhow::bail!(
+                    "--batch takes a directory of extracted saves and an output directory"
+                );
+            };
+            let suffix = suffix.unwrap_or_else(|| batch_suffix(json, pretty).to_owned());
+            let jobs = batch_paths(&input_dir, &output_dir, &suffix, false)?;
+            return run_batch(&jobs, fail_fast, "create", |input, output| {
+                let memory_stream_name = memory_stream_name.clone().or_else(|| {
+                    (guess_memory_stream_name)
+                        .then(|| memory_stream_names.guess(output.file_name()))
+                        .flatten()
+                });
+                let userid = userid
+                    .clone()
+                    .or_else(|| (!no_guess_userid).then(|| guess_userid(output)).flatten());
+                let signature_stream_data = payload(Some(input))?;
+                let sign_options_builder = SignOptionsBuilder::new()
+                    .key_ring(key_ring)
+                    .sign_key_name(&key_name)
+                    .memory_stream_name(memory_stream_name.as_ref())
+                    .userid(userid.as_ref())
+                    .hash_method(hash_method);
+                let sign_options = (!no_sign && !strip_signatures)
+                    .then(|| sign_options_builder.build())
+                    .transpose()?;
+                write_save(
+                    output,
+                    no_gz,
+                    stream_endian,
+                    sign_options.as_ref(),
+                    signature_stream_version,
+                    &signature_stream_data,
+                    salt,
+                    output_options,
+                )
+            });
+        }
 
-mod ctsemeta;
-mod helpers;
-mod signature_stream;
+        let (player_profile_extracted, player_profile) = stdio_paths(
+            player_profile_extracted.into_iter().chain(player_profile),
+            stdin,
+            stdout,
+            !dry_run,
+        )?;
+        if checksum && player_profile.is_none() {
+            anyhow::bail!("--checksum can't be used when writing the save to stdout");
+        }
+        let memory_stream_name = memory_stream_name.or_else(|| {
+            (guess_memory_stream_name)
+                .then(|| memory_stream_names.guess(player_profile.as_ref()?.file_name()))
+                .flatten()
+        });
+        let userid = userid.or_else(|| {
+            (!no_guess_userid)
+                .then(|| guess_userid(player_profile.as_deref()?))
+                .flatten()
+        });
+        let signature_stream_data = payload(player_profile_extracted.as_deref())?;
 
-#[derive(ValueEnum, Clone)]
-enum ClapEndian {
-    #[clap(alias = "b")]
-    Big,
-    #[clap(alias = "l")]
-    Little,
+        let sign_options_builder = SignOptionsBuilder::new()
+            .key_ring(key_ring)
+            .sign_key_name(&key_name)
+            .memory_stream_name(memory_stream_name.as_ref())
+            .userid(userid.as_ref())
+            .hash_method(hash_method);
+        let sign_options = (!no_sign && !strip_signatures)
+            .then(|| sign_options_builder.build())
+            .transpose()?;
+        if dry_run {
+            let mut writer = Cursor::new(Vec::new());
+            write_save_to(
+                &mut writer,
+                no_gz,
+                stream_endian,
+                sign_options.as_ref(),
+                signature_stream_version,
+                &signature_stream_data,
+                salt,
+            )?;
+            let save = writer.into_inner();
+            println!(
+                "{} bytes, SHA-256 {}",
+                save.len(),
+                hex(&Sha256::digest(&save))
+            );
+        } else if let Some(player_profile) = player_profile {
+            write_save(
+                &player_profile,
+                no_gz,
+                stream_endian,
+                sign_options.as_ref(),
+                signature_stream_version,
+                &signature_stream_data,
+                salt,
+                output_options,
+            )?;
+            if checksum {
+                let ctsemeta = CTSEMeta::read_options(
+                    &mut Cursor::new(&signature_stream_data),
+                    detect_payload_endian(&signature_stream_data, endian),
+                    ValidationOptions::default(),
+                )?;
+                println!(
+                    "{}  {}",
+                    hex(&ctsemeta.compute_checksum()?),
+                    player_profile.display()
+                );
+            }
+        } else {
+            // Writing gz seeks back to patch the header so stdout gets a copy
+            let mut writer = Cursor::new(Vec::new());
+            write_save_to(
+                &mut writer,
+                no_gz,
+                stream_endian,
+                sign_options.as_ref(),
+                signature_stream_version,
+                &signature_stream_data,
+                salt,
+            )?;
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&writer.into_inner())?;
+            stdout.flush()?;
+        }
+        Ok(())
+    }
 }
 
-impl From<ClapEndian> for Endian {
-    fn from(value: ClapEndian) -> Self {
-        match value {
-            ClapEndian::Big => Self::Big,
-            ClapEndian::Little => Self::Little,
+impl ConvertEndianArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let source_endian = self.source_endian.into();
+        let target_endian = self.target_endian.into();
+        let memory_stream_name = self.memory_stream_name.or_else(|| {
+            (self.guess_memory_stream_name)
+                .then(|| globals.memory_stream_names.guess(self.input.file_name()))
+                .flatten()
+        });
+
+        let signature_stream_data = read_save(
+            &self.input,
+            self.no_gz,
+            globals.max_size,
+            source_endian,
+            &globals.key_ring,
+            memory_stream_name.as_ref(),
+            self.userid.as_ref(),
+        )?;
+        let signature_stream_data =
+            convert_endian(&signature_stream_data, source_endian, target_endian)?;
+
+        let sign_options_builder = SignOptionsBuilder::new()
+            .key_ring(&globals.key_ring)
+            .sign_key_name(&self.sign.key_name)
+            .memory_stream_name(memory_stream_name.as_ref())
+            .userid(self.userid.as_ref());
+        let sign_options = (!self.sign.no_sign)
+            .then(|| sign_options_builder.build())
+            .transpose()?;
+        write_save(
+            &self.output,
+            self.no_gz,
+            target_endian,
+            sign_options.as_ref(),
+            self.sign.signature_stream_version,
+            &signature_stream_data,
+            globals.salt,
+            globals.output_options,
+        )
+    }
+}
+
+impl RecompressArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let input = std::fs::read(&self.input)?;
+        // gzip magic
+        let signature_stream = if input.starts_with(&[0x1F, 0x8B]) {
+            read_gz_signature_stream_with_max_size(&mut input.as_slice(), globals.max_size)?
+        } else {
+            input
+        };
+
+        let mut file = OutputFile::create(self.output, globals.output_options)?;
+        match self.to {
+            Container::Gz => {
+                write_gz_signature_stream(&mut file, &GzOptions::default(), &signature_stream)?
+            }
+            Container::Raw => file.write_all(&signature_stream)?,
         }
+        file.commit()?;
+        Ok(())
     }
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    #[clap(alias = "x")]
-    Extract {
-        player_profile: PathBuf,
-        player_profile_extracted: PathBuf,
-        #[arg(short, long)]
-        memory_stream_name: Option<String>,
-        #[arg(short, long)]
-        userid: Option<String>,
-        #[clap(value_enum)]
-        #[arg(short, long, default_value_t = ClapEndian::Little)]
-        endian: ClapEndian,
-        #[arg(short, long)]
-        no_guess_memory_stream_name: bool,
-        #[arg(short, long)]
-        json: bool,
-        #[arg(long)]
-        no_gz: bool,
-    },
-    #[clap(alias = "c")]
-    Create {
-        player_profile_extracted: PathBuf,
-        player_profile: PathBuf,
-        #[arg(short, long)]
-        memory_stream_name: Option<String>,
-        #[arg(short, long)]
-        userid: Option<String>,
-        #[clap(value_enum)]
-        #[arg(short, long, default_value_t = ClapEndian::Little)]
-        endian: ClapEndian,
-        #[arg(short, long)]
-        guess_memory_stream_name: bool,
-        #[arg(long)]
-        no_sign: bool,
-        #[arg(short, long, default_value_t = 5)]
-        signature_stream_version: u32,
-        #[arg(short, long)]
-        json: bool,
-        #[arg(short, long, default_value_t = SIGN_KEY_GAME_LOCAL_NAME.to_string())]
-        key_name: String,
-        #[arg(long)]
-        no_gz: bool,
-    },
+impl InfoArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let memory_stream_name = self.signature.memory_stream_name(
+            self.player_profile.file_name(),
+            &globals.memory_stream_names,
+        );
+
+        let save_info = SaveInfo::read(
+            &std::fs::read(&self.player_profile)?,
+            self.signature.stream_endian(),
+            &globals.key_ring,
+            memory_stream_name.as_ref(),
+            self.signature.userid.as_ref(),
+        )?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&save_info)?);
+        } else {
+            print!("{}", save_info);
+        }
+        Ok(())
+    }
 }
 
-#[derive(Parser)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    #[command(subcommand)]
-    command: Commands,
+impl DiffArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let [a, b] = [&self.player_profile_a, &self.player_profile_b]
+            .map(|player_profile| read_ctsemeta(player_profile, &self.save, globals));
+        let save_diff = SaveDiff::new(&a?.0, &b?.0);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&save_diff)?);
+        } else {
+            print!("{}", save_diff);
+        }
+        Ok(())
+    }
 }
 
-fn try_guess_memory_stream_name(file_name: Option<&OsStr>) -> Option<String> {
-    let file_name = file_name?.to_str()?;
+impl SignOnlyArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let save = self.save;
+        let memory_stream_name = save.memory_stream_name.or_else(|| {
+            (save.guess_memory_stream_name)
+                .then(|| globals.memory_stream_names.guess(self.output.file_name()))
+                .flatten()
+        });
 
-    if file_name.contains("PlayerProfile") {
-        if file_name.contains("unrestricted") {
-            Some("<memory stream:PlayerProfile_unrestricted.dat>".to_owned())
+        let sign_options_builder = SignOptionsBuilder::new()
+            .key_ring(&globals.key_ring)
+            .sign_key_name(&self.key_name)
+            .memory_stream_name(memory_stream_name.as_ref())
+            .userid(save.userid.as_ref());
+        let sign_options = sign_options_builder.build()?;
+        write_save(
+            &self.output,
+            save.no_gz,
+            save.endian.map_or(Endian::Little, Endian::from),
+            Some(&sign_options),
+            self.signature_stream_version,
+            &std::fs::read(&self.input)?,
+            globals.salt,
+            globals.output_options,
+        )
+    }
+}
+
+impl ListObjectsArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        let objects = list_objects(
+            &ctsemeta,
+            self.filter_type.as_deref(),
+            self.filter_ident.as_deref(),
+        );
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&objects)?);
         } else {
-            Some("<memory stream:PlayerProfile.dat>".to_owned())
+            print!("{}", render_objects(&objects));
         }
-    } else if file_name.contains("All") {
-        Some("Content/Talos/All.dat".to_owned())
-    } else if file_name.contains("DLC") {
-        Some("Content/Talos/DLC.dat".to_owned())
-    } else {
-        None
+        Ok(())
     }
 }
 
-fn main() -> Result<()> {
-    env_logger::init_from_env(
-        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "warn"),
-    );
+impl ListTypesArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        let types = list_types(&ctsemeta);
 
-    let cli = Args::parse();
-    let key_ring = KeyRing::default();
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&types)?);
+        } else {
+            print!("{}", render_types(&types, self.depth));
+        }
+        Ok(())
+    }
+}
 
-    match cli.command {
-        Commands::Extract {
-            player_profile,
-            player_profile_extracted,
-            memory_stream_name,
-            userid,
-            endian,
-            no_guess_memory_stream_name,
-            json,
-            no_gz,
-        } => {
-            let endian = endian.into();
-            let memory_stream_name = memory_stream_name.or_else(|| {
-                (!no_guess_memory_stream_name)
-                    .then(|| try_guess_memory_stream_name(player_profile.file_name()))
-                    .flatten()
-            });
+impl ExplainTypeArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        let id = find_type(&ctsemeta, &self.type_name_or_id)
+            .ok_or_else(|| anyhow::anyhow!("no type named {:?}", self.type_name_or_id))?;
+        let node = explain_type(&ctsemeta, id);
 
-            let mut reader = BufReader::new(File::open(&player_profile)?);
-            let signature_stream_data = if no_gz {
-                parse_signature_stream_data(
-                    &mut reader,
-                    endian,
-                    &key_ring,
-                    memory_stream_name,
-                    userid,
-                )?
-            } else {
-                parse_gz_signature_stream_data(
-                    &mut reader,
-                    endian,
-                    &key_ring,
-                    memory_stream_name,
-                    userid,
-                )?
-            };
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&node)?);
+        } else {
+            print!("{}", node);
+        }
+        Ok(())
+    }
+}
 
-            if json {
-                let ctsemeta =
-                    CTSEMeta::read_options(&mut Cursor::new(&signature_stream_data), endian, ())?;
+impl ExportSchemaArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
 
-                serde_json::to_writer_pretty(
-                    BufWriter::new(File::create(&player_profile_extracted)?),
-                    &ctsemeta,
-                )?;
-            } else {
-                std::fs::write(&player_profile_extracted, &signature_stream_data)?;
+        let mut writer = write_output(self.output.as_deref(), globals.output_options)?;
+        serde_json::to_writer_pretty(&mut writer, &Schema::from_ctsemeta(ctsemeta))?;
+        writer.finish()
+    }
+}
+
+impl CreateTemplateArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let schema: Schema = serde_path_to_error::deserialize(
+            &mut serde_json::Deserializer::from_reader(BufReader::new(File::open(&self.schema)?)),
+        )?;
+        let ctsemeta = create_template(schema, &self.type_name_or_id)?;
+        check_valid(&ctsemeta)?;
+
+        let mut writer = write_output(self.output.as_deref(), globals.output_options)?;
+        serde_json::to_writer_pretty(&mut writer, &ctsemeta)?;
+        writer.finish()
+    }
+}
+
+impl MergeSaveArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let [base, donor] = [&self.base, &self.donor]
+            .map(|player_profile| read_ctsemeta(player_profile, &self.save, globals));
+        // The merged save keeps the base's endianness
+        let ((mut base, payload_endian), (donor, _)) = (base?, donor?);
+        if let Err(conflicts) = base.merge(&donor) {
+            for conflict in &conflicts {
+                log::error!("{}", conflict);
             }
+            anyhow::bail!("{} merge conflicts", conflicts.len());
         }
-        Commands::Create {
-            player_profile_extracted,
-            player_profile,
-            memory_stream_name,
-            userid,
-            endian,
-            guess_memory_stream_name,
-            no_sign,
-            signature_stream_version,
-            json,
-            key_name,
-            no_gz,
-        } => {
-            let endian = endian.into();
-            let memory_stream_name = memory_stream_name.or_else(|| {
-                (guess_memory_stream_name)
-                    .then(|| try_guess_memory_stream_name(player_profile.file_name()))
-                    .flatten()
-            });
 
-            let signature_stream_data = if json {
-                let ctsemeta: CTSEMeta = serde_json::from_reader(BufReader::new(File::open(
-                    &player_profile_extracted,
-                )?))?;
+        let memory_stream_name = self
+            .save
+            .signature
+            .memory_stream_name(self.output.file_name(), &globals.memory_stream_names);
+        write_signed(
+            &self.output,
+            &base.to_bytes(payload_endian)?,
+            &self.sign,
+            &self.save,
+            memory_stream_name.as_ref(),
+            globals,
+            globals.output_options,
+        )
+    }
+}
+
+impl StripObjectArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (mut ctsemeta, payload_endian) =
+            read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        if let Err(conflicts) = ctsemeta.strip_object(self.object) {
+            for conflict in &conflicts {
+                log::error!("{}", conflict);
+            }
+            anyhow::bail!("can't strip object {}", self.object);
+        }
 
-                let mut signature_stream_data = Cursor::new(Vec::new());
-                ctsemeta.write_options(&mut signature_stream_data, endian, ())?;
-                signature_stream_data.into_inner()
-            } else {
-                std::fs::read(&player_profile_extracted)?
+        let memory_stream_name = self.save.signature.memory_stream_name(
+            self.player_profile.file_name(),
+            &globals.memory_stream_names,
+        );
+        write_signed(
+            &self.output,
+            &ctsemeta.to_bytes(payload_endian)?,
+            &self.sign,
+            &self.save,
+            memory_stream_name.as_ref(),
+            globals,
+            globals.output_options,
+        )
+    }
+}
+
+impl SplitByTypeArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, payload_endian) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        if !ctsemeta
+            .internal_types
+            .types
+            .iter()
+            .any(|data_type| data_type.Name == self.type_name)
+        {
+            anyhow::bail!("no type named {:?}", self.type_name);
+        }
+        let (matching, rest) = ctsemeta.split_by_type(&self.type_name);
+
+        let memory_stream_name = self.save.signature.memory_stream_name(
+            self.player_profile.file_name(),
+            &globals.memory_stream_names,
+        );
+        for (ctsemeta, output) in [(matching, &self.matching_output), (rest, &self.rest_output)] {
+            write_signed(
+                output,
+                &ctsemeta.to_bytes(payload_endian)?,
+                &self.sign,
+                &self.save,
+                memory_stream_name.as_ref(),
+                globals,
+                globals.output_options,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl VerifyArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let signature = &self.save.signature;
+        let memory_stream_name = signature.memory_stream_name(
+            self.player_profile.file_name(),
+            &globals.memory_stream_names,
+        );
+
+        let file = std::fs::read(&self.player_profile)?;
+        let signature_stream = if self.save.no_gz {
+            file
+        } else {
+            read_gz_signature_stream_with_max_size(&mut file.as_slice(), globals.max_size)?
+        };
+        let mut reader = SignatureStreamReader::new(
+            signature_stream.as_slice(),
+            signature.stream_endian(),
+            &globals.key_ring,
+            VerifyOptions {
+                memory_stream_name: memory_stream_name.as_deref(),
+                userid: signature.userid.as_deref(),
+            },
+        );
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        let report = reader.report();
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(report)?);
+        } else {
+            let header = match report.header_valid {
+                Some(true) => "valid",
+                Some(false) => "invalid",
+                None => "not verified",
             };
+            println!("header: {}", header);
+            if reader
+                .header()
+                .is_some_and(|header| header.sign_key_name.is_some())
+            {
+                println!("this save's signature covers: {}", report.coverage);
+            }
+            println!(
+                "blocks: {} of {} checked",
+                report.verified_blocks, report.blocks
+            );
+            if !report.invalid_blocks.is_empty() {
+                println!(
+                    "invalid blocks: {}",
+                    report
+                        .invalid_blocks
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+        if report.header_valid == Some(false) || !report.invalid_blocks.is_empty() {
+            return Err(Failure::Verification.into());
+        }
+        Ok(())
+    }
+}
 
-            let mut writer = BufWriter::new(File::create(&player_profile)?);
-            let sign_options = (!no_sign).then_some(SignOptions {
-                key_ring: &key_ring,
-                sign_key_name: &key_name,
-                memory_stream_name: memory_stream_name.as_ref(),
-                userid: userid.as_ref(),
-            });
-            if no_gz {
-                write_signature_stream_data(
-                    &mut writer,
-                    endian,
-                    sign_options.as_ref(),
-                    signature_stream_version,
-                    &signature_stream_data,
-                )?;
-            } else {
-                write_gz_signature_stream_data(
-                    &mut writer,
-                    endian,
-                    sign_options.as_ref(),
-                    signature_stream_version,
-                    &signature_stream_data,
-                )?;
+impl ValidateArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let mut prefix = Vec::new();
+        File::open(&self.player_profile_extracted)?
+            .take(12)
+            .read_to_end(&mut prefix)?;
+        let gz = starts_with_gz_magic(&prefix);
+        let ctsemeta = if gz || starts_with_signature_stream_magic(&prefix) {
+            let save = SaveArgs {
+                signature: self.signature,
+                no_gz: !gz,
+            };
+            read_ctsemeta(&self.player_profile_extracted, &save, globals)?.0
+        } else {
+            let mut value: serde_json::Value = serde_json::from_reader(BufReader::new(
+                File::open(&self.player_profile_extracted)?,
+            ))?;
+            strip_annotations(&mut value);
+            serde_path_to_error::deserialize(value)?
+        };
+        report_problems(&ctsemeta.full_validate())?;
+        println!("no problems found");
+        // Dangling references are already problems, orphans are only
+        // worth knowing about
+        let orphans = ctsemeta
+            .check_references()
+            .into_iter()
+            .filter_map(|issue| match issue {
+                ReferenceIssue::Orphan { object } => Some(object.to_string()),
+                ReferenceIssue::Dangling { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        if !orphans.is_empty() {
+            println!(
+                "note: {} objects aren't referenced by anything: {}",
+                orphans.len(),
+                orphans.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl GraphArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        let graph = ObjectGraph::new(&ctsemeta, self.root, self.max_nodes)?;
+
+        match self.output {
+            Some(output) => {
+                let mut writer = write_output(Some(&output), globals.output_options)?;
+                write!(writer, "{}", graph)?;
+                writer.finish()?;
             }
+            None => print!("{}", graph),
         }
+        Ok(())
     }
+}
 
-    Ok(())
+impl SearchArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+
+        let idents = ctsemeta.idents.by_id();
+        let pattern = self.string.to_lowercase();
+        for (id, value) in ctsemeta.find_string_values() {
+            if value.to_lowercase().contains(&pattern) {
+                println!(
+                    "{}\t{}\t{:?}",
+                    id,
+                    idents.get(&id).copied().unwrap_or("-"),
+                    value
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GetArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (ctsemeta, _) = read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(get_value(&ctsemeta, &self.path)?)?
+        );
+        Ok(())
+    }
+}
+
+impl SetArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let (mut ctsemeta, payload_endian) =
+            read_ctsemeta(&self.player_profile, &self.save, globals)?;
+        set_value(&mut ctsemeta, &self.path, &self.value)?;
+
+        let memory_stream_name = self.save.signature.memory_stream_name(
+            self.player_profile.file_name(),
+            &globals.memory_stream_names,
+        );
+        write_signed(
+            self.output.as_ref().unwrap_or(&self.player_profile),
+            &ctsemeta.to_bytes(payload_endian)?,
+            &self.sign,
+            &self.save,
+            memory_stream_name.as_ref(),
+            globals,
+            globals.output_options,
+        )
+    }
+}
+
+impl EditArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let Self {
+            player_profile,
+            editor,
+            mut save,
+            no_guess_userid,
+            sign,
+        } = self;
+        let memory_stream_name = save
+            .signature
+            .memory_stream_name(player_profile.file_name(), &globals.memory_stream_names);
+        save.signature.userid = save.signature.userid.or_else(|| {
+            (!no_guess_userid)
+                .then(|| guess_userid(&player_profile))
+                .flatten()
+        });
+        // Checked before anything is read so a missing editor fails fast
+        let editor = editor
+            .or_else(|| std::env::var("VISUAL").ok())
+            .or_else(|| std::env::var("EDITOR").ok())
+            .filter(|editor| !editor.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("no editor, set $EDITOR or pass --editor"))?;
+
+        let (ctsemeta, payload_endian) = read_ctsemeta(&player_profile, &save, globals)?;
+        let original = serde_json::to_vec_pretty(&ctsemeta)?;
+
+        let json_path = create_edit_file(&player_profile, &original)?;
+        let edited = read_edits(&editor, &json_path, &original);
+        // Keep the edits around if they can't be used
+        let ctsemeta = match edited {
+            Ok(ctsemeta) => {
+                std::fs::remove_file(&json_path)?;
+                ctsemeta
+            }
+            Err(e) => {
+                return Err(e.context(format!(
+                    "the edited JSON was kept at {}",
+                    json_path.display()
+                )));
+            }
+        };
+        let Some(ctsemeta) = ctsemeta else {
+            return Ok(());
+        };
+
+        write_signed(
+            &player_profile,
+            &ctsemeta.to_bytes(payload_endian)?,
+            &sign,
+            &save,
+            memory_stream_name.as_ref(),
+            globals,
+            OutputOptions {
+                backup: true,
+                ..globals.output_options
+            },
+        )
+    }
+}
+
+impl LocateArgs {
+    fn run(self, globals: &Globals) -> Result<()> {
+        let games = globals.game.map_or(Game::ALL.to_vec(), |game| vec![game]);
+        let candidates = locate(&SystemRoots, &games);
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&candidates)?);
+        } else if candidates.is_empty() {
+            println!("no saves found");
+        } else {
+            print!("{}", render_candidates(&candidates));
+        }
+        Ok(())
+    }
+}
+
+// Runs the editor on the JSON and reads it back, None if the editor failed or
+// nothing changed
+fn read_edits(editor: &str, json_path: &Path, original: &[u8]) -> Result<Option<CTSEMeta>> {
+    let status = run_editor(editor, json_path)?;
+    if !status.success() {
+        warn!("the editor exited with {}, not changing the save", status);
+        return Ok(None);
+    }
+    let edited = std::fs::read(json_path)?;
+    if edited == original {
+        info!("the JSON is unchanged, not changing the save");
+        return Ok(None);
+    }
+    let value: serde_json::Value = serde_json::from_slice(&edited)?;
+    let ctsemeta: CTSEMeta = serde_path_to_error::deserialize(value)?;
+    check_valid(&ctsemeta)?;
+    Ok(Some(ctsemeta))
+}
+
+// Like git, the editor goes through the shell so it can have arguments and
+// quotes, the path is passed separately so it can have spaces
+fn run_editor(editor: &str, path: &Path) -> Result<std::process::ExitStatus> {
+    #[cfg(windows)]
+    let status = {
+        use std::os::windows::process::CommandExt;
+        std::process::Command::new("cmd")
+            .arg("/C")
+            .raw_arg(format!("{} \"{}\"", editor, path.display()))
+            .status()
+    };
+    #[cfg(not(windows))]
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status();
+    status.with_context(|| format!("failed to run the editor {:?}", editor))
+}
+
+// A new file in the temp directory named after the save, so the editor shows
+// which save is being edited
+fn create_edit_file(player_profile: &Path, json: &[u8]) -> Result<PathBuf> {
+    let name = player_profile
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let mut rng = rand::thread_rng();
+    loop {
+        let path = std::env::temp_dir().join(format!("{}.{:08x}.json", name, rng.r#gen::<u32>()));
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(json)?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::ffi::OsStr;
     use std::fs::File;
     use std::io::Cursor;
     use std::path::PathBuf;
 
     use binrw::io::BufReader;
     use binrw::{BinRead, BinWrite, Endian};
-
-    use crate::ctsemeta::CTSEMeta;
-    use crate::signature_stream::{
+    use serious_save_editor::ctsemeta::CTSEMeta;
+    use serious_save_editor::helpers::ValidationOptions;
+    use serious_save_editor::signature_stream::{
+        GzOptions,
         KeyRing,
-        SIGN_KEY_GAME_LOCAL_NAME,
-        SignOptions,
+        MAX_DECOMPRESSED_SIZE,
+        SignOptionsBuilder,
+        SignatureStreamReader,
+        SignatureStreamVersion,
+        VerifyOptions,
         parse_gz_signature_stream_data,
+        read_gz_signature_stream_with_max_size,
         write_gz_signature_stream_data,
     };
-    use crate::try_guess_memory_stream_name;
+
+    use crate::{
+        Game,
+        MemoryStreamNames,
+        convert_endian,
+        memory_stream_name_guesses,
+        parse_salt,
+        try_guess_memory_stream_name,
+    };
 
     #[test]
     fn round_trip() {
@@ -254,7 +2757,8 @@ mod tests {
         let key_ring = KeyRing::default();
 
         let player_profile = PathBuf::from("data/PlayerProfile.dat");
-        let memory_stream_name = try_guess_memory_stream_name(player_profile.file_name());
+        let memory_stream_name =
+            try_guess_memory_stream_name(player_profile.file_name(), Game::Talos);
         let userid = Some("1100001075d8dea");
 
         // Try to read it first
@@ -269,8 +2773,12 @@ mod tests {
         .unwrap();
 
         // Parse the data
-        let ctsemeta =
-            CTSEMeta::read_options(&mut Cursor::new(&signature_stream_data), endian, ()).unwrap();
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(&signature_stream_data),
+            endian,
+            ValidationOptions::default(),
+        )
+        .unwrap();
 
         // Write back the data
         let mut writer = Cursor::new(Vec::new());
@@ -285,14 +2793,16 @@ mod tests {
         write_gz_signature_stream_data(
             &mut writer,
             endian,
-            Some(SignOptions {
-                key_ring: &key_ring,
-                sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
-                memory_stream_name: memory_stream_name.as_ref(),
-                userid: userid.as_ref(),
-            })
-            .as_ref(),
-            5,
+            Some(
+                &SignOptionsBuilder::new()
+                    .key_ring(&key_ring)
+                    .memory_stream_name(memory_stream_name.as_ref())
+                    .userid(userid)
+                    .build()
+                    .unwrap(),
+            ),
+            SignatureStreamVersion::LATEST,
+            &GzOptions::default(),
             &signature_stream_data,
         )
         .unwrap();
@@ -304,4 +2814,236 @@ mod tests {
         parse_gz_signature_stream_data(&mut reader, endian, &key_ring, memory_stream_name, userid)
             .unwrap();
     }
+
+    #[test]
+    fn convert_endian_round_trip() {
+        let key_ring = KeyRing::default();
+        let ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let little = writer.into_inner();
+
+        let big = convert_endian(&little, Endian::Little, Endian::Big).unwrap();
+        assert_ne!(little, big);
+        assert_eq!(&big[8..12], &[0x12, 0x34, 0xAB, 0xCD]);
+
+        // The converted payload should survive a big endian signature stream
+        let mut writer = Cursor::new(Vec::new());
+        write_gz_signature_stream_data(
+            &mut writer,
+            Endian::Big,
+            Some(
+                &SignOptionsBuilder::new()
+                    .key_ring(&key_ring)
+                    .build()
+                    .unwrap(),
+            ),
+            SignatureStreamVersion::LATEST,
+            &GzOptions::default(),
+            &big,
+        )
+        .unwrap();
+        let signature_stream = writer.into_inner();
+        let big_again = parse_gz_signature_stream_data(
+            &mut Cursor::new(&signature_stream),
+            Endian::Big,
+            &key_ring,
+            None::<&str>,
+            None::<&str>,
+        )
+        .unwrap();
+        assert_eq!(big, big_again.as_ref());
+
+        // Source endianness comes from the cookie, not the argument
+        let little_again = convert_endian(&big_again, Endian::Little, Endian::Little).unwrap();
+        assert_eq!(little, little_again);
+    }
+
+    #[test]
+    fn salt() {
+        assert_eq!(parse_salt("1234"), Ok(1234));
+        assert_eq!(parse_salt("0xDEADBEEF"), Ok(0xDEADBEEF));
+        assert_eq!(parse_salt("0X10"), Ok(0x10));
+        assert_eq!(
+            parse_salt("0x100000000"),
+            Err("salt must be a 32 bit decimal or 0x hex number, got \"0x100000000\"".to_owned())
+        );
+        assert!(parse_salt("DEADBEEF").is_err());
+        assert!(parse_salt("-1").is_err());
+    }
+
+    #[test]
+    fn memory_stream_name_db() {
+        let path = std::env::temp_dir().join(format!(
+            "sse_memory_stream_name_db_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#"{
+                "MyMod_Save.dat": "<memory stream:MyMod_Save.dat>",
+                "PlayerProfile.dat": "<memory stream:Renamed.dat>",
+                "Saves/Broken.dat": "<memory stream:Broken.dat>",
+                "Count.dat": 3
+            }"#,
+        )
+        .unwrap();
+        let memory_stream_names = MemoryStreamNames::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let guess = |file_name: &str| memory_stream_names.guess(Some(OsStr::new(file_name)));
+        assert_eq!(
+            guess("MyMod_Save.dat").as_deref(),
+            Some("<memory stream:MyMod_Save.dat>")
+        );
+        // The DB wins over the built in guesses, which are still used otherwise
+        assert_eq!(
+            guess("PlayerProfile.dat").as_deref(),
+            Some("<memory stream:Renamed.dat>")
+        );
+        assert_eq!(
+            guess("PlayerProfile_unrestricted.dat").as_deref(),
+            Some("<memory stream:PlayerProfile_unrestricted.dat>")
+        );
+        assert_eq!(guess("Broken.dat"), None);
+        assert_eq!(guess("Count.dat"), None);
+    }
+
+    #[test]
+    fn guess_memory_stream_name() {
+        let guess = |file_name: &str| {
+            try_guess_memory_stream_name(Some(OsStr::new(file_name)), Game::Talos)
+        };
+        assert_eq!(guess("All.dat").as_deref(), Some("Content/Talos/All.dat"));
+        assert_eq!(guess("DLC.dat").as_deref(), Some("Content/Talos/DLC.dat"));
+        assert_eq!(
+            guess("PlayerProfile.dat").as_deref(),
+            Some("<memory stream:PlayerProfile.dat>")
+        );
+        assert_eq!(
+            guess("PlayerProfile_unrestricted.dat").as_deref(),
+            Some("<memory stream:PlayerProfile_unrestricted.dat>")
+        );
+        assert_eq!(
+            guess("AutoSave.dat").as_deref(),
+            Some("<memory stream:AutoSave.dat>")
+        );
+        assert_eq!(
+            guess("QuickSave.dat").as_deref(),
+            Some("<memory stream:QuickSave.dat>")
+        );
+        assert_eq!(
+            guess("SaveGame0003.dat").as_deref(),
+            Some("<memory stream:SaveGame0003.dat>")
+        );
+        assert_eq!(guess("Unknown.dat"), None);
+        assert_eq!(try_guess_memory_stream_name(None, Game::Talos), None);
+
+        let guess =
+            |file_name: &str, game| try_guess_memory_stream_name(Some(OsStr::new(file_name)), game);
+        assert_eq!(
+            guess("All.dat", Game::Sam3).as_deref(),
+            Some("Content/SeriousSam3/All.dat")
+        );
+        assert_eq!(
+            guess("DLC.dat", Game::Sam3).as_deref(),
+            Some("Content/SeriousSam3/DLC.dat")
+        );
+        assert_eq!(
+            guess("All.dat", Game::SamHd).as_deref(),
+            Some("Content/SeriousSamHD/All.dat")
+        );
+        assert_eq!(
+            guess("DLC.dat", Game::SamHd).as_deref(),
+            Some("Content/SeriousSamHD/DLC.dat")
+        );
+        // Only The Talos Principle has an unrestricted profile
+        assert_eq!(
+            guess("PlayerProfile_unrestricted.dat", Game::Sam3).as_deref(),
+            Some("<memory stream:PlayerProfile.dat>")
+        );
+        assert_eq!(guess("PlayerProfile.dat", Game::Talos2), None);
+
+        // Every entry is reachable, nothing before it matches its own patterns
+        for game in Game::ALL {
+            for (patterns, memory_stream_name) in memory_stream_name_guesses(game) {
+                let file_name = format!("{}.dat", patterns.join("_"));
+                assert_eq!(
+                    guess(&file_name, game),
+                    Some(memory_stream_name.replace("{file}", &file_name)),
+                    "{} for {}",
+                    file_name,
+                    game
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn guessed_memory_stream_names_round_trip() {
+        let endian = Endian::Little;
+        let key_ring = KeyRing::default();
+        let ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        let data = ctsemeta.to_bytes(endian).unwrap();
+
+        let file_names = ["All.dat", "DLC.dat", "PlayerProfile.dat"];
+        for file_name in file_names {
+            let memory_stream_name =
+                try_guess_memory_stream_name(Some(OsStr::new(file_name)), Game::Talos);
+            let mut writer = Cursor::new(Vec::new());
+            write_gz_signature_stream_data(
+                &mut writer,
+                endian,
+                Some(
+                    &SignOptionsBuilder::new()
+                        .key_ring(&key_ring)
+                        .memory_stream_name(memory_stream_name.as_ref())
+                        .build()
+                        .unwrap(),
+                ),
+                SignatureStreamVersion::LATEST,
+                &GzOptions {
+                    filename: Some(file_name),
+                    ..Default::default()
+                },
+                &data,
+            )
+            .unwrap();
+            let signature_stream = read_gz_signature_stream_with_max_size(
+                &mut writer.into_inner().as_slice(),
+                MAX_DECOMPRESSED_SIZE,
+            )
+            .unwrap();
+
+            // Only the name the save was signed with verifies
+            for other in file_names {
+                let other_name = try_guess_memory_stream_name(Some(OsStr::new(other)), Game::Talos);
+                let mut reader = SignatureStreamReader::new(
+                    signature_stream.as_slice(),
+                    endian,
+                    &key_ring,
+                    VerifyOptions {
+                        memory_stream_name: other_name.as_deref(),
+                        userid: None,
+                    },
+                );
+                let mut read = Vec::new();
+                std::io::copy(&mut reader, &mut read).unwrap();
+                assert_eq!(
+                    reader.report().header_valid,
+                    Some(other == file_name),
+                    "{} verified as {}",
+                    file_name,
+                    other
+                );
+                assert_eq!(read, data);
+            }
+        }
+    }
 }
@@ -0,0 +1,436 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::ctsemeta::{
+    CTSEMeta,
+    DataTypeType,
+    Ident,
+    IdentRef,
+    InternalObjectDataValue,
+    InternalObjectType,
+    reachable_types,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MergeConflict {
+    Object {
+        id: u32,
+    },
+    Type {
+        id: u32,
+        base_name: String,
+        donor_name: String,
+    },
+    Ident {
+        id: u32,
+        base_name: String,
+        donor_name: String,
+    },
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Object { id } => write!(f, "object {} exists in both saves", id),
+            Self::Type {
+                id,
+                base_name,
+                donor_name,
+            } => write!(
+                f,
+                "type {} is {:?} in the base save but {:?} in the donor",
+                id, base_name, donor_name
+            ),
+            Self::Ident {
+                id,
+                base_name,
+                donor_name,
+            } => write!(
+                f,
+                "ident {} is {:?} in the base save but {:?} in the donor",
+                id, base_name, donor_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+// An ID that doesn't fit in 32 bits once offset. u32::MAX doesn't either, it's
+// the null pointer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct IdOverflow {
+    pub id: u32,
+    pub offset: u32,
+}
+
+impl fmt::Display for IdOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ID {} plus {} doesn't fit in 32 bits",
+            self.id, self.offset
+        )
+    }
+}
+
+impl std::error::Error for IdOverflow {}
+
+impl CTSEMeta {
+    // Appends the objects of other along with the types and idents they need.
+    // Types and idents are matched by ID and must have the same name in both
+    // saves. Nothing is merged if anything conflicts. Pointers inside the
+    // donor objects are copied as is.
+    pub fn merge(&mut self, other: &Self) -> Result<(), Vec<MergeConflict>> {
+        let mut conflicts = Vec::new();
+
+        let object_ids = self
+            .internal_objects
+            .internal_object
+            .iter()
+            .map(|object| object.Object)
+            .collect::<BTreeSet<_>>();
+        for object in &other.internal_objects.internal_object {
+            if object_ids.contains(&object.Object) {
+                conflicts.push(MergeConflict::Object { id: object.Object });
+            }
+        }
+
+        // Every type reachable from the donor objects
+        let other_types = other.internal_types.by_id();
+        let needed_types = reachable_types(
+            &other_types,
+            other
+                .internal_objects
+                .internal_object
+                .iter()
+                .map(|object| object.Type),
+        );
+
+        let self_types = self.internal_types.by_id();
+        let mut new_types = Vec::new();
+        let mut needed_idents = BTreeSet::new();
+        for id in needed_types {
+            let donor_type = other_types[&id];
+            if let DataTypeType::Struct { members, .. } = &donor_type.Type {
+                needed_idents.extend(members.iter().map(|member| member.ID));
+            }
+            match self_types.get(&id) {
+                Some(base_type) if base_type.Name != donor_type.Name => {
+                    conflicts.push(MergeConflict::Type {
+                        id,
+                        base_name: base_type.Name.clone(),
+                        donor_name: donor_type.Name.clone(),
+                    })
+                }
+                Some(_) => {}
+                None => new_types.push(id),
+            }
+        }
+
+        let self_idents = self.idents.by_id();
+        let other_idents = other.idents.by_id();
+        let mut new_idents = Vec::new();
+        for id in needed_idents {
+            let Some(donor_name) = other_idents.get(&id) else {
+                continue;
+            };
+            match self_idents.get(&id) {
+                Some(base_name) if base_name != donor_name => {
+                    conflicts.push(MergeConflict::Ident {
+                        id,
+                        base_name: (*base_name).to_owned(),
+                        donor_name: (*donor_name).to_owned(),
+                    })
+                }
+                Some(_) => {}
+                None => new_idents.push(Ident {
+                    Ident: id,
+                    Name: (*donor_name).to_owned(),
+                }),
+            }
+        }
+
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+
+        for id in new_types {
+            self.internal_types.types.push(other_types[&id].clone());
+        }
+        self.idents.idents.extend(new_idents);
+        for object in &other.internal_objects.internal_object {
+            self.internal_object_types.types.push(InternalObjectType {
+                Object: object.Object,
+                Type: object.Type,
+            });
+            self.internal_objects.internal_object.push(object.clone());
+        }
+
+        Ok(())
+    }
+
+    // Adds offset to every object and ident ID and everything that refers to
+    // them, so the objects can be merged into a save that already uses their
+    // IDs. Objects are named by the ident with their ID, so the two move
+    // together. Nothing changes if any ID overflows.
+    pub fn offset_ids(&mut self, offset: u32) -> Result<(), IdOverflow> {
+        fn offset_value(
+            value: &mut InternalObjectDataValue,
+            unnamed: &BTreeSet<String>,
+            shift: &impl Fn(u32) -> Result<u32, IdOverflow>,
+        ) -> Result<(), IdOverflow> {
+            match value {
+                InternalObjectDataValue::Pointer(-1) => {}
+                InternalObjectDataValue::Pointer(pointer) => {
+                    *pointer = shift(*pointer as u32)? as i32;
+                }
+                // u32::MAX is null here too
+                InternalObjectDataValue::DynamicContainer(pointers) => {
+                    for pointer in pointers.iter_mut().filter(|pointer| **pointer != u32::MAX) {
+                        *pointer = shift(*pointer)?;
+                    }
+                }
+                InternalObjectDataValue::IDENT(IdentRef::Id(u32::MAX)) => {}
+                InternalObjectDataValue::IDENT(IdentRef::Id(id)) => *id = shift(*id)?,
+                InternalObjectDataValue::Array(values)
+                | InternalObjectDataValue::StaticStackArray(values)
+                | InternalObjectDataValue::DynamicContainerValues(values) => {
+                    for value in values {
+                        offset_value(value, unnamed, shift)?;
+                    }
+                }
+                InternalObjectDataValue::Struct { Base, members } => {
+                    if let Some(base) = Base {
+                        offset_value(base, unnamed, shift)?;
+                    }
+                    for (name, value) in members.iter_mut() {
+                        if unnamed.contains(name) {
+                            *name = shift(name.parse().unwrap())?.to_string();
+                        }
+                        offset_value(value, unnamed, shift)?;
+                    }
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+
+        let shift = |id: u32| {
+            id.checked_add(offset)
+                .filter(|id| *id != u32::MAX)
+                .ok_or(IdOverflow { id, offset })
+        };
+
+        // Members without an ident are keyed by their ID
+        let idents = self.idents.by_id();
+        let unnamed = self
+            .internal_types
+            .types
+            .iter()
+            .filter_map(|data_type| match &data_type.Type {
+                DataTypeType::Struct { members, .. } => Some(members),
+                _ => None,
+            })
+            .flat_map(|members| members.iter())
+            .filter(|member| !idents.contains_key(&member.ID))
+            .map(|member| member.ID.to_string())
+            .collect::<BTreeSet<_>>();
+
+        let mut shifted = self.clone();
+        for ident in &mut shifted.idents.idents {
+            ident.Ident = shift(ident.Ident)?;
+        }
+        for data_type in &mut shifted.internal_types.types {
+            if let DataTypeType::Struct { members, .. } = &mut data_type.Type {
+                for member in members {
+                    member.ID = shift(member.ID)?;
+                }
+            }
+        }
+        for object in &mut shifted.external_objects.external_objects {
+            object.Object = shift(object.Object)?;
+        }
+        for object_type in &mut shifted.internal_object_types.types {
+            object_type.Object = shift(object_type.Object)?;
+        }
+        for object_type in &mut shifted.edit_object_types.edit_object_types {
+            object_type.Object = shift(object_type.Object)?;
+        }
+        for object in &mut shifted.internal_objects.internal_object {
+            object.Object = shift(object.Object)?;
+            offset_value(&mut object.value, &unnamed, &shift)?;
+        }
+        *self = shifted;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::{BinRead, BinWrite, Endian};
+
+    use crate::ctsemeta::{
+        CTSEMeta,
+        DataType,
+        DataTypeType,
+        IdentRef,
+        InternalObject,
+        InternalObjectDataValue,
+        synthetic,
+    };
+    use crate::helpers::ValidationOptions;
+    use crate::merge::{IdOverflow, MergeConflict};
+
+    #[test]
+    fn merge() {
+        let mut base = synthetic();
+        let mut donor = synthetic();
+        donor.internal_types.types.push(DataType {
+            DataType: 5,
+            Name: "UBYTE".to_owned(),
+            Format: 0,
+            Type: DataTypeType::Primitive { Bytes: 1, LBE: 0 },
+        });
+        donor
+            .internal_objects
+            .internal_object
+            .edit(|objects| objects.truncate(1));
+        donor.internal_objects.internal_object[0].Object = 10;
+        donor.internal_objects.internal_object[0].Type = 5;
+        donor.internal_objects.internal_object[0].value = InternalObjectDataValue::UBYTE(3);
+
+        base.merge(&donor).unwrap();
+        assert_eq!(base.internal_objects.internal_object.len(), 3);
+        assert_eq!(base.internal_object_types.types.len(), 3);
+        assert_eq!(base.internal_types.types.len(), 6);
+
+        // Info is recalculated when writing so it has to agree with the tables
+        let mut writer = Cursor::new(Vec::new());
+        base.write_options(&mut writer, Endian::Little, ()).unwrap();
+        let merged = CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            merged.internal_objects.internal_object[2].value,
+            InternalObjectDataValue::UBYTE(3)
+        ));
+    }
+
+    #[test]
+    fn merge_conflicts() {
+        let mut base = synthetic();
+        let mut donor = synthetic();
+        donor.internal_types.types[4].Name = "CTalosProgressV2".to_owned();
+
+        let conflicts = base.merge(&donor).unwrap_err();
+        assert_eq!(
+            conflicts,
+            vec![
+                MergeConflict::Object { id: 0 },
+                MergeConflict::Object { id: 1 },
+                MergeConflict::Type {
+                    id: 4,
+                    base_name: "CTalosProgress".to_owned(),
+                    donor_name: "CTalosProgressV2".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(conflicts[0].to_string(), "object 0 exists in both saves");
+        assert_eq!(base.internal_objects.internal_object.len(), 2);
+    }
+
+    #[test]
+    fn offset_ids() {
+        let mut base = synthetic();
+        let mut donor = synthetic();
+        donor.offset_ids(10000).unwrap();
+        let objects = &donor.internal_objects.internal_object;
+        assert_eq!((objects[0].Object, objects[1].Object), (10000, 10001));
+        assert_eq!(donor.internal_object_types.types[1].Object, 10001);
+        assert_eq!(donor.idents.idents[3].Ident, 10003);
+        let InternalObjectDataValue::Struct { members, .. } = &objects[0].value else {
+            unreachable!()
+        };
+        assert!(matches!(
+            members.get("m_pNext"),
+            Some(InternalObjectDataValue::Pointer(10001))
+        ));
+        let InternalObjectDataValue::Struct { members, .. } = &objects[1].value else {
+            unreachable!()
+        };
+        assert!(matches!(
+            members.get("m_pNext"),
+            Some(InternalObjectDataValue::Pointer(-1))
+        ));
+
+        // Still consistent enough to read back strictly, and no longer
+        // conflicts with the original
+        let mut writer = Cursor::new(Vec::new());
+        donor
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions {
+                strict: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        base.merge(&donor).unwrap();
+        assert_eq!(base.internal_objects.internal_object.len(), 4);
+
+        // Null entries stay null however close the offset gets to the limit
+        let mut donor = synthetic();
+        donor.internal_objects.internal_object.push(InternalObject {
+            Object: 2,
+            Type: 4,
+            value: InternalObjectDataValue::Array(vec![
+                InternalObjectDataValue::DynamicContainer(vec![u32::MAX, 1]),
+                InternalObjectDataValue::IDENT(IdentRef::Id(u32::MAX)),
+                InternalObjectDataValue::IDENT(IdentRef::Id(3)),
+            ]),
+        });
+        donor.offset_ids(u32::MAX - 4).unwrap();
+        let InternalObjectDataValue::Array(values) =
+            &donor.internal_objects.internal_object[2].value
+        else {
+            unreachable!()
+        };
+        assert!(matches!(
+            values[0],
+            InternalObjectDataValue::DynamicContainer(ref pointers)
+                if pointers == &[u32::MAX, u32::MAX - 3]
+        ));
+        assert!(matches!(
+            values[1],
+            InternalObjectDataValue::IDENT(IdentRef::Id(u32::MAX))
+        ));
+        assert!(matches!(
+            values[2],
+            InternalObjectDataValue::IDENT(IdentRef::Id(id)) if id == u32::MAX - 1
+        ));
+
+        // Ident 1 would become u32::MAX, and nothing is changed
+        let mut donor = synthetic();
+        assert_eq!(
+            donor.offset_ids(u32::MAX - 1),
+            Err(IdOverflow {
+                id: 1,
+                offset: u32::MAX - 1
+            })
+        );
+        assert_eq!(donor.idents.idents[0].Ident, 0);
+        assert_eq!(
+            donor.offset_ids(u32::MAX).unwrap_err().to_string(),
+            "ID 0 plus 4294967295 doesn't fit in 32 bits"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+// Attempts at finding an unused temporary file name before giving up
+const TEMP_ATTEMPTS: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OutputOptions {
+    // Write straight to the target, for filesystems where renaming is a problem
+    pub no_atomic: bool,
+    // Keep the file being replaced as <name>.bak
+    pub backup: bool,
+}
+
+// A file that's written next to its target as <name>.tmp-XXXXXXXX and only
+// renamed over it by commit. Dropping it without committing removes the
+// temporary file, so a write that fails partway leaves the target untouched.
+pub struct OutputFile {
+    // Only None while dropping, the file has to be closed before it's removed
+    writer: Option<BufWriter<File>>,
+    path: PathBuf,
+    temp_path: Option<PathBuf>,
+    backup: bool,
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    name.into()
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+impl OutputFile {
+    pub fn create(path: impl Into<PathBuf>, options: OutputOptions) -> io::Result<Self> {
+        let path = path.into();
+        if options.no_atomic {
+            if options.backup && path.exists() {
+                std::fs::rename(&path, backup_path(&path))?;
+            }
+            return Ok(Self {
+                writer: Some(BufWriter::new(File::create(&path)?)),
+                path,
+                temp_path: None,
+                backup: false,
+            });
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut attempts = 0;
+        let (file, temp_path) = loop {
+            let temp_path = with_suffix(&path, &format!(".tmp-{:08x}", rng.r#gen::<u32>()));
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temp_path)
+            {
+                Ok(file) => break (file, temp_path),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < TEMP_ATTEMPTS => {
+                    attempts += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        // The replacement should be as readable as what it replaces
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            let _ = file.set_permissions(metadata.permissions());
+        }
+        Ok(Self {
+            writer: Some(BufWriter::new(file)),
+            path,
+            temp_path: Some(temp_path),
+            backup: options.backup,
+        })
+    }
+
+    fn writer(&mut self) -> &mut BufWriter<File> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken when dropping")
+    }
+
+    pub fn commit(mut self) -> io::Result<()> {
+        self.writer().flush()?;
+        self.writer().get_ref().sync_all()?;
+        let Some(temp_path) = self.temp_path.take() else {
+            return Ok(());
+        };
+        // Closed first, Windows can't rename a file that's still open
+        self.writer = None;
+        let backed_up = self.backup && self.path.exists();
+        let result = (|| {
+            if backed_up {
+                std::fs::rename(&self.path, backup_path(&self.path))?;
+            }
+            // Replaces an existing target on Windows too, std uses
+            // MOVEFILE_REPLACE_EXISTING
+            std::fs::rename(&temp_path, &self.path)
+        })();
+        let Err(e) = result else {
+            return Ok(());
+        };
+        // Put the original back, or keep the new file if that fails too so
+        // neither is lost
+        if backed_up
+            && !self.path.exists()
+            && backup_path(&self.path).exists()
+            && std::fs::rename(backup_path(&self.path), &self.path).is_err()
+        {
+            return Err(io::Error::new(
+                e.kind(),
+                format!(
+                    "{}, the original is at {} and the new file at {}",
+                    e,
+                    backup_path(&self.path).display(),
+                    temp_path.display()
+                ),
+            ));
+        }
+        let _ = std::fs::remove_file(&temp_path);
+        Err(e)
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Seek for OutputFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.writer().seek(pos)
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        self.writer = None;
+        if let Some(temp_path) = self.temp_path.take() {
+            let _ = std::fs::remove_file(temp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+
+    use crate::output_file::{OutputFile, OutputOptions, backup_path};
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn output_file() {
+        let dir = std::env::temp_dir().join(format!("sse_output_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("PlayerProfile.dat");
+        std::fs::write(&path, b"original").unwrap();
+
+        // Failing partway through leaves the original and no temporary file
+        let result = (|| {
+            let mut file = OutputFile::create(&path, OutputOptions::default())?;
+            file.write_all(b"trunc")?;
+            Err::<(), _>(std::io::Error::other("disk full"))?;
+            file.commit()
+        })();
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"original");
+        assert_eq!(files(&dir), ["PlayerProfile.dat"]);
+
+        let mut file = OutputFile::create(&path, OutputOptions::default()).unwrap();
+        file.write_all(b"replaced").unwrap();
+        // Nothing changes until the commit
+        assert_eq!(std::fs::read(&path).unwrap(), b"original");
+        file.commit().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"replaced");
+        assert_eq!(files(&dir), ["PlayerProfile.dat"]);
+
+        let backup = OutputOptions {
+            backup: true,
+            ..Default::default()
+        };
+        let mut file = OutputFile::create(&path, backup).unwrap();
+        file.write_all(b"again").unwrap();
+        file.commit().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"again");
+        assert_eq!(std::fs::read(backup_path(&path)).unwrap(), b"replaced");
+
+        // Failing to rename over the target after the backup puts the original
+        // back
+        let mut file = OutputFile::create(&path, backup).unwrap();
+        file.write_all(b"lost").unwrap();
+        std::fs::remove_file(file.temp_path.as_ref().unwrap()).unwrap();
+        assert!(file.commit().is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"again");
+        assert_eq!(files(&dir), ["PlayerProfile.dat"]);
+
+        let direct = OutputOptions {
+            no_atomic: true,
+            backup: true,
+        };
+        let mut file = OutputFile::create(&path, direct).unwrap();
+        file.write_all(b"direct").unwrap();
+        file.commit().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"direct");
+        assert_eq!(std::fs::read(backup_path(&path)).unwrap(), b"again");
+        assert_eq!(files(&dir), ["PlayerProfile.dat", "PlayerProfile.dat.bak"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
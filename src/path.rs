@@ -0,0 +1,388 @@
+// Paths address a single value inside the parsed CTSEMETA, e.g.
+// `objects/12/m_strName`, `objects/12/members/4` or `objects/12/base/3`.
+// After the object ID each segment is one of
+//   base          the Base of a struct
+//   members/<i>   the i-th member of a struct
+//   <ident name>  the struct member with that ident, searching the bases too
+//   <i>           the i-th element of an array, or row of a multi-row array
+use anyhow::{Result, anyhow, bail};
+
+use crate::ctsemeta::{CTSEMeta, IdentRef, InternalObjectDataValue};
+
+enum Step {
+    Base,
+    Member(usize),
+    Index(usize),
+}
+
+fn step<'a>(
+    value: &'a InternalObjectDataValue,
+    step: &Step,
+) -> Option<&'a InternalObjectDataValue> {
+    match (value, step) {
+        (InternalObjectDataValue::Struct { Base, .. }, Step::Base) => Base.as_deref(),
+        (InternalObjectDataValue::Struct { members, .. }, Step::Member(i)) => {
+            members.0.get(*i).map(|(_, value)| value)
+        }
+        (
+            InternalObjectDataValue::Array(values)
+            | InternalObjectDataValue::StaticStackArray(values)
+            | InternalObjectDataValue::DynamicContainerValues(values),
+            Step::Index(i),
+        ) => values.get(*i),
+        _ => None,
+    }
+}
+
+fn step_mut<'a>(
+    value: &'a mut InternalObjectDataValue,
+    step: &Step,
+) -> Option<&'a mut InternalObjectDataValue> {
+    match (value, step) {
+        (InternalObjectDataValue::Struct { Base, .. }, Step::Base) => Base.as_deref_mut(),
+        (InternalObjectDataValue::Struct { members, .. }, Step::Member(i)) => {
+            members.0.get_mut(*i).map(|(_, value)| value)
+        }
+        (
+            InternalObjectDataValue::Array(values)
+            | InternalObjectDataValue::StaticStackArray(values)
+            | InternalObjectDataValue::DynamicContainerValues(values),
+            Step::Index(i),
+        ) => values.get_mut(*i),
+        _ => None,
+    }
+}
+
+fn parse_index(segment: Option<&str>, path: &str) -> Result<usize> {
+    let segment = segment.ok_or_else(|| anyhow!("path {:?} is missing an index", path))?;
+    segment
+        .parse()
+        .map_err(|_| anyhow!("{:?} in path {:?} is not an index", segment, path))
+}
+
+// Turns the path into the object's position and the steps to take from its
+// value, names are looked up in the struct members
+fn resolve(ctsemeta: &CTSEMeta, path: &str) -> Result<(usize, Vec<Step>)> {
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    if segments.next() != Some("objects") {
+        bail!("path {:?} must start with objects/<id>", path);
+    }
+    let object = parse_index(segments.next(), path)? as u32;
+    let object_index = ctsemeta
+        .internal_objects
+        .internal_object
+        .iter()
+        .position(|x| x.Object == object)
+        .ok_or_else(|| anyhow!("no object with ID {}", object))?;
+
+    let mut value = &ctsemeta.internal_objects.internal_object[object_index].value;
+    let mut steps = Vec::new();
+    while let Some(segment) = segments.next() {
+        let next = match (segment, value) {
+            ("base", InternalObjectDataValue::Struct { .. }) => Step::Base,
+            ("members", InternalObjectDataValue::Struct { .. }) => {
+                Step::Member(parse_index(segments.next(), path)?)
+            }
+            (
+                _,
+                InternalObjectDataValue::Array(_) | InternalObjectDataValue::StaticStackArray(_),
+            ) => Step::Index(parse_index(Some(segment), path)?),
+            (_, InternalObjectDataValue::Struct { .. }) => {
+                // Look through the bases for the member like C++ would
+                loop {
+                    let InternalObjectDataValue::Struct { Base, members } = value else {
+                        bail!("no member named {:?} in path {:?}", segment, path);
+                    };
+                    if let Some(i) = members.iter().position(|(name, _)| name == segment) {
+                        break Step::Member(i);
+                    }
+                    let Some(base) = Base else {
+                        bail!("no member named {:?} in path {:?}", segment, path);
+                    };
+                    steps.push(Step::Base);
+                    value = base;
+                }
+            }
+            _ => bail!(
+                "{:?} in path {:?} does not address into a struct or array",
+                segment,
+                path
+            ),
+        };
+        value = step(value, &next).ok_or_else(|| anyhow!("path {:?} does not exist", path))?;
+        steps.push(next);
+    }
+
+    Ok((object_index, steps))
+}
+
+pub fn get_value<'a>(ctsemeta: &'a CTSEMeta, path: &str) -> Result<&'a InternalObjectDataValue> {
+    let (object_index, steps) = resolve(ctsemeta, path)?;
+    let mut value = &ctsemeta.internal_objects.internal_object[object_index].value;
+    for s in &steps {
+        value = step(value, s).ok_or_else(|| anyhow!("path {:?} does not exist", path))?;
+    }
+    Ok(value)
+}
+
+pub fn get_value_mut<'a>(
+    ctsemeta: &'a mut CTSEMeta,
+    path: &str,
+) -> Result<&'a mut InternalObjectDataValue> {
+    let (object_index, steps) = resolve(ctsemeta, path)?;
+    let mut value = &mut ctsemeta.internal_objects.internal_object[object_index].value;
+    for s in &steps {
+        value = step_mut(value, s).ok_or_else(|| anyhow!("path {:?} does not exist", path))?;
+    }
+    Ok(value)
+}
+
+fn parse_integer<T: TryFrom<i128>>(value: &str, variant: &str) -> Result<T> {
+    let integer: i128 = value
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("{:?} is not a valid {}", value, variant))?;
+    T::try_from(integer).map_err(|_| anyhow!("{} is out of range for {}", integer, variant))
+}
+
+fn parse_floats<const N: usize>(value: &str, variant: &str) -> Result<[f32; N]> {
+    value
+        .split(',')
+        .map(|x| x.trim().parse())
+        .collect::<Result<Vec<f32>, _>>()
+        .ok()
+        .and_then(|floats| floats.try_into().ok())
+        .ok_or_else(|| anyhow!("{:?} is not a valid {}", value, variant))
+}
+
+fn parse_bytes(value: &str, len: usize, variant: &str) -> Result<Vec<u8>> {
+    let value = value.trim();
+    let bytes = (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            value
+                .get(i..i + 2)
+                .and_then(|x| u8::from_str_radix(x, 16).ok())
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("{:?} is not a valid hex string for {}", value, variant))?;
+    if bytes.len() != len {
+        bail!(
+            "{} expects {} bytes but {:?} is {} bytes",
+            variant,
+            len,
+            value,
+            bytes.len()
+        );
+    }
+    Ok(bytes)
+}
+
+// The new value must parse as the same variant as the old one so the payload
+// still matches the types
+pub fn set_value(ctsemeta: &mut CTSEMeta, path: &str, value: &str) -> Result<()> {
+    let target = get_value_mut(ctsemeta, path)?;
+    *target = match &*target {
+        InternalObjectDataValue::Pointer(_) => {
+            InternalObjectDataValue::Pointer(parse_integer(value, "Pointer")?)
+        }
+        InternalObjectDataValue::CString(_) | InternalObjectDataValue::CStringRaw(_) => {
+            InternalObjectDataValue::CString(value.to_owned())
+        }
+        // Either an ident name or its ID
+        InternalObjectDataValue::IDENT(_) => {
+            InternalObjectDataValue::IDENT(match parse_integer(value, "IDENT") {
+                Ok(id) => IdentRef::Id(id),
+                Err(_) => IdentRef::Name(value.to_owned()),
+            })
+        }
+        InternalObjectDataValue::Bool(_) => InternalObjectDataValue::Bool(match value.trim() {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => bail!("{:?} is not a valid Bool", value),
+        }),
+        InternalObjectDataValue::UBYTE(_) => {
+            InternalObjectDataValue::UBYTE(parse_integer(value, "UBYTE")?)
+        }
+        InternalObjectDataValue::UWORD(_) => {
+            InternalObjectDataValue::UWORD(parse_integer(value, "UWORD")?)
+        }
+        InternalObjectDataValue::SWORD(_) => {
+            InternalObjectDataValue::SWORD(parse_integer(value, "SWORD")?)
+        }
+        InternalObjectDataValue::ULONG(_) => {
+            InternalObjectDataValue::ULONG(parse_integer(value, "ULONG")?)
+        }
+        InternalObjectDataValue::SLONG(_) => {
+            InternalObjectDataValue::SLONG(parse_integer(value, "SLONG")?)
+        }
+        InternalObjectDataValue::UINDEX(_) => {
+            InternalObjectDataValue::UINDEX(parse_integer(value, "UINDEX")?)
+        }
+        InternalObjectDataValue::UQUAD(_) => {
+            InternalObjectDataValue::UQUAD(parse_integer(value, "UQUAD")?)
+        }
+        InternalObjectDataValue::SQUAD(_) => {
+            InternalObjectDataValue::SQUAD(parse_integer(value, "SQUAD")?)
+        }
+        InternalObjectDataValue::FLOAT(_) => InternalObjectDataValue::FLOAT(
+            value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{:?} is not a valid FLOAT", value))?,
+        ),
+        InternalObjectDataValue::SFLOAT(_) => InternalObjectDataValue::SFLOAT(
+            value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{:?} is not a valid SFLOAT", value))?,
+        ),
+        InternalObjectDataValue::DOUBLE(_) => InternalObjectDataValue::DOUBLE(
+            value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{:?} is not a valid DOUBLE", value))?,
+        ),
+        InternalObjectDataValue::TIME(_) => InternalObjectDataValue::TIME(
+            value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{:?} is not a valid TIME", value))?,
+        ),
+        InternalObjectDataValue::TICK(_) => InternalObjectDataValue::TICK(
+            value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{:?} is not a valid TICK", value))?,
+        ),
+        InternalObjectDataValue::Primitive(bytes) => {
+            InternalObjectDataValue::Primitive(parse_bytes(value, bytes.len(), "Primitive")?)
+        }
+        InternalObjectDataValue::UBYTEEnum(_) => {
+            InternalObjectDataValue::UBYTEEnum(parse_integer(value, "UBYTEEnum")?)
+        }
+        InternalObjectDataValue::UWORDEnum(_) => {
+            InternalObjectDataValue::UWORDEnum(parse_integer(value, "UWORDEnum")?)
+        }
+        InternalObjectDataValue::SLONGEnum(_) => {
+            InternalObjectDataValue::SLONGEnum(parse_integer(value, "SLONGEnum")?)
+        }
+        InternalObjectDataValue::SQUADEnum(_) => {
+            InternalObjectDataValue::SQUADEnum(parse_integer(value, "SQUADEnum")?)
+        }
+        InternalObjectDataValue::Enum(bytes) => {
+            InternalObjectDataValue::Enum(parse_bytes(value, bytes.len(), "Enum")?)
+        }
+        InternalObjectDataValue::CSyncedSLONG(_) => {
+            InternalObjectDataValue::CSyncedSLONG(parse_integer(value, "CSyncedSLONG")?)
+        }
+        // Comma separated components, e.g. `1.5, 0, -2`
+        InternalObjectDataValue::Vec3D { .. } => {
+            let [x, y, z] = parse_floats(value, "Vec3D")?;
+            InternalObjectDataValue::Vec3D { x, y, z }
+        }
+        // The quaternion then the position
+        InternalObjectDataValue::Placement3D { .. } => {
+            let [qx, qy, qz, qw, x, y, z] = parse_floats(value, "Placement3D")?;
+            InternalObjectDataValue::Placement3D {
+                qx,
+                qy,
+                qz,
+                qw,
+                x,
+                y,
+                z,
+            }
+        }
+        // All 16 values row by row
+        InternalObjectDataValue::Matrix4x4(_) => {
+            InternalObjectDataValue::Matrix4x4(parse_floats(value, "Matrix4x4")?)
+        }
+        InternalObjectDataValue::Array(_)
+        | InternalObjectDataValue::Struct { .. }
+        | InternalObjectDataValue::StaticStackArray(_)
+        | InternalObjectDataValue::DynamicContainer(_)
+        | InternalObjectDataValue::DynamicContainerValues(_) => {
+            bail!("path {:?} is not a primitive value", path)
+        }
+    };
+    ctsemeta.add_missing_idents();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::{BinRead, BinWrite, Endian};
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue, synthetic};
+    use crate::helpers::ValidationOptions;
+    use crate::path::{get_value, set_value};
+
+    #[test]
+    fn get() {
+        let ctsemeta = synthetic();
+        assert!(matches!(
+            get_value(&ctsemeta, "objects/0/m_ulCount").unwrap(),
+            InternalObjectDataValue::ULONG(42)
+        ));
+        assert!(matches!(
+            get_value(&ctsemeta, "objects/1/members/3").unwrap(),
+            InternalObjectDataValue::Pointer(-1)
+        ));
+        assert!(get_value(&ctsemeta, "objects/2").is_err());
+        assert!(get_value(&ctsemeta, "objects/0/m_strMissing").is_err());
+        assert!(get_value(&ctsemeta, "objects/0/members/9").is_err());
+        assert!(get_value(&ctsemeta, "types/0").is_err());
+    }
+
+    #[test]
+    fn set_round_trip() {
+        let mut ctsemeta = synthetic();
+        set_value(&mut ctsemeta, "objects/0/m_ulCount", "1337").unwrap();
+        set_value(&mut ctsemeta, "objects/0/m_fTime", "2.75").unwrap();
+        set_value(&mut ctsemeta, "objects/1/m_strName", "Cloud_2_01").unwrap();
+
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let ctsemeta = CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            get_value(&ctsemeta, "objects/0/m_ulCount").unwrap(),
+            InternalObjectDataValue::ULONG(1337)
+        ));
+        assert!(matches!(
+            get_value(&ctsemeta, "objects/0/m_fTime").unwrap(),
+            InternalObjectDataValue::FLOAT(2.75)
+        ));
+        assert!(matches!(
+            get_value(&ctsemeta, "objects/1/m_strName").unwrap(),
+            InternalObjectDataValue::CString(x) if x == "Cloud_2_01"
+        ));
+    }
+
+    #[test]
+    fn set_type_check() {
+        let mut ctsemeta = synthetic();
+        let error = set_value(&mut ctsemeta, "objects/0/m_ulCount", "4294967296").unwrap_err();
+        assert_eq!(error.to_string(), "4294967296 is out of range for ULONG");
+        let error = set_value(&mut ctsemeta, "objects/0/m_ulCount", "-1").unwrap_err();
+        assert_eq!(error.to_string(), "-1 is out of range for ULONG");
+        let error = set_value(&mut ctsemeta, "objects/0/m_fTime", "fast").unwrap_err();
+        assert_eq!(error.to_string(), "\"fast\" is not a valid FLOAT");
+        assert!(set_value(&mut ctsemeta, "objects/0", "1").is_err());
+
+        // Failed sets leave the value alone
+        assert!(matches!(
+            get_value(&ctsemeta, "objects/0/m_ulCount").unwrap(),
+            InternalObjectDataValue::ULONG(42)
+        ));
+    }
+}
@@ -1,28 +1,38 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
-use anyhow::Result;
 use binrw::meta::WriteMagic;
-use binrw::{BinRead, BinWrite, Endian, args, binwrite};
-use flate2::bufread::GzDecoder;
+use binrw::{BinRead, BinWrite, Endian, args, binrw};
+use flate2::bufread::{GzDecoder, MultiGzDecoder};
+use flate2::write::GzEncoder;
 use flate2::{Compression, GzBuilder};
-use log::warn;
-use rand::RngCore;
+use rand::rngs::ThreadRng;
+use rand::{CryptoRng, RngCore};
 use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
-use rsa::traits::SignatureScheme;
+use rsa::traits::{PublicKeyParts, SignatureScheme};
 use rsa::{Pss, RsaPrivateKey, RsaPublicKey};
+use serde::Serialize;
 use sha1::{Digest, Sha1};
 use sha2::Sha256;
 use tiger::Tiger;
 
 use crate::helpers::{parse_pascal_string, write_pascal_string};
+use crate::logging::{event, warn};
 
-const SIGNATURE_STREAM_BLOCK_SIZE: u32 = 0x10000;
+pub const SIGNATURE_STREAM_BLOCK_SIZE: u32 = 0x10000;
 const SIGNATURE_STREAM_HASH_METHOD: HashMethod = HashMethod::Sha1;
+// Real saves are a few megabytes at most
+pub const MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
+const GZ_MAGIC: [u8; 2] = [0x1F, 0x8B];
+// Larger block sizes are clamped to this when reading
+pub const MAX_BLOCK_SIZE: u32 = 0x80000;
+const PSS_SALT_LEN: usize = 11;
 
-#[derive(Copy, Clone)]
-enum HashMethod {
+#[derive(Copy, Clone, Debug, Serialize)]
+pub enum HashMethod {
     Sha1 = 4,
     Tiger = 5,
     Sha256 = 6,
@@ -57,20 +67,237 @@ impl HashMethod {
     }
 
     fn new_pss(self) -> Pss {
-        const SALT_LEN: usize = 11;
         match self {
-            Self::Sha1 => Pss::new_with_salt::<Sha1>(SALT_LEN),
-            Self::Tiger => Pss::new_with_salt::<Tiger>(SALT_LEN),
-            Self::Sha256 => Pss::new_with_salt::<Sha256>(SALT_LEN),
+            Self::Sha1 => Pss::new_with_salt::<Sha1>(PSS_SALT_LEN),
+            Self::Tiger => Pss::new_with_salt::<Tiger>(PSS_SALT_LEN),
+            Self::Sha256 => Pss::new_with_salt::<Sha256>(PSS_SALT_LEN),
         }
     }
 
-    fn signature_size(self, private_key: &RsaPrivateKey) -> rsa::Result<usize> {
-        let mut rng = rand::thread_rng();
-        let hasher = Self::new_hasher(self);
-        let pss = Self::new_pss(self);
-        pss.sign(Some(&mut rng), private_key, &hasher.finalize())
-            .map(|x| x.len())
+    // PSS signatures are always as long as the modulus
+    fn signature_size(self, private_key: &RsaPrivateKey) -> usize {
+        private_key.size()
+    }
+
+    fn output_size(self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Tiger => 24,
+            Self::Sha256 => 32,
+        }
+    }
+
+    // PSS needs room in the modulus for the digest, the salt, and two more bytes
+    pub fn fits_key(self, private_key: &RsaPrivateKey) -> bool {
+        (private_key.n().bits() - 1).div_ceil(8) >= self.output_size() + PSS_SALT_LEN + 2
+    }
+}
+
+impl Default for HashMethod {
+    fn default() -> Self {
+        SIGNATURE_STREAM_HASH_METHOD
+    }
+}
+
+impl fmt::Display for HashMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sha1 => write!(f, "sha1"),
+            Self::Tiger => write!(f, "tiger"),
+            Self::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidHashMethod(pub String);
+
+impl fmt::Display for InvalidHashMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hash method must be sha1, tiger, or sha256, got {:?}",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidHashMethod {}
+
+impl FromStr for HashMethod {
+    type Err = InvalidHashMethod;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha1" => Ok(Self::Sha1),
+            "tiger" => Ok(Self::Tiger),
+            "sha256" => Ok(Self::Sha256),
+            _ => Err(InvalidHashMethod(s.to_owned())),
+        }
+    }
+}
+
+// Signature stream format version, newer versions add more header fields
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct SignatureStreamVersion(u32);
+
+impl SignatureStreamVersion {
+    // No memory stream name, userid, or signature fields
+    pub const V1: Self = Self(1);
+    pub const LATEST: Self = Self(5);
+}
+
+#[derive(Debug)]
+pub struct InvalidSignatureStreamVersion(pub u32);
+
+impl fmt::Display for InvalidSignatureStreamVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "version must be between {} and {}, got {}",
+            SignatureStreamVersion::V1,
+            SignatureStreamVersion::LATEST,
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidSignatureStreamVersion {}
+
+#[derive(Debug)]
+pub enum SignatureStreamError {
+    Io(std::io::Error),
+    // Anything binrw fails on that isn't covered by the other variants
+    Parse(binrw::Error),
+    BadMagic,
+    InvalidVersion(InvalidSignatureStreamVersion),
+    UnknownHashMethod(u32),
+    // Block size and signature size are both 0 so no block makes progress
+    EmptyBlocks,
+    TruncatedBlock(u32),
+    Signing(rsa::Error),
+    NotGzip,
+    NoGzExtraField,
+    NoCTExtraField,
+    // Which gzip header field, filename or comment
+    NullInGzField(&'static str),
+    // SignOptionsBuilder::build without a key ring
+    NoKeyRing,
+    // The limit a gzipped save decompressed past, or that its CT extra field
+    // says it will
+    TooLarge(u64),
+    InvalidBlockSize(u32),
+    // PSS can't fit the digest of the hash method in the key's modulus
+    KeyTooSmall {
+        key_name: String,
+        hash_method: HashMethod,
+    },
+}
+
+impl fmt::Display for SignatureStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Parse(e) => write!(f, "{}", e),
+            Self::BadMagic => write!(f, "not a signature stream"),
+            Self::InvalidVersion(e) => write!(f, "{}", e),
+            Self::UnknownHashMethod(id) => write!(f, "unknown hash method {}", id),
+            Self::EmptyBlocks => write!(f, "block size and signature size are both 0"),
+            Self::TruncatedBlock(block_index) => {
+                write!(f, "block {} is too short for its signature", block_index)
+            }
+            Self::Signing(e) => write!(f, "failed to sign: {}", e),
+            Self::NotGzip => write!(f, "not a gzip file"),
+            Self::NoGzExtraField => write!(f, "gzip header has no extra field"),
+            Self::NoCTExtraField => write!(f, "gzip extra field has no CT subfield"),
+            Self::NullInGzField(field) => write!(f, "gz {} contains a null byte", field),
+            Self::NoKeyRing => write!(f, "sign options need a key ring"),
+            Self::TooLarge(max_size) => {
+                write!(f, "decompressed save is larger than {} bytes", max_size)
+            }
+            Self::KeyTooSmall {
+                key_name,
+                hash_method,
+            } => write!(
+                f,
+                "key \"{}\" is too small to sign with {}",
+                key_name, hash_method
+            ),
+            Self::InvalidBlockSize(block_size) => write!(
+                f,
+                "block size must be between 1 and {:#x}, got {:#x}",
+                MAX_BLOCK_SIZE, block_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureStreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse(e) => Some(e),
+            Self::InvalidVersion(e) => Some(e),
+            Self::Signing(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SignatureStreamError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<binrw::Error> for SignatureStreamError {
+    fn from(value: binrw::Error) -> Self {
+        match value {
+            // Derived readers wrap their errors with context
+            binrw::Error::Backtrace(backtrace) => Self::from(*backtrace.error),
+            binrw::Error::Io(e) => Self::Io(e),
+            binrw::Error::BadMagic { .. } => Self::BadMagic,
+            e => Self::Parse(e),
+        }
+    }
+}
+
+impl From<InvalidSignatureStreamVersion> for SignatureStreamError {
+    fn from(value: InvalidSignatureStreamVersion) -> Self {
+        Self::InvalidVersion(value)
+    }
+}
+
+impl From<rsa::Error> for SignatureStreamError {
+    fn from(value: rsa::Error) -> Self {
+        Self::Signing(value)
+    }
+}
+
+// anyhow::Error picks this up through its blanket impl for std errors
+pub type Result<T> = std::result::Result<T, SignatureStreamError>;
+
+impl TryFrom<u32> for SignatureStreamVersion {
+    type Error = InvalidSignatureStreamVersion;
+
+    fn try_from(value: u32) -> std::result::Result<Self, Self::Error> {
+        if (Self::V1.0..=Self::LATEST.0).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(InvalidSignatureStreamVersion(value))
+        }
+    }
+}
+
+impl From<SignatureStreamVersion> for u32 {
+    fn from(value: SignatureStreamVersion) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for SignatureStreamVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
@@ -83,10 +310,19 @@ macro_rules! to_endian_bytes {
     };
 }
 
-#[derive(BinRead, BinWrite)]
-#[brw(magic = b"SIGSTRM12GIS")]
+#[derive(BinWrite)]
+#[bw(magic = b"SIGSTRM12GIS")]
 struct SignatureStreamMagic;
 
+// For telling what a file is from its first bytes
+pub fn starts_with_gz_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GZ_MAGIC)
+}
+
+pub fn starts_with_signature_stream_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(&<SignatureStreamMagic as WriteMagic>::MAGIC)
+}
+
 pub fn parse_gz_signature_stream_data<R: BufRead>(
     reader: &mut R,
     endian: Endian,
@@ -94,8 +330,89 @@ pub fn parse_gz_signature_stream_data<R: BufRead>(
     memory_stream_name: Option<impl AsRef<str>>,
     userid: Option<impl AsRef<str>>,
 ) -> Result<Box<[u8]>> {
-    let mut reader = GzDecoder::new(reader);
+    parse_gz_signature_stream_data_with_max_size(
+        reader,
+        endian,
+        key_ring,
+        memory_stream_name,
+        userid,
+        MAX_DECOMPRESSED_SIZE,
+    )
+}
+
+pub fn parse_gz_signature_stream_data_with_max_size<R: BufRead>(
+    reader: &mut R,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<impl AsRef<str>>,
+    userid: Option<impl AsRef<str>>,
+    max_size: u64,
+) -> Result<Box<[u8]>> {
+    let mut reader = limited_gz_decoder(reader, max_size)?;
+    let data =
+        parse_signature_stream_data(&mut reader, endian, key_ring, memory_stream_name, userid)
+            .map_err(|e| reader.check(e));
+    // The last block runs to the end of the gzip stream so anything after
+    // that is the only place for extra data to hide
+    let rest = reader.inner.into_inner();
+    if rest.fill_buf()?.starts_with(&GZ_MAGIC) {
+        warn!("the gzip stream has more than one member, only the first was read");
+        return data;
+    }
+    let data = data?;
+    let trailing = rest.read_to_end(&mut Vec::new())?;
+    if trailing > 0 {
+        warn!(
+            "ignoring {} bytes after the end of the gzip stream",
+            trailing
+        );
+    }
+    Ok(data)
+}
+
+// For gzip files made of several members one after the other, the signature
+// stream is all of them decompressed back to back. Unlike the single member
+// parser any bytes after the last member are an error.
+pub fn parse_multi_gz_signature_stream_data<R: BufRead>(
+    reader: &mut R,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<impl AsRef<str>>,
+    userid: Option<impl AsRef<str>>,
+) -> Result<Box<[u8]>> {
+    parse_multi_gz_signature_stream_data_with_max_size(
+        reader,
+        endian,
+        key_ring,
+        memory_stream_name,
+        userid,
+        MAX_DECOMPRESSED_SIZE,
+    )
+}
+
+pub fn parse_multi_gz_signature_stream_data_with_max_size<R: BufRead>(
+    reader: &mut R,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<impl AsRef<str>>,
+    userid: Option<impl AsRef<str>>,
+    max_size: u64,
+) -> Result<Box<[u8]>> {
+    let mut reader = LimitedReader {
+        inner: MultiGzDecoder::new(reader),
+        max_size,
+        read: 0,
+    };
     parse_signature_stream_data(&mut reader, endian, key_ring, memory_stream_name, userid)
+        .map_err(|e| reader.check(e))
+}
+
+// Decompresses the first member to see if another one starts after it
+pub fn is_multi_member_gz<R: BufRead>(reader: &mut R, max_size: u64) -> Result<bool> {
+    let mut reader = limited_gz_decoder(reader, max_size)?;
+    std::io::copy(&mut reader, &mut std::io::sink())
+        .map_err(|e| reader.check(SignatureStreamError::Io(e)))?;
+    Ok(reader.inner.into_inner().fill_buf()?.starts_with(&GZ_MAGIC))
 }
 
 pub fn parse_signature_stream_data<R: Read>(
@@ -105,11 +422,48 @@ pub fn parse_signature_stream_data<R: Read>(
     memory_stream_name: Option<impl AsRef<str>>,
     userid: Option<impl AsRef<str>>,
 ) -> Result<Box<[u8]>> {
+    parse_signature_stream(reader, endian, key_ring, memory_stream_name, userid)
+        .map(|(_, data)| data)
+}
+
+#[derive(Serialize)]
+pub struct SignatureStreamHeader {
+    pub version: SignatureStreamVersion,
+    pub block_size: u32,
+    pub hash_method: HashMethod,
+    pub hash_size: i32,
+    pub salt: u32,
+    pub has_memory_stream_name: bool,
+    pub has_userid: bool,
+    pub signature_related_string: Option<String>,
+    pub signature_size: u32,
+    pub sign_key_name: Option<String>,
+}
+
+// The header as it is on disk, the signature covers the exact flag values
+struct RawSignatureStreamHeader {
+    header: SignatureStreamHeader,
+    has_memory_stream_name: Option<u32>,
+    has_userid: Option<u32>,
+    signature: Option<(String, Vec<u8>)>,
+}
+
+fn read_signature_stream_header<R: Read>(
+    reader: &mut R,
+    endian: Endian,
+) -> Result<RawSignatureStreamHeader> {
     let mut reader = binrw::io::NoSeek::new(reader);
-    SignatureStreamMagic::read_options(&mut reader, endian, ())?;
-    let version = u32::read_options(&mut reader, endian, ())?;
-    let block_size = u32::read_options(&mut reader, endian, ())?.clamp(0, 0x80000);
+    // binrw would try to seek back on a mismatch which NoSeek can't do
+    let mut magic = [0u8; 12];
+    reader.read_exact(&mut magic)?;
+    if magic != <SignatureStreamMagic as WriteMagic>::MAGIC {
+        return Err(SignatureStreamError::BadMagic);
+    }
+    let version = SignatureStreamVersion::try_from(u32::read_options(&mut reader, endian, ())?)?;
+    let block_size = u32::read_options(&mut reader, endian, ())?.clamp(0, MAX_BLOCK_SIZE);
     let hash_method_id = u32::read_options(&mut reader, endian, ())?;
+    let hash_method = HashMethod::try_from(hash_method_id)
+        .map_err(|()| SignatureStreamError::UnknownHashMethod(hash_method_id))?;
     let hash_size = i32::read_options(&mut reader, endian, ())?.clamp(0, 0x1000);
     Vec::<u8>::read_options(
         &mut reader,
@@ -117,24 +471,24 @@ pub fn parse_signature_stream_data<R: Read>(
         args! { count: hash_size as usize, inner: () },
     )?;
     let salt = u32::read_options(&mut reader, endian, ())?;
-    let has_memory_stream_name = if version >= 2 {
+    let has_memory_stream_name = if version.0 >= 2 {
         Some(u32::read_options(&mut reader, endian, ())?)
     } else {
         None
     };
-    let has_userid = if version >= 3 {
+    let has_userid = if version.0 >= 3 {
         Some(u32::read_options(&mut reader, endian, ())?)
     } else {
         None
     };
-    let signature_related_string = if version >= 5 {
+    let signature_related_string = if version.0 >= 5 {
         Some(parse_pascal_string(&mut reader, endian, ())?)
     } else {
         None
     };
     let signature_size = u32::read_options(&mut reader, endian, ())?.clamp(0, 0x1000);
 
-    let signature_info = if version >= 3 && signature_size > 0 {
+    let signature = if version.0 >= 3 && signature_size > 0 {
         let sign_key_name = parse_pascal_string(&mut reader, endian, ())?;
         let signature = Vec::<u8>::read_options(
             &mut reader,
@@ -146,26 +500,188 @@ pub fn parse_signature_stream_data<R: Read>(
         None
     };
 
-    struct VerifyingInfo<'a> {
-        public_key: RsaPublicKey,
-        hash_method: HashMethod,
-        salt: u32,
-        memory_stream_name_bytes: Option<&'a [u8]>,
-        userid_bytes: Option<&'a [u8]>,
+    Ok(RawSignatureStreamHeader {
+        header: SignatureStreamHeader {
+            version,
+            block_size,
+            hash_method,
+            hash_size,
+            salt,
+            has_memory_stream_name: has_memory_stream_name.is_some_and(|x| x != 0),
+            has_userid: has_userid.is_some_and(|x| x != 0),
+            signature_related_string,
+            signature_size,
+            sign_key_name: signature
+                .as_ref()
+                .map(|(sign_key_name, _)| sign_key_name.clone()),
+        },
+        has_memory_stream_name,
+        has_userid,
+        signature,
+    })
+}
+
+// Reads up to the end of the header signature and stops, nothing is verified
+pub fn parse_signature_stream_header<R: Read>(
+    reader: &mut R,
+    endian: Endian,
+) -> Result<SignatureStreamHeader> {
+    read_signature_stream_header(reader, endian).map(|raw| raw.header)
+}
+
+pub fn parse_gz_signature_stream_header<R: BufRead>(
+    reader: &mut R,
+    endian: Endian,
+) -> Result<SignatureStreamHeader> {
+    parse_signature_stream_header(&mut GzDecoder::new(reader), endian)
+}
+
+// What a save's signature covers besides the data, verifying a save without
+// something it covers fails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SignatureRequirements {
+    pub memory_stream_name: bool,
+    pub userid: bool,
+}
+
+impl SignatureRequirements {
+    // The flags are written even when there's no signature to cover anything
+    pub fn from_header(header: &SignatureStreamHeader) -> Self {
+        let signed = header.sign_key_name.is_some();
+        Self {
+            memory_stream_name: signed && header.has_memory_stream_name,
+            userid: signed && header.has_userid,
+        }
+    }
+
+    // The ones that weren't provided
+    pub fn missing(self, options: VerifyOptions) -> Self {
+        Self {
+            memory_stream_name: self.memory_stream_name && options.memory_stream_name.is_none(),
+            userid: self.userid && options.userid.is_none(),
+        }
+    }
+
+    pub fn any(self) -> bool {
+        self.memory_stream_name || self.userid
+    }
+}
+
+// Reads only the header, so a memory stream name and userid can be asked for
+// before reading the whole save. Works on both gz and raw signature streams.
+pub fn probe_signature_requirements<R: BufRead>(
+    reader: &mut R,
+    endian: Endian,
+) -> Result<SignatureRequirements> {
+    let header = if starts_with_gz_magic(reader.fill_buf()?) {
+        parse_gz_signature_stream_header(reader, endian)?
+    } else {
+        parse_signature_stream_header(reader, endian)?
+    };
+    Ok(SignatureRequirements::from_header(&header))
+}
+
+// The requirements and which of them weren't provided when verifying
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SignatureCoverage {
+    pub covers: SignatureRequirements,
+    pub not_provided: SignatureRequirements,
+}
+
+impl SignatureCoverage {
+    pub fn new(header: &SignatureStreamHeader, options: VerifyOptions) -> Self {
+        let covers = SignatureRequirements::from_header(header);
+        Self {
+            covers,
+            not_provided: covers.missing(options),
+        }
     }
+}
+
+// Like "memory stream name (not provided), userid (provided)"
+impl fmt::Display for SignatureCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let provided = |not_provided| {
+            if not_provided {
+                "not provided"
+            } else {
+                "provided"
+            }
+        };
+        let mut covered = Vec::new();
+        if self.covers.memory_stream_name {
+            covered.push(format!(
+                "memory stream name ({})",
+                provided(self.not_provided.memory_stream_name)
+            ));
+        }
+        if self.covers.userid {
+            covered.push(format!("userid ({})", provided(self.not_provided.userid)));
+        }
+        if covered.is_empty() {
+            write!(f, "only the data")
+        } else {
+            write!(f, "{}", covered.join(", "))
+        }
+    }
+}
+
+struct VerifyingInfo<'a> {
+    public_key: RsaPublicKey,
+    hash_method: HashMethod,
+    salt: u32,
+    memory_stream_name_bytes: Option<&'a [u8]>,
+    userid_bytes: Option<&'a [u8]>,
+}
+
+// Checks the header signature, the info for checking the blocks is returned
+// even if it's invalid so every bad block is still reported. The bool is
+// whether the header signature is valid, None when it couldn't be checked.
+fn verify_header<'a>(
+    raw_header: RawSignatureStreamHeader,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<&'a str>,
+    userid: Option<&'a str>,
+) -> (
+    SignatureStreamHeader,
+    Option<VerifyingInfo<'a>>,
+    Option<bool>,
+) {
+    let RawSignatureStreamHeader {
+        header,
+        has_memory_stream_name,
+        has_userid,
+        signature: signature_info,
+    } = raw_header;
+    let SignatureStreamHeader {
+        version,
+        block_size,
+        hash_method,
+        hash_size,
+        salt,
+        signature_size,
+        ..
+    } = header;
+    let version = u32::from(version);
+    let hash_method_id = u32::from(hash_method);
+    event!(
+        debug,
+        version = version,
+        block_size = block_size,
+        hash_method = hash_method_id,
+        signature_size = signature_size;
+        "read signature stream header"
+    );
 
+    let mut header_valid = None;
     let verifying_info = (|| {
         let (sign_key_name, signature) = signature_info?;
         let Some(public_key) = key_ring
             .get(sign_key_name.as_str())
             .map(|keys| keys.public.clone())
         else {
-            warn!("no key \"{}\" in key ring", sign_key_name);
-            return None;
-        };
-
-        let Ok(hash_method) = <u32 as TryInto<HashMethod>>::try_into(hash_method_id) else {
-            warn!("unknown hash method {}", hash_method_id);
+            event!(warn, key_name = sign_key_name.as_str(); "no key \"{}\" in key ring", sign_key_name);
             return None;
         };
 
@@ -179,39 +695,48 @@ pub fn parse_signature_stream_data<R: Read>(
         let memory_stream_name_bytes = has_memory_stream_name.and_then(|has_memory_stream_name| {
             hasher.update(&to_endian_bytes!(endian, has_memory_stream_name));
             (has_memory_stream_name != 0).then(|| {
-                    let Some(memory_stream_name) = memory_stream_name.as_ref() else {
+                    let Some(memory_stream_name) = memory_stream_name else {
                         warn!("save requires memory stream name to be verified but one was not provided");
                         return None;
                     };
-                    let memory_stream_name_bytes = memory_stream_name.as_ref().as_bytes();
-                    hasher.update(memory_stream_name_bytes);
+                    let memory_stream_name_bytes = memory_stream_name.as_bytes();
+                    // Before version 4 only the blocks are signed with the name
+                    if version >= 4 {
+                        hasher.update(memory_stream_name_bytes);
+                    }
                     Some(memory_stream_name_bytes)
                 }).flatten()
         });
         let userid_bytes = has_userid.and_then(|has_userid| {
             hasher.update(&to_endian_bytes!(endian, has_userid));
             (has_userid != 0)
-            .then(|| {
-                let Some(userid) = userid.as_ref() else {
-                    warn!(
-                        "save requires memory stream name to be verified but one was not provided"
-                    );
-                    return None;
-                };
-                let userid_bytes = userid.as_ref().as_bytes();
-                hasher.update(userid_bytes);
-                Some(userid_bytes)
-            })
-            .flatten()
+                .then(|| {
+                    let Some(userid) = userid else {
+                        warn!("save requires userid to be verified but one was not provided");
+                        return None;
+                    };
+                    let userid_bytes = userid.as_bytes();
+                    hasher.update(userid_bytes);
+                    Some(userid_bytes)
+                })
+                .flatten()
         });
-        if let Some(signature_related_string) = signature_related_string {
+        if let Some(signature_related_string) = header.signature_related_string.as_ref() {
             hasher.update(signature_related_string.as_bytes());
         }
         hasher.update(&to_endian_bytes!(endian, signature_size));
         hasher.update(sign_key_name.as_bytes());
-        if let Err(e) = pss.verify(&public_key, &hasher.finalize(), &signature) {
-            warn!("invalid signature in header: {}", e);
+        let verified = pss.verify(&public_key, &hasher.finalize(), &signature);
+        if let Err(e) = &verified {
+            event!(
+                warn,
+                key_name = sign_key_name.as_str(),
+                hash_method = hash_method_id;
+                "invalid signature in header: {}",
+                e
+            );
         }
+        header_valid = Some(verified.is_ok());
 
         Some(VerifyingInfo {
             public_key,
@@ -222,6 +747,68 @@ pub fn parse_signature_stream_data<R: Read>(
         })
     })();
 
+    (header, verifying_info, header_valid)
+}
+
+// Warns and returns false if the block's signature is invalid
+fn verify_block(
+    verifying_info: &VerifyingInfo,
+    endian: Endian,
+    block_index: u32,
+    block_data: &[u8],
+    signature: &[u8],
+) -> bool {
+    let mut hasher = verifying_info.hash_method.new_hasher();
+    let pss = verifying_info.hash_method.new_pss();
+    hasher.update(&to_endian_bytes!(
+        endian,
+        verifying_info.salt ^ (block_index + 0xB1B)
+    ));
+    if let Some(memory_stream_name_bytes) = verifying_info.memory_stream_name_bytes {
+        hasher.update(memory_stream_name_bytes);
+    }
+    if let Some(userid_bytes) = verifying_info.userid_bytes {
+        hasher.update(userid_bytes);
+    }
+    hasher.update(block_data);
+    if let Err(e) = pss.verify(&verifying_info.public_key, &hasher.finalize(), signature) {
+        event!(
+            warn,
+            block_index = block_index,
+            hash_method = u32::from(verifying_info.hash_method);
+            "invalid signature for block {}: {}",
+            block_index,
+            e
+        );
+        return false;
+    }
+    true
+}
+
+// Same as parse_signature_stream_data but also returns the header fields
+pub fn parse_signature_stream<R: Read>(
+    reader: &mut R,
+    endian: Endian,
+    key_ring: &KeyRing,
+    memory_stream_name: Option<impl AsRef<str>>,
+    userid: Option<impl AsRef<str>>,
+) -> Result<(SignatureStreamHeader, Box<[u8]>)> {
+    let (header, verifying_info, _) = verify_header(
+        read_signature_stream_header(reader, endian)?,
+        endian,
+        key_ring,
+        memory_stream_name.as_ref().map(AsRef::as_ref),
+        userid.as_ref().map(AsRef::as_ref),
+    );
+    let block_size = header.block_size;
+    let signature_size = header.signature_size;
+    let mut reader = binrw::io::NoSeek::new(reader);
+
+    // Otherwise a block would never make progress
+    if block_size == 0 && signature_size == 0 {
+        return Err(SignatureStreamError::EmptyBlocks);
+    }
+
     let mut data = Vec::new();
     reader.read_to_end(&mut data)?;
     let mut reader = Cursor::new(&data);
@@ -244,7 +831,9 @@ pub fn parse_signature_stream_data<R: Read>(
             reader.read_exact(&mut signature_data)?;
             &deinterleaved_data[deinterleaved_data.len() - block_size as usize..]
         } else {
-            let short_block_size = remaining - signature_size as u64;
+            let Some(short_block_size) = remaining.checked_sub(signature_size as u64) else {
+                return Err(SignatureStreamError::TruncatedBlock(block_index));
+            };
             reader
                 .by_ref()
                 .take(short_block_size)
@@ -254,30 +843,178 @@ pub fn parse_signature_stream_data<R: Read>(
         };
 
         if let Some(verifying_info) = verifying_info.as_ref() {
-            let mut hasher = verifying_info.hash_method.new_hasher();
-            let pss = verifying_info.hash_method.new_pss();
-            hasher.update(&to_endian_bytes!(
+            verify_block(
+                verifying_info,
                 endian,
-                verifying_info.salt ^ (block_index + 0xB1B)
-            ));
-            if let Some(memory_stream_name_bytes) = verifying_info.memory_stream_name_bytes {
-                hasher.update(memory_stream_name_bytes);
-            }
-            if let Some(userid_bytes) = verifying_info.userid_bytes {
-                hasher.update(userid_bytes);
-            }
-            hasher.update(block_data);
-            if let Err(e) = pss.verify(
-                &verifying_info.public_key,
-                &hasher.finalize(),
+                block_index,
+                block_data,
                 &signature_data,
+            );
+        }
+    }
+
+    Ok((header, deinterleaved_data.into_boxed_slice()))
+}
+
+// The memory stream name and userid to verify a save with
+#[derive(Clone, Copy, Default)]
+pub struct VerifyOptions<'a> {
+    pub memory_stream_name: Option<&'a str>,
+    pub userid: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct VerificationReport {
+    // None when the header isn't signed or its key isn't in the key ring
+    pub header_valid: Option<bool>,
+    pub coverage: SignatureCoverage,
+    pub blocks: u32,
+    pub verified_blocks: u32,
+    pub invalid_blocks: Vec<u32>,
+}
+
+struct StreamState<'a> {
+    header: SignatureStreamHeader,
+    verifying_info: Option<VerifyingInfo<'a>>,
+    // One block followed by its signature
+    block: Vec<u8>,
+    block_len: usize,
+    position: usize,
+    done: bool,
+}
+
+// The streaming version of parse_signature_stream_data. The header is read on
+// the first read and each block is verified when it's reached, so no more than
+// one block and its signature are held at a time. Invalid signatures are
+// warned about like in the other parsers and collected in the report.
+pub struct SignatureStreamReader<'a, R> {
+    inner: R,
+    endian: Endian,
+    key_ring: &'a KeyRing<'a>,
+    options: VerifyOptions<'a>,
+    state: Option<StreamState<'a>>,
+    report: VerificationReport,
+}
+
+impl<'a, R: Read> SignatureStreamReader<'a, R> {
+    pub fn new(
+        inner: R,
+        endian: Endian,
+        key_ring: &'a KeyRing<'a>,
+        options: VerifyOptions<'a>,
+    ) -> Self {
+        Self {
+            inner,
+            endian,
+            key_ring,
+            options,
+            state: None,
+            report: VerificationReport::default(),
+        }
+    }
+
+    // None until the first read
+    pub fn header(&self) -> Option<&SignatureStreamHeader> {
+        self.state.as_ref().map(|state| &state.header)
+    }
+
+    // Complete once read has returned 0
+    pub fn report(&self) -> &VerificationReport {
+        &self.report
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let (header, verifying_info, header_valid) = verify_header(
+            read_signature_stream_header(&mut self.inner, self.endian)?,
+            self.endian,
+            self.key_ring,
+            self.options.memory_stream_name,
+            self.options.userid,
+        );
+        // Otherwise a block would never make progress
+        if header.block_size == 0 && header.signature_size == 0 {
+            return Err(SignatureStreamError::EmptyBlocks);
+        }
+        self.report.header_valid = header_valid;
+        self.report.coverage = SignatureCoverage::new(&header, self.options);
+        self.state = Some(StreamState {
+            block: vec![0; header.block_size as usize + header.signature_size as usize],
+            header,
+            verifying_info,
+            block_len: 0,
+            position: 0,
+            done: false,
+        });
+        Ok(())
+    }
+
+    // A block that doesn't fill the buffer is the last one
+    fn next_block(&mut self) -> Result<()> {
+        let Some(state) = self.state.as_mut() else {
+            return self.start();
+        };
+        let mut filled = 0;
+        while filled < state.block.len() {
+            match self.inner.read(&mut state.block[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        state.position = 0;
+        state.block_len = 0;
+        state.done = filled < state.block.len();
+        if filled == 0 {
+            return Ok(());
+        }
+
+        let block_index = self.report.blocks;
+        state.block_len = filled
+            .checked_sub(state.header.signature_size as usize)
+            .ok_or(SignatureStreamError::TruncatedBlock(block_index))?;
+        self.report.blocks += 1;
+        if let Some(verifying_info) = state.verifying_info.as_ref() {
+            let (block_data, signature) = state.block[..filled].split_at(state.block_len);
+            self.report.verified_blocks += 1;
+            if !verify_block(
+                verifying_info,
+                self.endian,
+                block_index,
+                block_data,
+                signature,
             ) {
-                warn!("invalid signature for block {}: {}", block_index, e);
+                self.report.invalid_blocks.push(block_index);
             }
         }
+        Ok(())
     }
+}
 
-    Ok(deinterleaved_data.into_boxed_slice())
+impl<R: Read> Read for SignatureStreamReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if let Some(state) = self.state.as_mut() {
+                if state.position < state.block_len {
+                    let n = buf.len().min(state.block_len - state.position);
+                    buf[..n].copy_from_slice(&state.block[state.position..state.position + n]);
+                    state.position += n;
+                    return Ok(n);
+                }
+                if state.done || buf.is_empty() {
+                    return Ok(0);
+                }
+            }
+            self.next_block().map_err(|e| match e {
+                SignatureStreamError::Io(e) => e,
+                e => std::io::Error::other(e),
+            })?;
+        }
+    }
 }
 
 pub struct SignOptions<'a, S: AsRef<str> + ?Sized, T: AsRef<str> + ?Sized, U: AsRef<str> + ?Sized> {
@@ -287,42 +1024,344 @@ pub struct SignOptions<'a, S: AsRef<str> + ?Sized, T: AsRef<str> + ?Sized, U: As
     pub userid: Option<&'a U>,
 }
 
-pub fn write_gz_signature_stream_data<
-    W: Write + Seek,
+// SignOptions along with the hash method to sign with, what SignOptionsBuilder
+// builds. SignOptions on its own signs with SHA-1 like the game.
+pub struct SignOptionsWithHash<
+    'a,
     S: AsRef<str> + ?Sized,
     T: AsRef<str> + ?Sized,
     U: AsRef<str> + ?Sized,
->(
+> {
+    pub options: SignOptions<'a, S, T, U>,
+    pub hash_method: HashMethod,
+}
+
+impl<'a, S: AsRef<str> + ?Sized, T: AsRef<str> + ?Sized, U: AsRef<str> + ?Sized> Deref
+    for SignOptionsWithHash<'a, S, T, U>
+{
+    type Target = SignOptions<'a, S, T, U>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.options
+    }
+}
+
+// What the writers take to sign with, either of the two above
+pub trait Signing<'a> {
+    type S: AsRef<str> + ?Sized + 'a;
+    type T: AsRef<str> + ?Sized + 'a;
+    type U: AsRef<str> + ?Sized + 'a;
+
+    fn sign_options(&self) -> &SignOptions<'a, Self::S, Self::T, Self::U>;
+
+    fn hash_method(&self) -> HashMethod {
+        HashMethod::default()
+    }
+}
+
+impl<'a, S: AsRef<str> + ?Sized + 'a, T: AsRef<str> + ?Sized + 'a, U: AsRef<str> + ?Sized + 'a>
+    Signing<'a> for SignOptions<'a, S, T, U>
+{
+    type S = S;
+    type T = T;
+    type U = U;
+
+    fn sign_options(&self) -> &Self {
+        self
+    }
+}
+
+impl<'a, S: AsRef<str> + ?Sized + 'a, T: AsRef<str> + ?Sized + 'a, U: AsRef<str> + ?Sized + 'a>
+    Signing<'a> for SignOptionsWithHash<'a, S, T, U>
+{
+    type S = S;
+    type T = T;
+    type U = U;
+
+    fn sign_options(&self) -> &SignOptions<'a, S, T, U> {
+        &self.options
+    }
+
+    fn hash_method(&self) -> HashMethod {
+        self.hash_method
+    }
+}
+
+// Builds SignOptions without the three string type parameters, the key name
+// defaults to the game's local key and the hash method to SHA-1 like the game
+#[derive(Default)]
+pub struct SignOptionsBuilder<'a> {
+    key_ring: Option<&'a KeyRing<'a>>,
+    sign_key_name: Option<String>,
+    memory_stream_name: Option<String>,
+    userid: Option<String>,
+    hash_method: HashMethod,
+}
+
+impl<'a> SignOptionsBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key_ring(mut self, key_ring: &'a KeyRing<'a>) -> Self {
+        self.key_ring = Some(key_ring);
+        self
+    }
+
+    pub fn sign_key_name(mut self, sign_key_name: impl Into<String>) -> Self {
+        self.sign_key_name = Some(sign_key_name.into());
+        self
+    }
+
+    pub fn memory_stream_name(mut self, memory_stream_name: Option<impl Into<String>>) -> Self {
+        self.memory_stream_name = memory_stream_name.map(Into::into);
+        self
+    }
+
+    pub fn userid(mut self, userid: Option<impl Into<String>>) -> Self {
+        self.userid = userid.map(Into::into);
+        self
+    }
+
+    pub fn hash_method(mut self, hash_method: HashMethod) -> Self {
+        self.hash_method = hash_method;
+        self
+    }
+
+    // The options borrow the strings so the builder has to outlive them. A
+    // missing key is only warned about when signing, but one that's too small
+    // for the hash method is an error here.
+    pub fn build(&self) -> Result<SignOptionsWithHash<'_, str, String, String>> {
+        let key_ring = self.key_ring.ok_or(SignatureStreamError::NoKeyRing)?;
+        let sign_key_name = self
+            .sign_key_name
+            .as_deref()
+            .unwrap_or(SIGN_KEY_GAME_LOCAL_NAME);
+        let hash_method = self.hash_method;
+        if let Some(private_key) = key_ring
+            .get(sign_key_name)
+            .and_then(|keys| keys.private.as_ref())
+        {
+            if !hash_method.fits_key(private_key) {
+                return Err(SignatureStreamError::KeyTooSmall {
+                    key_name: sign_key_name.to_owned(),
+                    hash_method,
+                });
+            }
+        }
+        Ok(SignOptionsWithHash {
+            options: SignOptions {
+                key_ring,
+                sign_key_name,
+                memory_stream_name: self.memory_stream_name.as_ref(),
+                userid: self.userid.as_ref(),
+            },
+            hash_method,
+        })
+    }
+}
+
+// Optional gzip header fields, the game doesn't write these but some tools do
+#[derive(Default)]
+pub struct GzOptions<'a> {
+    pub filename: Option<&'a str>,
+    pub comment: Option<&'a str>,
+}
+
+pub fn write_gz_signature_stream_data<'a, W: Write + Seek, O: Signing<'a> + ?Sized>(
+    writer: &mut W,
+    endian: Endian,
+    sign_options: Option<&O>,
+    version: SignatureStreamVersion,
+    gz_options: &GzOptions,
+    data: &[u8],
+) -> Result<()> {
+    write_gz(writer, gz_options, |writer| {
+        write_signature_stream_data(writer, endian, sign_options, version, data)
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn write_gz_signature_stream_data_with_rng<'a, W: Write + Seek, O: Signing<'a> + ?Sized>(
     writer: &mut W,
     endian: Endian,
-    sign_options: Option<&SignOptions<S, T, U>>,
-    version: u32,
+    sign_options: Option<&O>,
+    version: SignatureStreamVersion,
+    gz_options: &GzOptions,
     data: &[u8],
+    rng: &mut (impl RngCore + CryptoRng),
+    salt: Option<u32>,
+) -> Result<()> {
+    write_gz(writer, gz_options, |writer| {
+        write_signature_stream_data_with_rng(writer, endian, sign_options, version, data, rng, salt)
+    })
+}
+
+// Wraps an already written signature stream without touching its signatures
+pub fn write_gz_signature_stream<W: Write + Seek>(
+    writer: &mut W,
+    gz_options: &GzOptions,
+    signature_stream: &[u8],
+) -> Result<()> {
+    write_gz(writer, gz_options, |writer| {
+        writer.write_all(signature_stream)?;
+        Ok(signature_stream.len())
+    })
+}
+
+// Unwraps a signature stream without verifying or deinterleaving it
+pub fn read_gz_signature_stream<R: BufRead>(reader: &mut R) -> Result<Vec<u8>> {
+    read_gz_signature_stream_with_max_size(reader, MAX_DECOMPRESSED_SIZE)
+}
+
+pub fn read_gz_signature_stream_with_max_size<R: BufRead>(
+    reader: &mut R,
+    max_size: u64,
+) -> Result<Vec<u8>> {
+    let mut reader = limited_gz_decoder(reader, max_size)?;
+    let mut signature_stream = Vec::new();
+    reader
+        .read_to_end(&mut signature_stream)
+        .map_err(|e| reader.check(SignatureStreamError::Io(e)))?;
+    Ok(signature_stream)
+}
+
+// Errors instead of decompressing more than max_size bytes so a small gzip
+// bomb can't use up all the memory
+struct LimitedReader<R> {
+    inner: R,
+    max_size: u64,
+    read: u64,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        if self.read > self.max_size {
+            return Err(std::io::Error::other(SignatureStreamError::TooLarge(
+                self.max_size,
+            )));
+        }
+        Ok(n)
+    }
+}
+
+impl<R> LimitedReader<R> {
+    // Whatever the limit turned into on the way up is reported as TooLarge
+    fn check(&self, e: SignatureStreamError) -> SignatureStreamError {
+        if self.read > self.max_size {
+            SignatureStreamError::TooLarge(self.max_size)
+        } else {
+            e
+        }
+    }
+}
+
+// The CT extra field has the decompressed size so most bombs are caught before
+// decompressing anything
+fn limited_gz_decoder<R: BufRead>(reader: R, max_size: u64) -> Result<LimitedReader<GzDecoder<R>>> {
+    let decoder = GzDecoder::new(reader);
+    let advertised = decoder
+        .header()
+        .and_then(|header| header.extra())
+        .and_then(|extra| ExtraFieldCT::read(&mut Cursor::new(extra)).ok());
+    if let Some(extra_field) = advertised {
+        if extra_field.decompressed_size() as u64 > max_size {
+            return Err(SignatureStreamError::TooLarge(max_size));
+        }
+    }
+    Ok(LimitedReader {
+        inner: decoder,
+        max_size,
+        read: 0,
+    })
+}
+
+// Croteam sizes prefix extra field
+#[binrw]
+#[brw(little, magic = b"CT")]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtraFieldCT {
+    #[br(temp, assert(field_data_length == 8, "CT extra field has length {}", field_data_length))]
+    #[bw(calc = 8)]
+    field_data_length: u16,
+    compressed_size: u32,
+    decompressed_size: u32,
+}
+
+impl ExtraFieldCT {
+    // Size of the deflate data, not counting the gzip header and footer
+    pub fn compressed_size(&self) -> u32 {
+        self.compressed_size
+    }
+
+    pub fn decompressed_size(&self) -> u32 {
+        self.decompressed_size
+    }
+}
+
+// Reads the CT field from the gzip header without decompressing anything
+pub fn read_gz_extra_field<R: Read + Seek>(reader: &mut R) -> Result<ExtraFieldCT> {
+    const FEXTRA: u8 = 0x04;
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header)?;
+    if header[..2] != [0x1F, 0x8B] {
+        return Err(SignatureStreamError::NotGzip);
+    }
+    if header[3] & FEXTRA == 0 {
+        return Err(SignatureStreamError::NoGzExtraField);
+    }
+
+    let extra_length = u16::read_le(reader)?;
+    let extra_end = reader.stream_position()? + extra_length as u64;
+    // The extra field is a list of subfields with a two byte ID and a length
+    while reader.stream_position()? + 4 <= extra_end {
+        let mut id = [0u8; 2];
+        reader.read_exact(&mut id)?;
+        if &id == b"CT" {
+            reader.seek(SeekFrom::Current(-2))?;
+            return Ok(ExtraFieldCT::read(reader)?);
+        }
+        let length = u16::read_le(reader)?;
+        reader.seek(SeekFrom::Current(length as i64))?;
+    }
+    Err(SignatureStreamError::NoCTExtraField)
+}
+
+fn write_gz<W: Write + Seek>(
+    writer: &mut W,
+    gz_options: &GzOptions,
+    write_contents: impl FnOnce(&mut GzEncoder<&mut W>) -> Result<usize>,
 ) -> Result<()> {
-    let mut writer = GzBuilder::new()
-        .extra([0u8; 0xC])
-        .operating_system(0)
-        .write(writer, Compression::new(6));
+    let writer_start_pos = writer.stream_position()?;
+    let mut gzip_header_size: u64 = 0x18;
+    let mut builder = GzBuilder::new().extra([0u8; 0xC]).operating_system(0);
+    // The null terminated strings come after the extra field so the CT field
+    // offset doesn't change
+    if let Some(filename) = gz_options.filename {
+        if filename.contains('\0') {
+            return Err(SignatureStreamError::NullInGzField("filename"));
+        }
+        builder = builder.filename(filename);
+        gzip_header_size += filename.len() as u64 + 1;
+    }
+    if let Some(comment) = gz_options.comment {
+        if comment.contains('\0') {
+            return Err(SignatureStreamError::NullInGzField("comment"));
+        }
+        builder = builder.comment(comment);
+        gzip_header_size += comment.len() as u64 + 1;
+    }
+    let mut writer = builder.write(writer, Compression::new(6));
 
-    let decompressed_size =
-        write_signature_stream_data(&mut writer, endian, sign_options, version, data)?;
+    let decompressed_size = write_contents(&mut writer)?;
 
     let mut writer = writer.finish()?;
     let writer_end_pos = writer.stream_position()?;
-    const GZIP_HEADER_SIZE: u64 = 0x18;
     const GZIP_FOOTER_SIZE: u64 = 0x8;
-    let compressed_size = writer_end_pos - GZIP_HEADER_SIZE - GZIP_FOOTER_SIZE; // flate2 is annoying
-    writer.seek(SeekFrom::Start(0xC))?;
-
-    // Croteam sizes prefix extra field
-    #[binwrite]
-    #[bw(little, magic = b"CT")]
-    struct ExtraFieldCT {
-        #[bw(calc = 8)]
-        field_data_length: u16,
-        compressed_size: u32,
-        decompressed_size: u32,
-    }
+    let compressed_size = writer_end_pos - writer_start_pos - gzip_header_size - GZIP_FOOTER_SIZE; // flate2 is annoying
+    writer.seek(SeekFrom::Start(writer_start_pos + 0xC))?;
 
     ExtraFieldCT {
         compressed_size: compressed_size as u32,
@@ -333,181 +1372,276 @@ pub fn write_gz_signature_stream_data<
     Ok(())
 }
 
-pub fn write_signature_stream_data<
-    W: Write,
-    S: AsRef<str> + ?Sized,
-    T: AsRef<str> + ?Sized,
-    U: AsRef<str> + ?Sized,
->(
+pub fn write_signature_stream_data<'a, W: Write, O: Signing<'a> + ?Sized>(
     writer: &mut W,
     endian: Endian,
-    sign_options: Option<&SignOptions<S, T, U>>,
-    version: u32,
+    sign_options: Option<&O>,
+    version: SignatureStreamVersion,
     data: &[u8],
 ) -> Result<usize> {
-    let mut writer = binrw::io::NoSeek::new(writer);
-    let mut rng = rand::thread_rng();
-    let signature_stream_block_size = SIGNATURE_STREAM_BLOCK_SIZE;
-    let hash_method = SIGNATURE_STREAM_HASH_METHOD;
-    let hash_method_id = <HashMethod as Into<u32>>::into(hash_method);
-    let hash_size = 0i32;
-    let salt = rng.next_u32();
-    let (has_memory_stream_name, has_userid) = sign_options
-        .map(|sign_options| {
-            (
-                sign_options.memory_stream_name.is_some() as u32,
-                sign_options.userid.is_some() as u32,
-            )
-        })
-        .unwrap_or_default();
-    let signature_related_string = "";
-
-    SignatureStreamMagic.write(&mut writer)?;
-    let mut decompressed_size = <SignatureStreamMagic as WriteMagic>::MAGIC.len();
-    version.write_options(&mut writer, endian, ())?;
-    decompressed_size += 4;
-    signature_stream_block_size.write_options(&mut writer, endian, ())?;
-    decompressed_size += 4;
-    hash_method_id.write_options(&mut writer, endian, ())?;
-    decompressed_size += 4;
-    hash_size.write_options(&mut writer, endian, ())?;
-    decompressed_size += 4;
-    salt.write_options(&mut writer, endian, ())?;
-    decompressed_size += 4;
-    if version >= 2 {
-        has_memory_stream_name.write_options(&mut writer, endian, ())?;
-        decompressed_size += 4;
+    write_signature_stream_data_with_rng(
+        writer,
+        endian,
+        sign_options,
+        version,
+        data,
+        &mut rand::thread_rng(),
+        None,
+    )
+}
+
+// The same save every time for a seeded rng, the header salt is drawn from the
+// rng unless it's given
+pub fn write_signature_stream_data_with_rng<'a, W: Write, O: Signing<'a> + ?Sized>(
+    writer: &mut W,
+    endian: Endian,
+    sign_options: Option<&O>,
+    version: SignatureStreamVersion,
+    data: &[u8],
+    rng: &mut (impl RngCore + CryptoRng),
+    salt: Option<u32>,
+) -> Result<usize> {
+    let mut writer = SignatureStreamWriter::with_rng(
+        writer,
+        endian,
+        sign_options,
+        version,
+        SIGNATURE_STREAM_BLOCK_SIZE,
+        rng,
+        salt,
+    )?;
+    writer.push(data)?;
+    writer.finish()
+}
+
+struct SigningInfo<'a> {
+    private_key: RsaPrivateKey,
+    hash_method: HashMethod,
+    salt: u32,
+    memory_stream_name_bytes: Option<&'a [u8]>,
+    userid_bytes: Option<&'a [u8]>,
+}
+
+// The streaming version of write_signature_stream_data. The header is written
+// by new and every block is signed and written as soon as it fills up, the last
+// short block is written by finish.
+pub struct SignatureStreamWriter<'a, W: Write, G: RngCore + CryptoRng = ThreadRng> {
+    inner: W,
+    endian: Endian,
+    block_size: usize,
+    signing_info: Option<SigningInfo<'a>>,
+    rng: G,
+    block: Vec<u8>,
+    block_index: u32,
+    decompressed_size: usize,
+}
+
+impl<'a, W: Write> SignatureStreamWriter<'a, W> {
+    pub fn new<O: Signing<'a> + ?Sized>(
+        inner: W,
+        endian: Endian,
+        sign_options: Option<&O>,
+        version: SignatureStreamVersion,
+        block_size: u32,
+    ) -> Result<Self> {
+        Self::with_rng(
+            inner,
+            endian,
+            sign_options,
+            version,
+            block_size,
+            rand::thread_rng(),
+            None,
+        )
     }
-    if version >= 3 {
-        has_userid.write_options(&mut writer, endian, ())?;
+}
+
+impl<'a, W: Write, G: RngCore + CryptoRng> SignatureStreamWriter<'a, W, G> {
+    // The header salt is drawn from the rng unless it's given
+    pub fn with_rng<O: Signing<'a> + ?Sized>(
+        mut inner: W,
+        endian: Endian,
+        sign_options: Option<&O>,
+        version: SignatureStreamVersion,
+        block_size: u32,
+        mut rng: G,
+        salt: Option<u32>,
+    ) -> Result<Self> {
+        if !(1..=MAX_BLOCK_SIZE).contains(&block_size) {
+            return Err(SignatureStreamError::InvalidBlockSize(block_size));
+        }
+        let version = u32::from(version);
+        let mut writer = binrw::io::NoSeek::new(&mut inner);
+        // Unsigned saves still name one
+        let hash_method = sign_options
+            .map(|sign_options| sign_options.hash_method())
+            .unwrap_or_default();
+        let sign_options = sign_options.map(|sign_options| sign_options.sign_options());
+        let hash_method_id = <HashMethod as Into<u32>>::into(hash_method);
+        let hash_size = 0i32;
+        let salt = salt.unwrap_or_else(|| rng.next_u32());
+        let (has_memory_stream_name, has_userid) = sign_options
+            .map(|sign_options| {
+                (
+                    sign_options.memory_stream_name.is_some() as u32,
+                    sign_options.userid.is_some() as u32,
+                )
+            })
+            .unwrap_or_default();
+        let signature_related_string = "";
+
+        SignatureStreamMagic.write(&mut writer)?;
+        let mut decompressed_size = <SignatureStreamMagic as WriteMagic>::MAGIC.len();
+        version.write_options(&mut writer, endian, ())?;
         decompressed_size += 4;
-    }
-    if version >= 5 {
-        write_pascal_string(&signature_related_string, &mut writer, endian, ())?;
-        decompressed_size += 4 + signature_related_string.len();
-    }
-
-    let (header_signature_stuff_size, signing_info) = if version >= 3 {
-        struct SigningInfo<'a> {
-            private_key: RsaPrivateKey,
-            hash_method: HashMethod,
-            salt: u32,
-            memory_stream_name_bytes: Option<&'a [u8]>,
-            userid_bytes: Option<&'a [u8]>,
-        }
-
-        if let Some(sign_options) = sign_options.as_ref() {
-            if let Some(private_key) = sign_options
-                .key_ring
-                .get(sign_options.sign_key_name.as_ref())
-                .and_then(|keys| keys.private.clone())
-            {
-                let mut hasher = hash_method.new_hasher();
-                let pss = hash_method.new_pss();
-                hasher.update(&to_endian_bytes!(endian, version));
-                hasher.update(&to_endian_bytes!(endian, signature_stream_block_size));
-                hasher.update(&to_endian_bytes!(endian, hash_method_id));
-                hasher.update(&to_endian_bytes!(endian, hash_size));
-                hasher.update(&to_endian_bytes!(endian, salt));
-                if version >= 2 {
-                    hasher.update(&to_endian_bytes!(endian, has_memory_stream_name));
-                    if version >= 4 {
-                        if let Some(memory_stream_name) = sign_options.memory_stream_name.as_ref() {
-                            hasher.update(memory_stream_name.as_ref().as_bytes());
+        block_size.write_options(&mut writer, endian, ())?;
+        decompressed_size += 4;
+        hash_method_id.write_options(&mut writer, endian, ())?;
+        decompressed_size += 4;
+        hash_size.write_options(&mut writer, endian, ())?;
+        decompressed_size += 4;
+        salt.write_options(&mut writer, endian, ())?;
+        decompressed_size += 4;
+        if version >= 2 {
+            has_memory_stream_name.write_options(&mut writer, endian, ())?;
+            decompressed_size += 4;
+        }
+        if version >= 3 {
+            has_userid.write_options(&mut writer, endian, ())?;
+            decompressed_size += 4;
+        }
+        if version >= 5 {
+            write_pascal_string(&signature_related_string, &mut writer, endian, ())?;
+            decompressed_size += 4 + signature_related_string.len();
+        }
+
+        let (header_signature_stuff_size, signing_info) = if version >= 3 {
+            if let Some(sign_options) = sign_options.as_ref() {
+                if let Some(private_key) = sign_options
+                    .key_ring
+                    .get(sign_options.sign_key_name.as_ref())
+                    .and_then(|keys| keys.private.clone())
+                {
+                    let mut hasher = hash_method.new_hasher();
+                    let pss = hash_method.new_pss();
+                    hasher.update(&to_endian_bytes!(endian, version));
+                    hasher.update(&to_endian_bytes!(endian, block_size));
+                    hasher.update(&to_endian_bytes!(endian, hash_method_id));
+                    hasher.update(&to_endian_bytes!(endian, hash_size));
+                    hasher.update(&to_endian_bytes!(endian, salt));
+                    if version >= 2 {
+                        hasher.update(&to_endian_bytes!(endian, has_memory_stream_name));
+                        if version >= 4 {
+                            if let Some(memory_stream_name) = sign_options.memory_stream_name {
+                                hasher.update(memory_stream_name.as_ref().as_bytes());
+                            }
                         }
                     }
-                }
-                if version >= 3 {
-                    hasher.update(&to_endian_bytes!(endian, has_userid));
-                    if let Some(userid) = sign_options.userid.as_ref() {
-                        hasher.update(userid.as_ref().as_bytes());
+                    if version >= 3 {
+                        hasher.update(&to_endian_bytes!(endian, has_userid));
+                        if let Some(userid) = sign_options.userid {
+                            hasher.update(userid.as_ref().as_bytes());
+                        }
                     }
-                }
-                if version >= 5 {
-                    hasher.update(signature_related_string.as_bytes());
-                }
-                match hash_method.signature_size(&private_key) {
-                    Err(e) => {
-                        warn!("failed to sign header: {}", e);
-                        0u32.write_options(&mut writer, endian, ())?;
-                        (4, None)
+                    if version >= 5 {
+                        hasher.update(signature_related_string.as_bytes());
                     }
-                    Ok(signature_size) => {
-                        hasher.update(&to_endian_bytes!(endian, signature_size as u32));
-                        hasher.update(sign_options.sign_key_name.as_ref().as_bytes());
-                        match pss.sign(Some(&mut rng), &private_key, &hasher.finalize()) {
-                            Err(e) => {
-                                warn!("failed to sign header: {}", e);
-                                0u32.write_options(&mut writer, endian, ())?;
-                                (4, None)
-                            }
-                            Ok(signature) => {
-                                let signature_size = signature.len() as u32;
-                                signature_size.write_options(&mut writer, endian, ())?;
-                                write_pascal_string(
-                                    sign_options.sign_key_name,
-                                    &mut writer,
-                                    endian,
-                                    (),
-                                )?;
-                                signature.write(&mut writer)?;
-                                (
-                                    4 + 4
-                                        + sign_options.sign_key_name.as_ref().len()
-                                        + signature.len(),
-                                    Some(SigningInfo {
-                                        private_key,
-                                        hash_method,
-                                        salt,
-                                        memory_stream_name_bytes: sign_options
-                                            .memory_stream_name
-                                            .as_ref()
-                                            .map(|x| x.as_ref().as_bytes()),
-                                        userid_bytes: sign_options
-                                            .userid
-                                            .as_ref()
-                                            .map(|x| x.as_ref().as_bytes()),
-                                    }),
-                                )
-                            }
+                    let signature_size = hash_method.signature_size(&private_key);
+                    hasher.update(&to_endian_bytes!(endian, signature_size as u32));
+                    hasher.update(sign_options.sign_key_name.as_ref().as_bytes());
+                    match pss.sign(Some(&mut rng), &private_key, &hasher.finalize()) {
+                        Err(e) => {
+                            event!(
+                                warn,
+                                key_name = sign_options.sign_key_name.as_ref(),
+                                hash_method = u32::from(hash_method);
+                                "failed to sign header: {}",
+                                e
+                            );
+                            0u32.write_options(&mut writer, endian, ())?;
+                            (4, None)
+                        }
+                        Ok(signature) => {
+                            // The size is signed before the signature exists
+                            debug_assert_eq!(signature.len(), signature_size);
+                            (signature_size as u32).write_options(&mut writer, endian, ())?;
+                            write_pascal_string(
+                                sign_options.sign_key_name,
+                                &mut writer,
+                                endian,
+                                (),
+                            )?;
+                            signature.write(&mut writer)?;
+                            (
+                                4 + 4 + sign_options.sign_key_name.as_ref().len() + signature.len(),
+                                Some(SigningInfo {
+                                    private_key,
+                                    hash_method,
+                                    salt,
+                                    memory_stream_name_bytes: sign_options
+                                        .memory_stream_name
+                                        .map(|x| x.as_ref().as_bytes()),
+                                    userid_bytes: sign_options
+                                        .userid
+                                        .map(|x| x.as_ref().as_bytes()),
+                                }),
+                            )
                         }
                     }
+                } else {
+                    event!(
+                        warn,
+                        key_name = sign_options.sign_key_name.as_ref();
+                        "no private key \"{}\" in key ring",
+                        sign_options.sign_key_name.as_ref()
+                    );
+                    0u32.write_options(&mut writer, endian, ())?;
+                    (4, None)
                 }
             } else {
-                warn!(
-                    "no private key \"{}\" in key ring",
-                    sign_options.sign_key_name.as_ref()
-                );
                 0u32.write_options(&mut writer, endian, ())?;
                 (4, None)
             }
         } else {
             0u32.write_options(&mut writer, endian, ())?;
             (4, None)
-        }
-    } else {
-        0u32.write_options(&mut writer, endian, ())?;
-        (4, None)
-    };
+        };
 
-    decompressed_size += header_signature_stuff_size;
+        decompressed_size += header_signature_stuff_size;
 
-    for block_index in 0.. {
-        let start = block_index as usize * signature_stream_block_size as usize;
-        if start >= data.len() {
-            break;
+        Ok(Self {
+            inner,
+            endian,
+            block_size: block_size as usize,
+            signing_info,
+            rng,
+            block: Vec::with_capacity(block_size as usize),
+            block_index: 0,
+            decompressed_size,
+        })
+    }
+
+    fn push(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let n = data.len().min(self.block_size - self.block.len());
+            self.block.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.block.len() == self.block_size {
+                self.write_block()?;
+            }
         }
-        let end =
-            ((block_index as usize + 1) * signature_stream_block_size as usize).min(data.len());
-        let block_data = &data[start..end];
-        writer.write_all(block_data)?;
-        decompressed_size += block_data.len();
-        if let Some(signing_info) = signing_info.as_ref() {
+        Ok(())
+    }
+
+    fn write_block(&mut self) -> Result<()> {
+        self.inner.write_all(&self.block)?;
+        self.decompressed_size += self.block.len();
+        if let Some(signing_info) = self.signing_info.as_ref() {
             let mut hasher = signing_info.hash_method.new_hasher();
             let pss = signing_info.hash_method.new_pss();
             hasher.update(&to_endian_bytes!(
-                endian,
-                signing_info.salt ^ (block_index + 0xB1B)
+                self.endian,
+                signing_info.salt ^ (self.block_index + 0xB1B)
             ));
             if let Some(memory_stream_name_bytes) = signing_info.memory_stream_name_bytes {
                 hasher.update(memory_stream_name_bytes);
@@ -515,18 +1649,45 @@ pub fn write_signature_stream_data<
             if let Some(userid_bytes) = signing_info.userid_bytes {
                 hasher.update(userid_bytes);
             }
-            hasher.update(block_data);
+            hasher.update(&self.block);
             let signature = pss.sign(
-                Some(&mut rng),
+                Some(&mut self.rng),
                 &signing_info.private_key,
                 &hasher.finalize(),
             )?;
-            writer.write_all(&signature)?;
-            decompressed_size += signature.len();
+            self.inner.write_all(&signature)?;
+            self.decompressed_size += signature.len();
+        }
+        self.block.clear();
+        self.block_index += 1;
+        Ok(())
+    }
+
+    // Writes the last block and returns the size of the whole signature stream,
+    // dropping the writer without this loses the last block
+    pub fn finish(mut self) -> Result<usize> {
+        if !self.block.is_empty() {
+            self.write_block()?;
         }
+        self.inner.flush()?;
+        Ok(self.decompressed_size)
+    }
+}
+
+impl<W: Write, G: RngCore + CryptoRng> Write for SignatureStreamWriter<'_, W, G> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.block_size - self.block.len());
+        self.push(&buf[..n]).map_err(|e| match e {
+            SignatureStreamError::Io(e) => e,
+            e => std::io::Error::other(e),
+        })?;
+        Ok(n)
     }
 
-    Ok(decompressed_size)
+    // Only what's in full blocks, the last one can't be signed until finish
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 pub struct RsaKeys {
@@ -686,3 +1847,1217 @@ impl Default for KeyRing<'_> {
         key_ring
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::{Cursor, Read, Write};
+    use std::sync::Once;
+
+    use binrw::Endian;
+    use flate2::bufread::GzDecoder;
+    use flate2::{Compression, GzBuilder};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand_chacha::ChaCha20Rng;
+    use rsa::RsaPrivateKey;
+    use rsa::traits::SignatureScheme;
+
+    use crate::signature_stream::{
+        GzOptions,
+        HashMethod,
+        KeyRing,
+        MAX_BLOCK_SIZE,
+        MAX_DECOMPRESSED_SIZE,
+        RsaKeys,
+        SIGN_KEY_GAME_LOCAL_NAME,
+        SIGNATURE_STREAM_BLOCK_SIZE,
+        SignOptions,
+        SignOptionsBuilder,
+        SignOptionsWithHash,
+        SignatureCoverage,
+        SignatureRequirements,
+        SignatureStreamError,
+        SignatureStreamReader,
+        SignatureStreamVersion,
+        SignatureStreamWriter,
+        VerificationReport,
+        VerifyOptions,
+        is_multi_member_gz,
+        parse_gz_signature_stream_data,
+        parse_gz_signature_stream_data_with_max_size,
+        parse_multi_gz_signature_stream_data,
+        parse_signature_stream_data,
+        parse_signature_stream_header,
+        probe_signature_requirements,
+        read_gz_extra_field,
+        read_gz_signature_stream,
+        read_gz_signature_stream_with_max_size,
+        write_gz,
+        write_gz_signature_stream,
+        write_gz_signature_stream_data,
+        write_gz_signature_stream_data_with_rng,
+        write_signature_stream_data,
+        write_signature_stream_data_with_rng,
+    };
+
+    // Records warnings per thread so tests running in parallel don't see each
+    // other's logs
+    struct CaptureLogger;
+
+    thread_local! {
+        static CAPTURED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                CAPTURED.with(|captured| captured.borrow_mut().push(record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&CaptureLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+
+        CAPTURED.with(|captured| captured.borrow_mut().clear());
+        let result = f();
+        (result, CAPTURED.with(|captured| captured.take()))
+    }
+
+    fn sha256_signature_stream(data: &[u8]) -> Vec<u8> {
+        let key_ring = KeyRing::default();
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&SignOptionsWithHash {
+                options: SignOptions {
+                    key_ring: &key_ring,
+                    sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
+                    memory_stream_name: Some("<memory stream:PlayerProfile.dat>"),
+                    userid: None::<&str>,
+                },
+                hash_method: HashMethod::Sha256,
+            }),
+            SignatureStreamVersion::LATEST,
+            data,
+        )
+        .unwrap();
+        signature_stream
+    }
+
+    #[test]
+    fn signed_versions_round_trip() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let memory_stream_name = "<memory stream:PlayerProfile.dat>";
+        let userid = "76561197960287930";
+        // Version 3 signs the header without the memory stream name, 4 adds it
+        for version in 3..=5 {
+            let version = SignatureStreamVersion::try_from(version).unwrap();
+            let mut signature_stream = Vec::new();
+            write_signature_stream_data(
+                &mut signature_stream,
+                Endian::Little,
+                Some(
+                    &SignOptionsBuilder::new()
+                        .key_ring(&key_ring)
+                        .memory_stream_name(Some(memory_stream_name))
+                        .userid(Some(userid))
+                        .build()
+                        .unwrap(),
+                ),
+                version,
+                &data,
+            )
+            .unwrap();
+
+            let header =
+                parse_signature_stream_header(&mut signature_stream.as_slice(), Endian::Little)
+                    .unwrap();
+            assert_eq!(header.version, version);
+            assert!(header.has_memory_stream_name);
+            assert!(header.has_userid);
+
+            let (parsed, logs) = capture_logs(|| {
+                parse_signature_stream_data(
+                    &mut signature_stream.as_slice(),
+                    Endian::Little,
+                    &key_ring,
+                    Some(memory_stream_name),
+                    Some(userid),
+                )
+                .unwrap()
+            });
+            assert_eq!(parsed.as_ref(), data.as_slice());
+            assert!(
+                logs.is_empty(),
+                "version {}: unexpected warnings: {:?}",
+                version,
+                logs
+            );
+        }
+    }
+
+    #[test]
+    fn sha256_block_hashes() {
+        let key_ring = KeyRing::default();
+        // More than one block so the block index goes into the hash
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let signature_stream = sha256_signature_stream(&data);
+
+        let (parsed, logs) = capture_logs(|| {
+            parse_signature_stream_data(
+                &mut signature_stream.as_slice(),
+                Endian::Little,
+                &key_ring,
+                Some("<memory stream:PlayerProfile.dat>"),
+                None::<&str>,
+            )
+            .unwrap()
+        });
+        assert_eq!(parsed.as_ref(), data.as_slice());
+        assert!(logs.is_empty(), "unexpected warnings: {:?}", logs);
+    }
+
+    #[test]
+    fn sha256_tampered_block() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let mut signature_stream = sha256_signature_stream(&data);
+
+        // Flip a byte well inside the first block, past the header
+        let position = signature_stream.len() / 4;
+        signature_stream[position] ^= 0xFF;
+
+        let (_, logs) = capture_logs(|| {
+            parse_signature_stream_data(
+                &mut signature_stream.as_slice(),
+                Endian::Little,
+                &key_ring,
+                Some("<memory stream:PlayerProfile.dat>"),
+                None::<&str>,
+            )
+            .unwrap()
+        });
+        assert_eq!(logs.len(), 1, "unexpected warnings: {:?}", logs);
+        assert!(logs[0].starts_with("invalid signature for block 0"));
+    }
+
+    #[test]
+    fn signature_requirements() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough";
+        let memory_stream_name = "<memory stream:PlayerProfile.dat>";
+        let userid = "1100001075d8dea";
+        let sign_options_builder = SignOptionsBuilder::new()
+            .key_ring(&key_ring)
+            .memory_stream_name(Some(memory_stream_name))
+            .userid(Some(userid));
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&sign_options_builder.build().unwrap()),
+            SignatureStreamVersion::LATEST,
+            data,
+        )
+        .unwrap();
+        let mut gz = Cursor::new(Vec::new());
+        write_gz_signature_stream_data(
+            &mut gz,
+            Endian::Little,
+            Some(&sign_options_builder.build().unwrap()),
+            SignatureStreamVersion::LATEST,
+            &GzOptions::default(),
+            data,
+        )
+        .unwrap();
+
+        let both = SignatureRequirements {
+            memory_stream_name: true,
+            userid: true,
+        };
+        assert_eq!(
+            probe_signature_requirements(&mut signature_stream.as_slice(), Endian::Little).unwrap(),
+            both
+        );
+        assert_eq!(
+            probe_signature_requirements(&mut gz.into_inner().as_slice(), Endian::Little).unwrap(),
+            both
+        );
+
+        // Verifying without them says so, and why the header is invalid
+        let ((coverage, header_valid), logs) = capture_logs(|| {
+            let mut reader = SignatureStreamReader::new(
+                signature_stream.as_slice(),
+                Endian::Little,
+                &key_ring,
+                VerifyOptions {
+                    memory_stream_name: Some(memory_stream_name),
+                    userid: None,
+                },
+            );
+            std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+            (reader.report().coverage, reader.report().header_valid)
+        });
+        assert_eq!(header_valid, Some(false));
+        assert_eq!(
+            coverage.to_string(),
+            "memory stream name (provided), userid (not provided)"
+        );
+        assert_eq!(
+            logs[0],
+            "save requires userid to be verified but one was not provided"
+        );
+
+        // Nothing is covered without a signature, whatever the flags say
+        let mut unsigned = Vec::new();
+        write_signature_stream_data(
+            &mut unsigned,
+            Endian::Little,
+            None::<&SignOptions<str, str, str>>,
+            SignatureStreamVersion::LATEST,
+            data,
+        )
+        .unwrap();
+        let requirements =
+            probe_signature_requirements(&mut unsigned.as_slice(), Endian::Little).unwrap();
+        assert!(!requirements.any());
+        assert_eq!(
+            SignatureCoverage {
+                covers: requirements,
+                not_provided: requirements,
+            }
+            .to_string(),
+            "only the data"
+        );
+    }
+
+    #[test]
+    fn signature_stream_reader() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let mut signature_stream = sha256_signature_stream(&data);
+        let options = VerifyOptions {
+            memory_stream_name: Some("<memory stream:PlayerProfile.dat>"),
+            userid: None,
+        };
+        let read_all = |signature_stream: &[u8]| {
+            let mut reader =
+                SignatureStreamReader::new(signature_stream, Endian::Little, &key_ring, options);
+            assert!(reader.header().is_none());
+            let mut read = Vec::new();
+            let mut buf = [0; 7777];
+            let result = loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break Ok(read),
+                    Ok(n) => read.extend_from_slice(&buf[..n]),
+                    Err(e) => break Err(e),
+                }
+            };
+            (result, reader.report().clone())
+        };
+
+        let (read, report) = read_all(&signature_stream);
+        assert_eq!(read.unwrap(), data);
+        assert_eq!(
+            report,
+            VerificationReport {
+                header_valid: Some(true),
+                coverage: SignatureCoverage {
+                    covers: SignatureRequirements {
+                        memory_stream_name: true,
+                        userid: false,
+                    },
+                    not_provided: SignatureRequirements::default(),
+                },
+                blocks: 2,
+                verified_blocks: 2,
+                invalid_blocks: Vec::new(),
+            }
+        );
+
+        let position = signature_stream.len() / 4;
+        signature_stream[position] ^= 0xFF;
+        let ((read, report), logs) = capture_logs(|| read_all(&signature_stream));
+        assert_eq!(read.unwrap().len(), data.len());
+        assert_eq!(report.invalid_blocks, [0]);
+        assert_eq!(logs.len(), 1, "unexpected warnings: {:?}", logs);
+
+        // Shorter than the last block's signature
+        let signature_size =
+            parse_signature_stream_header(&mut signature_stream.as_slice(), Endian::Little)
+                .unwrap()
+                .signature_size as usize;
+        let header_size = signature_stream.len() - data.len() - 2 * signature_size;
+        let (read, _) = read_all(&signature_stream[..header_size + 10]);
+        assert_eq!(
+            read.unwrap_err().to_string(),
+            "block 0 is too short for its signature"
+        );
+    }
+
+    #[test]
+    fn signature_stream_writer() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let memory_stream_name = "<memory stream:PlayerProfile.dat>";
+        let sign_options = SignOptions {
+            key_ring: &key_ring,
+            sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
+            memory_stream_name: Some(memory_stream_name),
+            userid: None::<&str>,
+        };
+        let parse = |signature_stream: &[u8]| {
+            capture_logs(|| {
+                parse_signature_stream_data(
+                    &mut &signature_stream[..],
+                    Endian::Little,
+                    &key_ring,
+                    Some(memory_stream_name),
+                    None::<&str>,
+                )
+                .unwrap()
+            })
+        };
+
+        let mut signature_stream = Vec::new();
+        let mut writer = SignatureStreamWriter::new(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&sign_options),
+            SignatureStreamVersion::LATEST,
+            0x1000,
+        )
+        .unwrap();
+        for chunk in data.chunks(777) {
+            writer.write_all(chunk).unwrap();
+        }
+        let size = writer.finish().unwrap();
+        assert_eq!(size, signature_stream.len());
+        let (parsed, logs) = parse(&signature_stream);
+        assert_eq!(parsed.as_ref(), data.as_slice());
+        assert!(logs.is_empty(), "unexpected warnings: {:?}", logs);
+
+        // The same bytes as writing it all at once
+        let mut signature_stream = Vec::new();
+        let mut writer = SignatureStreamWriter::with_rng(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&sign_options),
+            SignatureStreamVersion::LATEST,
+            SIGNATURE_STREAM_BLOCK_SIZE,
+            ChaCha20Rng::seed_from_u64(7),
+            None,
+        )
+        .unwrap();
+        writer.write_all(&data).unwrap();
+        writer.finish().unwrap();
+        let mut expected = Vec::new();
+        write_signature_stream_data_with_rng(
+            &mut expected,
+            Endian::Little,
+            Some(&sign_options),
+            SignatureStreamVersion::LATEST,
+            &data,
+            &mut ChaCha20Rng::seed_from_u64(7),
+            None,
+        )
+        .unwrap();
+        assert_eq!(signature_stream, expected);
+
+        let mut save = Cursor::new(Vec::new());
+        write_gz(&mut save, &GzOptions::default(), |gz| {
+            let mut writer = SignatureStreamWriter::new(
+                gz,
+                Endian::Little,
+                Some(&sign_options),
+                SignatureStreamVersion::LATEST,
+                SIGNATURE_STREAM_BLOCK_SIZE,
+            )?;
+            writer.write_all(&data)?;
+            writer.finish()
+        })
+        .unwrap();
+        // The CT extra field has to match for this to parse without warnings
+        let (parsed, logs) = capture_logs(|| {
+            parse_gz_signature_stream_data(
+                &mut save.get_ref().as_slice(),
+                Endian::Little,
+                &key_ring,
+                Some(memory_stream_name),
+                None::<&str>,
+            )
+            .unwrap()
+        });
+        assert_eq!(parsed.as_ref(), data.as_slice());
+        assert!(logs.is_empty(), "unexpected warnings: {:?}", logs);
+
+        for block_size in [0, MAX_BLOCK_SIZE + 1] {
+            let result = SignatureStreamWriter::new(
+                Vec::new(),
+                Endian::Little,
+                Some(&sign_options),
+                SignatureStreamVersion::LATEST,
+                block_size,
+            );
+            assert!(matches!(
+                result,
+                Err(SignatureStreamError::InvalidBlockSize(x)) if x == block_size
+            ));
+        }
+    }
+
+    #[test]
+    fn hash_method_strings() {
+        for (hash_method, name) in [
+            (HashMethod::Sha1, "sha1"),
+            (HashMethod::Tiger, "tiger"),
+            (HashMethod::Sha256, "sha256"),
+        ] {
+            assert_eq!(hash_method.to_string(), name);
+            assert_eq!(
+                u32::from(name.parse::<HashMethod>().unwrap()),
+                u32::from(hash_method)
+            );
+            assert_eq!(
+                u32::from(name.to_uppercase().parse::<HashMethod>().unwrap()),
+                u32::from(hash_method)
+            );
+        }
+        assert_eq!(
+            "md5".parse::<HashMethod>().unwrap_err().to_string(),
+            "hash method must be sha1, tiger, or sha256, got \"md5\""
+        );
+    }
+
+    #[test]
+    fn hash_method_key_size() {
+        let mut key_ring = KeyRing::default();
+        // 44 bytes of modulus fits a SHA-1 or Tiger digest but not SHA-256
+        let private_key = RsaPrivateKey::new(&mut ChaCha20Rng::seed_from_u64(7), 352).unwrap();
+        key_ring.insert(
+            "small",
+            RsaKeys {
+                public: private_key.to_public_key(),
+                private: Some(private_key),
+            },
+        );
+        let build = |hash_method| {
+            SignOptionsBuilder::new()
+                .key_ring(&key_ring)
+                .sign_key_name("small")
+                .hash_method(hash_method)
+                .build()
+                .map(|sign_options| sign_options.hash_method)
+        };
+        assert!(build(HashMethod::Sha1).is_ok());
+        assert!(build(HashMethod::Tiger).is_ok());
+        assert_eq!(
+            build(HashMethod::Sha256).unwrap_err().to_string(),
+            "key \"small\" is too small to sign with sha256"
+        );
+
+        // Every method fits the game's keys, and is what the header says
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        for hash_method in [HashMethod::Sha1, HashMethod::Tiger, HashMethod::Sha256] {
+            let builder = SignOptionsBuilder::new()
+                .key_ring(&key_ring)
+                .memory_stream_name(Some("<memory stream:PlayerProfile.dat>"))
+                .hash_method(hash_method);
+            let mut signature_stream = Vec::new();
+            write_signature_stream_data(
+                &mut signature_stream,
+                Endian::Little,
+                Some(&builder.build().unwrap()),
+                SignatureStreamVersion::LATEST,
+                &data,
+            )
+            .unwrap();
+            let header =
+                parse_signature_stream_header(&mut signature_stream.as_slice(), Endian::Little)
+                    .unwrap();
+            assert_eq!(u32::from(header.hash_method), u32::from(hash_method));
+            let (parsed, logs) = capture_logs(|| {
+                parse_signature_stream_data(
+                    &mut signature_stream.as_slice(),
+                    Endian::Little,
+                    &key_ring,
+                    Some("<memory stream:PlayerProfile.dat>"),
+                    None::<&str>,
+                )
+                .unwrap()
+            });
+            assert_eq!(parsed.as_ref(), data.as_slice());
+            assert!(logs.is_empty(), "unexpected warnings: {:?}", logs);
+        }
+
+        // SignOptions without a hash method signs like the game
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&SignOptions {
+                key_ring: &key_ring,
+                sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
+                memory_stream_name: None::<&str>,
+                userid: None::<&str>,
+            }),
+            SignatureStreamVersion::LATEST,
+            &data,
+        )
+        .unwrap();
+        let header =
+            parse_signature_stream_header(&mut signature_stream.as_slice(), Endian::Little)
+                .unwrap();
+        assert_eq!(u32::from(header.hash_method), u32::from(HashMethod::Sha1));
+    }
+
+    #[test]
+    fn seeded_rng() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let write = |seed: u64, salt: Option<u32>| {
+            let mut writer = Cursor::new(Vec::new());
+            write_gz_signature_stream_data_with_rng(
+                &mut writer,
+                Endian::Little,
+                Some(
+                    &SignOptionsBuilder::new()
+                        .key_ring(&key_ring)
+                        .memory_stream_name(Some("<memory stream:PlayerProfile.dat>"))
+                        .build()
+                        .unwrap(),
+                ),
+                SignatureStreamVersion::LATEST,
+                &GzOptions::default(),
+                &data,
+                &mut StdRng::seed_from_u64(seed),
+                salt,
+            )
+            .unwrap();
+            writer.into_inner()
+        };
+
+        assert_eq!(write(1, None), write(1, None));
+        assert_ne!(write(1, None), write(2, None));
+
+        let save = write(1, Some(0x1234));
+        assert_eq!(save, write(1, Some(0x1234)));
+        let signature_stream = read_gz_signature_stream(&mut save.as_slice()).unwrap();
+        let header =
+            parse_signature_stream_header(&mut signature_stream.as_slice(), Endian::Little)
+                .unwrap();
+        assert_eq!(header.salt, 0x1234);
+    }
+
+    #[test]
+    fn caller_rng() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let memory_stream_name = "<memory stream:PlayerProfile.dat>";
+        let write = |rng: &mut ChaCha20Rng| {
+            let mut signature_stream = Vec::new();
+            write_signature_stream_data_with_rng(
+                &mut signature_stream,
+                Endian::Little,
+                Some(
+                    &SignOptionsBuilder::new()
+                        .key_ring(&key_ring)
+                        .memory_stream_name(Some(memory_stream_name))
+                        .build()
+                        .unwrap(),
+                ),
+                SignatureStreamVersion::LATEST,
+                &data,
+                rng,
+                None,
+            )
+            .unwrap();
+            signature_stream
+        };
+
+        let signature_stream = write(&mut ChaCha20Rng::seed_from_u64(7));
+        assert_eq!(signature_stream, write(&mut ChaCha20Rng::seed_from_u64(7)));
+        // The rng carries on from where it was left
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        write(&mut rng);
+        assert_ne!(write(&mut rng), signature_stream);
+
+        let (parsed, logs) = capture_logs(|| {
+            parse_signature_stream_data(
+                &mut signature_stream.as_slice(),
+                Endian::Little,
+                &key_ring,
+                Some(memory_stream_name),
+                None::<&str>,
+            )
+            .unwrap()
+        });
+        assert_eq!(parsed.as_ref(), data.as_slice());
+        assert!(logs.is_empty(), "unexpected warnings: {:?}", logs);
+    }
+
+    #[test]
+    fn signature_size() {
+        let key_ring = KeyRing::default();
+        let private_key = key_ring
+            .get(SIGN_KEY_GAME_LOCAL_NAME)
+            .and_then(|keys| keys.private.clone())
+            .unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        for hash_method in [HashMethod::Sha1, HashMethod::Tiger, HashMethod::Sha256] {
+            let signature = hash_method
+                .new_pss()
+                .sign(
+                    Some(&mut rng),
+                    &private_key,
+                    &hash_method.new_hasher().finalize(),
+                )
+                .unwrap();
+            assert_eq!(hash_method.signature_size(&private_key), signature.len());
+        }
+    }
+
+    #[test]
+    fn signature_stream_version() {
+        assert_eq!(u32::from(SignatureStreamVersion::try_from(3).unwrap()), 3);
+        assert_eq!(
+            SignatureStreamVersion::try_from(999)
+                .unwrap_err()
+                .to_string(),
+            "version must be between 1 and 5, got 999"
+        );
+        assert!(SignatureStreamVersion::try_from(0).is_err());
+    }
+
+    #[test]
+    fn version_1_header() {
+        let data = b"CTSEMETA not really but close enough";
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            None::<&SignOptions<str, str, str>>,
+            SignatureStreamVersion::V1,
+            data,
+        )
+        .unwrap();
+
+        // Magic, version, block size, hash method, hash size, salt, signature size
+        assert_eq!(signature_stream.len(), 12 + 6 * 4 + data.len());
+        let parsed = parse_signature_stream_data(
+            &mut signature_stream.as_slice(),
+            Endian::Little,
+            &KeyRing::default(),
+            None::<&str>,
+            None::<&str>,
+        )
+        .unwrap();
+        assert_eq!(parsed.as_ref(), data.as_slice());
+    }
+
+    #[test]
+    fn sign_options_builder() {
+        assert!(matches!(
+            SignOptionsBuilder::new().build(),
+            Err(SignatureStreamError::NoKeyRing)
+        ));
+
+        let key_ring = KeyRing::default();
+        let builder = SignOptionsBuilder::new().key_ring(&key_ring);
+        let sign_options = builder.build().unwrap();
+        assert_eq!(sign_options.sign_key_name, SIGN_KEY_GAME_LOCAL_NAME);
+        assert!(sign_options.memory_stream_name.is_none());
+        assert!(sign_options.userid.is_none());
+
+        let builder = builder
+            .sign_key_name("Other")
+            .memory_stream_name(Some("PlayerProfile"))
+            .userid(Some(String::from("12345")));
+        let sign_options = builder.build().unwrap();
+        assert_eq!(sign_options.sign_key_name, "Other");
+        assert_eq!(sign_options.memory_stream_name.unwrap(), "PlayerProfile");
+        assert_eq!(sign_options.userid.unwrap(), "12345");
+    }
+
+    #[test]
+    fn gz_filename_and_comment() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(100);
+
+        let mut writer = Cursor::new(Vec::new());
+        write_gz_signature_stream_data(
+            &mut writer,
+            Endian::Little,
+            Some(
+                &SignOptionsBuilder::new()
+                    .key_ring(&key_ring)
+                    .build()
+                    .unwrap(),
+            ),
+            SignatureStreamVersion::LATEST,
+            &GzOptions {
+                filename: Some("PlayerProfile.dat"),
+                comment: Some("SeriousSaveEditor"),
+            },
+            &data,
+        )
+        .unwrap();
+        let gz = writer.into_inner();
+
+        let mut decoder = GzDecoder::new(gz.as_slice());
+        let mut decompressed = Vec::new();
+        decoder.read_to_end(&mut decompressed).unwrap();
+        let header = decoder.header().unwrap();
+        assert_eq!(header.filename(), Some(b"PlayerProfile.dat".as_slice()));
+        assert_eq!(header.comment(), Some(b"SeriousSaveEditor".as_slice()));
+
+        // The CT extra field must account for the variable sized header
+        let extra = header.extra().unwrap();
+        assert_eq!(&extra[..4], b"CT\x08\x00");
+        let compressed_size = u32::from_le_bytes(extra[4..8].try_into().unwrap()) as usize;
+        let decompressed_size = u32::from_le_bytes(extra[8..12].try_into().unwrap()) as usize;
+        let gzip_header_size = 0x18 + "PlayerProfile.dat".len() + 1 + "SeriousSaveEditor".len() + 1;
+        assert_eq!(compressed_size, gz.len() - gzip_header_size - 8);
+        assert_eq!(decompressed_size, decompressed.len());
+
+        let extra_field = read_gz_extra_field(&mut Cursor::new(&gz)).unwrap();
+        assert_eq!(extra_field.compressed_size() as usize, compressed_size);
+        assert_eq!(extra_field.decompressed_size() as usize, decompressed_size);
+        assert!(read_gz_extra_field(&mut Cursor::new(&decompressed)).is_err());
+
+        let parsed = parse_gz_signature_stream_data(
+            &mut Cursor::new(&gz),
+            Endian::Little,
+            &key_ring,
+            None::<&str>,
+            None::<&str>,
+        )
+        .unwrap();
+        assert_eq!(parsed.as_ref(), data.as_slice());
+    }
+
+    #[test]
+    fn gz_bomb() {
+        let key_ring = KeyRing::default();
+        let max_size = 0x10000;
+        let parse = |gz: &[u8]| {
+            parse_gz_signature_stream_data_with_max_size(
+                &mut Cursor::new(gz),
+                Endian::Little,
+                &key_ring,
+                None::<&str>,
+                None::<&str>,
+                max_size,
+            )
+        };
+
+        // A megabyte of zeros is a couple of kilobytes compressed, without the
+        // CT extra field it is only caught while decompressing
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            None::<&SignOptions<str, str, str>>,
+            SignatureStreamVersion::LATEST,
+            &vec![0; 0x100000],
+        )
+        .unwrap();
+        let mut encoder = GzBuilder::new().write(Vec::new(), Compression::best());
+        encoder.write_all(&signature_stream).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert!(bomb.len() < 0x2000);
+        assert!(matches!(
+            parse(&bomb),
+            Err(SignatureStreamError::TooLarge(0x10000))
+        ));
+        assert!(matches!(
+            read_gz_signature_stream_with_max_size(&mut bomb.as_slice(), max_size),
+            Err(SignatureStreamError::TooLarge(0x10000))
+        ));
+        assert_eq!(
+            SignatureStreamError::TooLarge(max_size).to_string(),
+            "decompressed save is larger than 65536 bytes"
+        );
+        // The default limit is far above it
+        assert_eq!(
+            read_gz_signature_stream(&mut bomb.as_slice()).unwrap(),
+            signature_stream
+        );
+
+        // A small save whose CT extra field claims it is huge is refused
+        // before anything is decompressed
+        let mut writer = Cursor::new(Vec::new());
+        write_gz_signature_stream_data(
+            &mut writer,
+            Endian::Little,
+            None::<&SignOptions<str, str, str>>,
+            SignatureStreamVersion::LATEST,
+            &GzOptions::default(),
+            b"CTSEMETA",
+        )
+        .unwrap();
+        let mut gz = writer.into_inner();
+        assert!(parse(&gz).is_ok());
+        gz[20..24].copy_from_slice(&0x10001u32.to_le_bytes());
+        assert!(matches!(
+            parse(&gz),
+            Err(SignatureStreamError::TooLarge(0x10000))
+        ));
+    }
+
+    #[test]
+    fn gz_trailing_bytes() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(100);
+        let mut writer = Cursor::new(Vec::new());
+        write_gz_signature_stream_data(
+            &mut writer,
+            Endian::Little,
+            Some(
+                &SignOptionsBuilder::new()
+                    .key_ring(&key_ring)
+                    .build()
+                    .unwrap(),
+            ),
+            SignatureStreamVersion::LATEST,
+            &GzOptions::default(),
+            &data,
+        )
+        .unwrap();
+
+        for trailing in [1, 4096] {
+            let mut gz = writer.get_ref().clone();
+            gz.resize(gz.len() + trailing, 0xAA);
+            let (parsed, logs) = capture_logs(|| {
+                parse_gz_signature_stream_data(
+                    &mut Cursor::new(&gz),
+                    Endian::Little,
+                    &key_ring,
+                    None::<&str>,
+                    None::<&str>,
+                )
+                .unwrap()
+            });
+            assert_eq!(parsed.as_ref(), data.as_slice());
+            assert_eq!(
+                logs,
+                [format!(
+                    "ignoring {} bytes after the end of the gzip stream",
+                    trailing
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn multi_member_gz() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(
+                &SignOptionsBuilder::new()
+                    .key_ring(&key_ring)
+                    .build()
+                    .unwrap(),
+            ),
+            SignatureStreamVersion::LATEST,
+            &data,
+        )
+        .unwrap();
+        let mut gz = Vec::new();
+        let (first, second) = signature_stream.split_at(signature_stream.len() / 2);
+        for member in [first, second] {
+            let mut encoder = GzBuilder::new().write(&mut gz, Compression::default());
+            encoder.write_all(member).unwrap();
+            encoder.finish().unwrap();
+        }
+        assert!(is_multi_member_gz(&mut gz.as_slice(), MAX_DECOMPRESSED_SIZE).unwrap());
+
+        let (parsed, logs) = capture_logs(|| {
+            parse_multi_gz_signature_stream_data(
+                &mut gz.as_slice(),
+                Endian::Little,
+                &key_ring,
+                None::<&str>,
+                None::<&str>,
+            )
+            .unwrap()
+        });
+        assert_eq!(parsed.as_ref(), data.as_slice());
+        assert!(logs.is_empty(), "unexpected warnings: {:?}", logs);
+
+        // The first member alone is a truncated signature stream
+        let (_, logs) = capture_logs(|| {
+            parse_gz_signature_stream_data(
+                &mut gz.as_slice(),
+                Endian::Little,
+                &key_ring,
+                None::<&str>,
+                None::<&str>,
+            )
+        });
+        assert!(
+            logs.iter()
+                .any(|log| log
+                    == "the gzip stream has more than one member, only the first was read"),
+            "{:?}",
+            logs
+        );
+
+        // Neither one member nor one with garbage after it count
+        let mut single = Vec::new();
+        let mut encoder = GzBuilder::new().write(&mut single, Compression::default());
+        encoder.write_all(&signature_stream).unwrap();
+        encoder.finish().unwrap();
+        assert!(!is_multi_member_gz(&mut single.as_slice(), MAX_DECOMPRESSED_SIZE).unwrap());
+        single.extend_from_slice(&[0xAA; 16]);
+        assert!(!is_multi_member_gz(&mut single.as_slice(), MAX_DECOMPRESSED_SIZE).unwrap());
+    }
+
+    #[test]
+    fn signature_stream_header() {
+        let key_ring = KeyRing::default();
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&SignOptions {
+                key_ring: &key_ring,
+                sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
+                memory_stream_name: Some("<memory stream:PlayerProfile.dat>"),
+                userid: None::<&str>,
+            }),
+            SignatureStreamVersion::LATEST,
+            &[0x55u8; 100],
+        )
+        .unwrap();
+
+        let mut reader = signature_stream.as_slice();
+        let header = parse_signature_stream_header(&mut reader, Endian::Little).unwrap();
+        assert_eq!(header.version, SignatureStreamVersion::LATEST);
+        assert!(matches!(header.hash_method, HashMethod::Sha1));
+        assert!(header.has_memory_stream_name);
+        assert!(!header.has_userid);
+        assert_eq!(
+            header.sign_key_name.as_deref(),
+            Some(SIGN_KEY_GAME_LOCAL_NAME)
+        );
+        // Only the block and its signature are left
+        assert_eq!(reader.len(), 100 + header.signature_size as usize);
+    }
+
+    #[test]
+    fn truncated_signature_stream() {
+        let key_ring = KeyRing::default();
+        let data = [0x55u8; 100];
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&SignOptions {
+                key_ring: &key_ring,
+                sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
+                memory_stream_name: None::<&str>,
+                userid: None::<&str>,
+            }),
+            SignatureStreamVersion::LATEST,
+            &data,
+        )
+        .unwrap();
+
+        // Cut into the signature of the only block
+        signature_stream.truncate(signature_stream.len() - data.len() - 50);
+        assert!(matches!(
+            parse_signature_stream_data(
+                &mut signature_stream.as_slice(),
+                Endian::Little,
+                &key_ring,
+                None::<&str>,
+                None::<&str>,
+            ),
+            Err(SignatureStreamError::TruncatedBlock(0))
+        ));
+    }
+
+    // A version 1 header, it has no optional fields
+    fn v1_header(version: u32, block_size: u32, hash_method: u32, signature_size: u32) -> Vec<u8> {
+        let mut header = b"SIGSTRM12GIS".to_vec();
+        for value in [version, block_size, hash_method, 0, 0, signature_size] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        header
+    }
+
+    fn gz_with_extra(extra: &[u8]) -> Vec<u8> {
+        let mut gz = Vec::new();
+        GzBuilder::new()
+            .extra(extra)
+            .write(&mut gz, flate2::Compression::default())
+            .finish()
+            .unwrap();
+        gz
+    }
+
+    #[test]
+    fn signature_stream_errors() {
+        let key_ring = KeyRing::default();
+        let parse = |data: &[u8]| {
+            parse_signature_stream_data(
+                &mut &data[..],
+                Endian::Little,
+                &key_ring,
+                None::<&str>,
+                None::<&str>,
+            )
+            .unwrap_err()
+        };
+
+        assert!(matches!(
+            parse(b"SIGSTRM21GIS"),
+            SignatureStreamError::BadMagic
+        ));
+        assert!(matches!(
+            parse(&v1_header(1, 0x10000, 4, 0)[..20]),
+            SignatureStreamError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+        assert!(matches!(
+            parse(&v1_header(9, 0x10000, 4, 0)),
+            SignatureStreamError::InvalidVersion(e) if e.0 == 9
+        ));
+        assert!(matches!(
+            parse(&v1_header(1, 0x10000, 7, 0)),
+            SignatureStreamError::UnknownHashMethod(7)
+        ));
+        let mut empty_blocks = v1_header(1, 0, 4, 0);
+        empty_blocks.push(0);
+        assert!(matches!(
+            parse(&empty_blocks),
+            SignatureStreamError::EmptyBlocks
+        ));
+
+        let gz_error = |gz: &[u8]| read_gz_extra_field(&mut Cursor::new(gz)).unwrap_err();
+        assert!(matches!(
+            gz_error(&v1_header(1, 0x10000, 4, 0)),
+            SignatureStreamError::NotGzip
+        ));
+        assert!(matches!(
+            gz_error(&gz_with_extra(b"XY\x02\x00ab")),
+            SignatureStreamError::NoCTExtraField
+        ));
+        assert!(matches!(
+            gz_error(&gz_with_extra(b"CT\x04\x00abcd")),
+            SignatureStreamError::Parse(_)
+        ));
+        let mut gz = Vec::new();
+        GzBuilder::new()
+            .write(&mut gz, flate2::Compression::default())
+            .finish()
+            .unwrap();
+        assert!(matches!(
+            gz_error(&gz),
+            SignatureStreamError::NoGzExtraField
+        ));
+
+        let write_error = |gz_options: &GzOptions| {
+            write_gz_signature_stream(&mut Cursor::new(Vec::new()), gz_options, &[]).unwrap_err()
+        };
+        assert!(matches!(
+            write_error(&GzOptions {
+                filename: Some("Player\0Profile.dat"),
+                comment: None,
+            }),
+            SignatureStreamError::NullInGzField("filename")
+        ));
+        assert!(matches!(
+            write_error(&GzOptions {
+                filename: None,
+                comment: Some("\0"),
+            }),
+            SignatureStreamError::NullInGzField("comment")
+        ));
+
+        let signing = SignatureStreamError::from(rsa::Error::Internal);
+        assert!(matches!(signing, SignatureStreamError::Signing(_)));
+        assert!(std::error::Error::source(&signing).is_some());
+
+        // main.rs relies on this going through ? into anyhow
+        let error = anyhow::Error::from(SignatureStreamError::UnknownHashMethod(7));
+        assert_eq!(error.to_string(), "unknown hash method 7");
+    }
+
+    #[test]
+    fn recompress_preserves_signature_stream() {
+        let key_ring = KeyRing::default();
+        let data = b"CTSEMETA not really but close enough".repeat(3000);
+        let sign_options = SignOptions {
+            key_ring: &key_ring,
+            sign_key_name: SIGN_KEY_GAME_LOCAL_NAME,
+            memory_stream_name: Some("<memory stream:PlayerProfile.dat>"),
+            userid: Some("1100001075d8dea"),
+        };
+
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(&sign_options),
+            SignatureStreamVersion::LATEST,
+            &data,
+        )
+        .unwrap();
+
+        let mut writer = Cursor::new(Vec::new());
+        write_gz_signature_stream(&mut writer, &GzOptions::default(), &signature_stream).unwrap();
+        let gz = writer.into_inner();
+
+        let signature_stream_again = read_gz_signature_stream(&mut gz.as_slice()).unwrap();
+        assert_eq!(signature_stream, signature_stream_again);
+
+        let extra = GzDecoder::new(gz.as_slice())
+            .header()
+            .unwrap()
+            .extra()
+            .unwrap()
+            .to_vec();
+        let compressed_size = u32::from_le_bytes(extra[4..8].try_into().unwrap()) as usize;
+        let decompressed_size = u32::from_le_bytes(extra[8..12].try_into().unwrap()) as usize;
+        assert_eq!(compressed_size, gz.len() - 0x18 - 8);
+        assert_eq!(decompressed_size, signature_stream.len());
+
+        let from_gz = parse_gz_signature_stream_data(
+            &mut gz.as_slice(),
+            Endian::Little,
+            &key_ring,
+            sign_options.memory_stream_name,
+            sign_options.userid,
+        )
+        .unwrap();
+        let from_raw = parse_signature_stream_data(
+            &mut signature_stream.as_slice(),
+            Endian::Little,
+            &key_ring,
+            sign_options.memory_stream_name,
+            sign_options.userid,
+        )
+        .unwrap();
+        assert_eq!(from_gz.as_ref(), data.as_slice());
+        assert_eq!(from_raw.as_ref(), data.as_slice());
+    }
+}
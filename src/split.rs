@@ -0,0 +1,210 @@
+use std::collections::BTreeSet;
+
+use crate::ctsemeta::{
+    CTSEMeta,
+    DataTypeType,
+    IdentRef,
+    InternalObject,
+    InternalObjectDataValue,
+    reachable_types,
+};
+
+impl CTSEMeta {
+    // Splits the internal objects into those whose type is named type_name and
+    // the rest. Each half keeps only the object types, internal types, and
+    // idents its objects need, Info is recalculated when writing. Pointers
+    // between the halves are kept as they are so merging the halves gives the
+    // original objects back. Edit data and external objects aren't split.
+    pub fn split_by_type(&self, type_name: &str) -> (Self, Self) {
+        let matches = |object: &InternalObject| {
+            self.internal_types
+                .types
+                .iter()
+                .any(|data_type| data_type.DataType == object.Type && data_type.Name == type_name)
+        };
+        let mut matching = self.clone();
+        matching.retain_objects(matches);
+        let mut rest = self.clone();
+        rest.retain_objects(|object| !matches(object));
+        (matching, rest)
+    }
+
+    fn retain_objects(&mut self, mut keep: impl FnMut(&InternalObject) -> bool) {
+        fn collect_idents<'a>(
+            value: &'a InternalObjectDataValue,
+            ids: &mut BTreeSet<u32>,
+            names: &mut Vec<&'a str>,
+        ) {
+            match value {
+                InternalObjectDataValue::IDENT(IdentRef::Id(id)) => {
+                    ids.insert(*id);
+                }
+                InternalObjectDataValue::IDENT(IdentRef::Name(name)) => names.push(name),
+                InternalObjectDataValue::Array(values)
+                | InternalObjectDataValue::StaticStackArray(values)
+                | InternalObjectDataValue::DynamicContainerValues(values) => values
+                    .iter()
+                    .for_each(|value| collect_idents(value, ids, names)),
+                InternalObjectDataValue::Struct { Base, members } => {
+                    if let Some(base) = Base {
+                        collect_idents(base, ids, names);
+                    }
+                    members
+                        .iter()
+                        .for_each(|(_, value)| collect_idents(value, ids, names));
+                }
+                _ => {}
+            }
+        }
+
+        self.internal_objects
+            .internal_object
+            .retain(|object| keep(object));
+        let objects = self
+            .internal_objects
+            .internal_object
+            .iter()
+            .map(|object| object.Object)
+            .collect::<BTreeSet<_>>();
+        self.internal_object_types
+            .types
+            .retain(|object_type| objects.contains(&object_type.Object));
+
+        let types = self.internal_types.by_id();
+        let needed_types = reachable_types(
+            &types,
+            self.internal_objects
+                .internal_object
+                .iter()
+                .map(|object| object.Type)
+                .chain(
+                    self.edit_object_types
+                        .edit_object_types
+                        .iter()
+                        .map(|object_type| object_type.Type),
+                ),
+        );
+
+        // Struct members, IDENT values, and the names of the objects
+        let mut needed_idents = objects.clone();
+        needed_idents.extend(
+            self.external_objects
+                .external_objects
+                .iter()
+                .map(|object| object.Object),
+        );
+        for id in &needed_types {
+            if let DataTypeType::Struct { members, .. } = &types[id].Type {
+                needed_idents.extend(members.iter().map(|member| member.ID));
+            }
+        }
+        let mut names = Vec::new();
+        for object in &self.internal_objects.internal_object {
+            collect_idents(&object.value, &mut needed_idents, &mut names);
+        }
+        let names = names
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+
+        self.internal_types
+            .types
+            .retain(|data_type| needed_types.contains(&data_type.DataType));
+        self.idents
+            .idents
+            .retain(|ident| needed_idents.contains(&ident.Ident) || names.contains(&ident.Name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::{BinRead, BinWrite, Endian};
+
+    use crate::ctsemeta::{
+        CTSEMeta,
+        DataType,
+        DataTypeType,
+        InternalObject,
+        InternalObjectDataValue,
+        InternalObjectType,
+        synthetic,
+    };
+    use crate::helpers::ValidationOptions;
+    use crate::validate::validate;
+
+    fn read_back(ctsemeta: &CTSEMeta) -> CTSEMeta {
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions {
+                strict: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn split_by_type() {
+        let mut ctsemeta = synthetic();
+        ctsemeta.internal_types.types.push(DataType {
+            DataType: 5,
+            Name: "UBYTE".to_owned(),
+            Format: 0,
+            Type: DataTypeType::Primitive { Bytes: 1, LBE: 0 },
+        });
+        ctsemeta
+            .internal_object_types
+            .types
+            .push(InternalObjectType {
+                Object: 10,
+                Type: 5,
+            });
+        ctsemeta
+            .internal_objects
+            .internal_object
+            .push(InternalObject {
+                Object: 10,
+                Type: 5,
+                value: InternalObjectDataValue::UBYTE(3),
+            });
+
+        let (matching, rest) = ctsemeta.split_by_type("UBYTE");
+        assert_eq!(matching.internal_objects.internal_object.len(), 1);
+        assert_eq!(matching.internal_object_types.types.len(), 1);
+        assert_eq!(matching.internal_types.types.len(), 1);
+        assert!(matching.idents.idents.is_empty());
+        assert_eq!(rest.internal_objects.internal_object.len(), 2);
+        assert_eq!(rest.internal_object_types.types.len(), 2);
+        assert_eq!(rest.internal_types.types.len(), 5);
+        assert_eq!(rest.idents.idents.len(), ctsemeta.idents.idents.len());
+        assert!(validate(&matching).is_empty());
+        assert!(validate(&rest).is_empty());
+
+        // Both are consistent enough to read back strictly
+        assert!(matches!(
+            read_back(&matching).internal_objects.internal_object[0].value,
+            InternalObjectDataValue::UBYTE(3)
+        ));
+        assert_eq!(read_back(&rest).internal_objects.internal_object.len(), 2);
+
+        // Merging the halves gives every object back
+        let (mut merged, rest) = ctsemeta.split_by_type("CTalosProgress");
+        assert_eq!(merged.internal_objects.internal_object.len(), 2);
+        merged.merge(&rest).unwrap();
+        assert_eq!(
+            merged.to_bytes(Endian::Little).unwrap().len(),
+            ctsemeta.to_bytes(Endian::Little).unwrap().len()
+        );
+
+        let (matching, rest) = ctsemeta.split_by_type("CTalosThing");
+        assert!(matching.internal_objects.internal_object.is_empty());
+        assert_eq!(rest.internal_objects.internal_object.len(), 3);
+    }
+}
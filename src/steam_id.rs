@@ -0,0 +1,207 @@
+use std::fmt;
+
+// An individual account's SteamID64 is this plus its account ID
+pub const STEAM_ID64_BASE: u64 = 0x0110000100000000;
+
+// How to read a userid. The game signs with the SteamID64 in lowercase hex,
+// the others are the forms Steam shows in profiles and URLs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UseridFormat {
+    // Whichever of the others it looks like, anything else is used as is
+    #[default]
+    Auto,
+    // 1100001075d8dea
+    Hex,
+    // 76561198083837418
+    SteamId64,
+    // [U:1:123456789]
+    SteamId3,
+    // 123456789
+    AccountId,
+}
+
+impl fmt::Display for UseridFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Hex => write!(f, "hex"),
+            Self::SteamId64 => write!(f, "steamid64"),
+            Self::SteamId3 => write!(f, "steamid3"),
+            Self::AccountId => write!(f, "account ID"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidUserid {
+    pub userid: String,
+    pub format: UseridFormat,
+}
+
+impl fmt::Display for InvalidUserid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} isn't a {} userid", self.userid, self.format)
+    }
+}
+
+impl std::error::Error for InvalidUserid {}
+
+// The userid the game signs with for an account
+pub fn steam_userid(account: u32) -> String {
+    format!("{:x}", STEAM_ID64_BASE + u64::from(account))
+}
+
+fn account_from_steam_id64(steam_id64: u64) -> Option<u32> {
+    steam_id64
+        .checked_sub(STEAM_ID64_BASE)
+        .and_then(|account| u32::try_from(account).ok())
+}
+
+fn parse_hex(userid: &str) -> Option<u32> {
+    let digits = userid
+        .strip_prefix("0x")
+        .or_else(|| userid.strip_prefix("0X"))
+        .unwrap_or(userid);
+    account_from_steam_id64(u64::from_str_radix(digits, 16).ok()?)
+}
+
+fn parse_steam_id64(userid: &str) -> Option<u32> {
+    account_from_steam_id64(userid.parse().ok()?)
+}
+
+fn parse_steam_id3(userid: &str) -> Option<u32> {
+    let userid = userid
+        .strip_prefix('[')
+        .and_then(|userid| userid.strip_suffix(']'))
+        .unwrap_or(userid);
+    userid.strip_prefix("U:1:")?.parse().ok()
+}
+
+// The userid in the form the signature hashes. Auto tells the formats apart
+// by shape: SteamID3 has brackets, a decimal SteamID64 is 17 digits, hex ones
+// are 15 characters, and an account ID fits in 32 bits. Userids that aren't
+// any of these, like ones from other stores, are left alone.
+pub fn normalize_userid(userid: &str, format: UseridFormat) -> Result<String, InvalidUserid> {
+    let trimmed = userid.trim();
+    let account = match format {
+        UseridFormat::Auto => {
+            let digits = !trimmed.is_empty() && trimmed.bytes().all(|byte| byte.is_ascii_digit());
+            if let Some(account) = parse_steam_id3(trimmed) {
+                Some(account)
+            } else if digits && trimmed.len() == 17 {
+                parse_steam_id64(trimmed)
+            } else if digits && trimmed.len() <= 10 {
+                trimmed.parse().ok()
+            } else {
+                parse_hex(trimmed)
+            }
+        }
+        UseridFormat::Hex => parse_hex(trimmed),
+        UseridFormat::SteamId64 => parse_steam_id64(trimmed),
+        UseridFormat::SteamId3 => parse_steam_id3(trimmed),
+        UseridFormat::AccountId => trimmed.parse().ok(),
+    };
+    match (account, format) {
+        (Some(account), _) => Ok(steam_userid(account)),
+        (None, UseridFormat::Auto) => Ok(userid.to_owned()),
+        (None, format) => Err(InvalidUserid {
+            userid: userid.to_owned(),
+            format,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use binrw::Endian;
+
+    use crate::signature_stream::{
+        KeyRing,
+        SignOptionsBuilder,
+        SignatureStreamReader,
+        SignatureStreamVersion,
+        VerifyOptions,
+        write_signature_stream_data,
+    };
+    use crate::steam_id::{InvalidUserid, UseridFormat, normalize_userid};
+
+    const USERID: &str = "1100001075d8dea";
+
+    #[test]
+    fn userid_formats() {
+        let key_ring = KeyRing::default();
+        let memory_stream_name = "<memory stream:PlayerProfile.dat>";
+        let mut signature_stream = Vec::new();
+        write_signature_stream_data(
+            &mut signature_stream,
+            Endian::Little,
+            Some(
+                &SignOptionsBuilder::new()
+                    .key_ring(&key_ring)
+                    .memory_stream_name(Some(memory_stream_name))
+                    .userid(Some(USERID))
+                    .build()
+                    .unwrap(),
+            ),
+            SignatureStreamVersion::LATEST,
+            b"CTSEMETA not really but close enough",
+        )
+        .unwrap();
+        let header_valid = |userid: &str| {
+            let mut reader = SignatureStreamReader::new(
+                signature_stream.as_slice(),
+                Endian::Little,
+                &key_ring,
+                VerifyOptions {
+                    memory_stream_name: Some(memory_stream_name),
+                    userid: Some(userid),
+                },
+            );
+            std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+            reader.report().header_valid
+        };
+        // The signature really depends on the exact string
+        assert_eq!(header_valid(USERID), Some(true));
+        assert_eq!(header_valid("1100001075D8DEA"), Some(false));
+
+        // 0x75d8dea is 123571690
+        for (userid, format) in [
+            ("1100001075d8dea", UseridFormat::Hex),
+            ("1100001075D8DEA", UseridFormat::Hex),
+            ("0x1100001075d8dea", UseridFormat::Hex),
+            ("76561198083837418", UseridFormat::SteamId64),
+            ("[U:1:123571690]", UseridFormat::SteamId3),
+            ("U:1:123571690", UseridFormat::SteamId3),
+            ("123571690", UseridFormat::AccountId),
+        ] {
+            for format in [UseridFormat::Auto, format] {
+                let normalized = normalize_userid(userid, format).unwrap();
+                assert_eq!(normalized, USERID, "{} as {}", userid, format);
+                assert_eq!(header_valid(&normalized), Some(true));
+            }
+        }
+
+        // All digits, but too long for an account ID so it's read as hex
+        assert_eq!(
+            normalize_userid("110000100000002", UseridFormat::Hex).unwrap(),
+            "110000100000002"
+        );
+        assert_eq!(
+            normalize_userid("110000100000002", UseridFormat::Auto).unwrap(),
+            "110000100000002"
+        );
+        assert_eq!(
+            normalize_userid("110000100000002", UseridFormat::AccountId),
+            Err(InvalidUserid {
+                userid: "110000100000002".to_owned(),
+                format: UseridFormat::AccountId,
+            })
+        );
+        // Not a Steam ID at all
+        assert_eq!(
+            normalize_userid("player@example", UseridFormat::Auto).unwrap(),
+            "player@example"
+        );
+        assert!(normalize_userid("player@example", UseridFormat::SteamId3).is_err());
+    }
+}
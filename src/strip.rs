@@ -0,0 +1,165 @@
+use std::fmt;
+
+use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StripConflict {
+    Missing { id: u32 },
+    // A reference that can't be nulled, the path is a JSON pointer into the
+    // extracted JSON
+    Required { object: u32, path: String },
+}
+
+impl fmt::Display for StripConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { id } => write!(f, "object {} doesn't exist", id),
+            Self::Required { object, path } => {
+                write!(f, "object {} needs it at {}", object, path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StripConflict {}
+
+impl CTSEMeta {
+    // Removes an internal object and its object type, pointers to it become -1.
+    // Nothing is changed if a dynamic container still holds it since those
+    // entries can't be null. Info is recalculated when writing.
+    pub fn strip_object(&mut self, id: u32) -> Result<(), Vec<StripConflict>> {
+        fn null_pointers(value: &mut InternalObjectDataValue, id: u32) {
+            match value {
+                InternalObjectDataValue::Pointer(pointer) if *pointer as u32 == id => *pointer = -1,
+                InternalObjectDataValue::Array(values)
+                | InternalObjectDataValue::StaticStackArray(values)
+                | InternalObjectDataValue::DynamicContainerValues(values) => {
+                    values.iter_mut().for_each(|value| null_pointers(value, id))
+                }
+                InternalObjectDataValue::Struct { Base, members } => {
+                    if let Some(base) = Base {
+                        null_pointers(base, id);
+                    }
+                    members
+                        .iter_mut()
+                        .for_each(|(_, value)| null_pointers(value, id));
+                }
+                _ => {}
+            }
+        }
+
+        if !self
+            .internal_objects
+            .internal_object
+            .iter()
+            .any(|object| object.Object == id)
+        {
+            return Err(vec![StripConflict::Missing { id }]);
+        }
+
+        let conflicts = self
+            .references()
+            .into_iter()
+            .filter(|reference| reference.target == id && !reference.nullable)
+            .map(|reference| StripConflict::Required {
+                object: reference.object,
+                path: reference.path,
+            })
+            .collect::<Vec<_>>();
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+
+        self.internal_objects
+            .internal_object
+            .retain(|object| object.Object != id);
+        self.internal_object_types
+            .types
+            .retain(|object_type| object_type.Object != id);
+        for object in self.internal_objects.internal_object.iter_mut() {
+            null_pointers(&mut object.value, id);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::{BinRead, BinWrite, Endian};
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue, ReferenceIssue, synthetic};
+    use crate::helpers::ValidationOptions;
+    use crate::strip::StripConflict;
+
+    #[test]
+    fn strip_object() {
+        let mut ctsemeta = synthetic();
+        ctsemeta.strip_object(1).unwrap();
+        assert_eq!(ctsemeta.internal_objects.internal_object.len(), 1);
+        assert_eq!(ctsemeta.internal_object_types.types.len(), 1);
+        let InternalObjectDataValue::Struct { members, .. } =
+            &ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        assert!(matches!(
+            members.get("m_pNext"),
+            Some(InternalObjectDataValue::Pointer(-1))
+        ));
+        assert!(
+            ctsemeta
+                .check_references()
+                .iter()
+                .all(|issue| !matches!(issue, ReferenceIssue::Dangling { .. }))
+        );
+
+        // Info is recalculated so the stripped save reads back
+        let mut writer = Cursor::new(Vec::new());
+        ctsemeta
+            .write_options(&mut writer, Endian::Little, ())
+            .unwrap();
+        let stripped = CTSEMeta::read_options(
+            &mut Cursor::new(writer.into_inner()),
+            Endian::Little,
+            ValidationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stripped.internal_objects.internal_object.len(), 1);
+
+        assert_eq!(
+            synthetic().strip_object(7).unwrap_err(),
+            [StripConflict::Missing { id: 7 }]
+        );
+    }
+
+    #[test]
+    fn strip_required_object() {
+        let mut ctsemeta = synthetic();
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_pNext").unwrap() = InternalObjectDataValue::DynamicContainer(vec![1]);
+
+        let conflicts = ctsemeta.strip_object(1).unwrap_err();
+        assert_eq!(
+            conflicts,
+            [StripConflict::Required {
+                object: 0,
+                path: "/internal_objects/internal_object/0/value/Struct/members/m_pNext/\
+                       DynamicContainer/0"
+                    .to_owned(),
+            }]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "object 0 needs it at \
+             /internal_objects/internal_object/0/value/Struct/members/m_pNext/DynamicContainer/0"
+        );
+        assert_eq!(ctsemeta.internal_objects.internal_object.len(), 2);
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::ctsemeta::{
+    CTSEMeta,
+    DataType,
+    DataTypeType,
+    EditObjectTypes,
+    EditObjects,
+    ExternalObjects,
+    ExternalTypes,
+    IdentRef,
+    Idents,
+    InternalObject,
+    InternalObjectDataValue,
+    InternalObjectType,
+    InternalObjectTypes,
+    InternalObjects,
+    InternalTypes,
+    Messages,
+    Metadata,
+    ResourceFiles,
+    StructMembers,
+    container_holds_values,
+    csynced_primitive,
+    is_csynced,
+    is_matrix4x4,
+    is_placement3d,
+    is_vec3d,
+    member_name,
+};
+use crate::helpers::ParsedVec;
+use crate::types::find_type;
+
+// Same limit as reading, only types that contain themselves get this deep
+const MAX_TYPE_DEPTH: usize = 64;
+
+// Everything a save declares about its types, without any objects. Written by
+// export-schema so saves can be made from scratch with create-template.
+#[derive(Serialize, Deserialize)]
+pub struct Schema {
+    pub metadata: Metadata,
+    pub idents: Idents,
+    pub external_types: ExternalTypes,
+    pub internal_types: InternalTypes,
+}
+
+impl Schema {
+    pub fn from_ctsemeta(ctsemeta: CTSEMeta) -> Self {
+        Self {
+            metadata: ctsemeta.metadata,
+            idents: ctsemeta.idents,
+            external_types: ctsemeta.external_types,
+            internal_types: ctsemeta.internal_types,
+        }
+    }
+
+    // A save with the schema's types and no objects
+    pub fn into_ctsemeta(self) -> CTSEMeta {
+        CTSEMeta {
+            metadata: self.metadata,
+            messages: Messages {
+                messages: ParsedVec::new(),
+            },
+            edit_data_stripped: true,
+            resource_files: ResourceFiles {
+                resource_files: ParsedVec::new(),
+            },
+            idents: self.idents,
+            external_types: self.external_types,
+            internal_types: self.internal_types,
+            external_objects: ExternalObjects {
+                external_objects: ParsedVec::new(),
+            },
+            internal_object_types: InternalObjectTypes {
+                types: ParsedVec::new(),
+            },
+            edit_object_types: EditObjectTypes {
+                edit_object_types: ParsedVec::new(),
+            },
+            internal_objects: InternalObjects {
+                internal_object: ParsedVec::new(),
+            },
+            edit_objects: EditObjects {
+                edit_objects: ParsedVec::new(),
+            },
+            trailing: Vec::new(),
+        }
+    }
+}
+
+// A save with a single object 0 of the type, every value in it is zero, empty,
+// or a null pointer
+pub fn create_template(schema: Schema, type_name_or_id: &str) -> Result<CTSEMeta> {
+    let mut ctsemeta = schema.into_ctsemeta();
+    let type_id = find_type(&ctsemeta, type_name_or_id)
+        .ok_or_else(|| anyhow!("no type named {:?}", type_name_or_id))?;
+    let value = default_value(
+        &ctsemeta.internal_types.by_id(),
+        &ctsemeta.idents.by_id(),
+        type_id,
+        0,
+    )?;
+    ctsemeta
+        .internal_object_types
+        .types
+        .push(InternalObjectType {
+            Object: 0,
+            Type: type_id,
+        });
+    ctsemeta
+        .internal_objects
+        .internal_object
+        .push(InternalObject {
+            Object: 0,
+            Type: type_id,
+            value,
+        });
+    Ok(ctsemeta)
+}
+
+// The value reading a zeroed object of the type would give, this has to agree
+// with InternalObject's BinRead
+pub fn default_value(
+    types: &HashMap<u32, &DataType>,
+    idents: &HashMap<u32, &str>,
+    type_id: u32,
+    depth: usize,
+) -> Result<InternalObjectDataValue> {
+    if depth > MAX_TYPE_DEPTH {
+        return Err(anyhow!("type {} is nested too deeply", type_id));
+    }
+    let data_type = types
+        .get(&type_id)
+        .ok_or_else(|| anyhow!("type {} isn't an internal type", type_id))?;
+    let default = |type_id| default_value(types, idents, type_id, depth + 1);
+    Ok(match &data_type.Type {
+        _ if is_vec3d(types, data_type) => InternalObjectDataValue::Vec3D {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        // The identity rotation, an all zero quaternion isn't a rotation
+        _ if is_placement3d(types, data_type) => InternalObjectDataValue::Placement3D {
+            qx: 0.0,
+            qy: 0.0,
+            qz: 0.0,
+            qw: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        // The identity too, for the same reason
+        _ if is_matrix4x4(types, data_type) => {
+            InternalObjectDataValue::Matrix4x4(std::array::from_fn(|i| {
+                if i % 5 == 0 { 1.0 } else { 0.0 }
+            }))
+        }
+        DataTypeType::Primitive { Bytes, .. } => match data_type.Name.as_str() {
+            "CString" => InternalObjectDataValue::CString(String::new()),
+            "IDENT" => InternalObjectDataValue::IDENT(IdentRef::Id(0)),
+            "BOOL" | "SBOOL" if matches!(Bytes, 1 | 2 | 4) => InternalObjectDataValue::Bool(false),
+            "UBYTE" => InternalObjectDataValue::UBYTE(0),
+            "UWORD" if *Bytes == 2 => InternalObjectDataValue::UWORD(0),
+            "SWORD" if *Bytes == 2 => InternalObjectDataValue::SWORD(0),
+            "UINDEX" if *Bytes == 4 => InternalObjectDataValue::UINDEX(0),
+            "ULONG" => InternalObjectDataValue::ULONG(0),
+            "SLONG" => InternalObjectDataValue::SLONG(0),
+            "UQUAD" => InternalObjectDataValue::UQUAD(0),
+            "SQUAD" => InternalObjectDataValue::SQUAD(0),
+            "FLOAT" => InternalObjectDataValue::FLOAT(0.0),
+            "SFLOAT" if *Bytes == 2 => InternalObjectDataValue::SFLOAT(0.0),
+            "DOUBLE" if *Bytes == 8 => InternalObjectDataValue::DOUBLE(0.0),
+            "TIME" if matches!(Bytes, 4 | 8) => InternalObjectDataValue::TIME(0.0),
+            "TICK" if matches!(Bytes, 4 | 8) => InternalObjectDataValue::TICK(0.0),
+            _ => InternalObjectDataValue::Primitive(vec![0; *Bytes as usize]),
+        },
+        DataTypeType::Enum { Bytes } => match Bytes {
+            1 => InternalObjectDataValue::UBYTEEnum(0),
+            2 => InternalObjectDataValue::UWORDEnum(0),
+            4 => InternalObjectDataValue::SLONGEnum(0),
+            8 => InternalObjectDataValue::SQUADEnum(0),
+            _ => InternalObjectDataValue::Enum(vec![0; *Bytes as usize]),
+        },
+        DataTypeType::Pointer { .. } => InternalObjectDataValue::Pointer(-1),
+        // Fixed size, so these can't be empty
+        DataTypeType::Array { Of, rows, cols } => {
+            let row = || (0..*cols).map(|_| default(*Of)).collect::<Result<Vec<_>>>();
+            if *rows == 1 {
+                InternalObjectDataValue::Array(row()?)
+            } else {
+                InternalObjectDataValue::Array(
+                    (0..*rows)
+                        .map(|_| row().map(InternalObjectDataValue::Array))
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+        }
+        DataTypeType::Struct { .. } if is_csynced(data_type) => {
+            let primitive = csynced_primitive(types, data_type).ok_or_else(|| {
+                anyhow!(
+                    "{} has no members and its primitive isn't in the types",
+                    data_type.Name
+                )
+            })?;
+            default(primitive.DataType)?
+        }
+        DataTypeType::Struct { Base, members } => InternalObjectDataValue::Struct {
+            Base: if *Base != -1 {
+                Some(Box::new(default(*Base as u32)?))
+            } else {
+                None
+            },
+            members: StructMembers(
+                members
+                    .iter()
+                    .map(|member| Ok((member_name(idents, member.ID), default(member.Type)?)))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        },
+        DataTypeType::StaticStackArray { .. } => {
+            InternalObjectDataValue::StaticStackArray(Vec::new())
+        }
+        DataTypeType::DynamicContainer { Of } if container_holds_values(types, *Of) => {
+            InternalObjectDataValue::DynamicContainerValues(Vec::new())
+        }
+        DataTypeType::DynamicContainer { .. } => {
+            InternalObjectDataValue::DynamicContainer(Vec::new())
+        }
+        DataTypeType::TypeDef { For } => default(*For)?,
+        DataTypeType::Unknown { tag, .. } => {
+            return Err(anyhow!("type {} has unknown kind {}", data_type.Name, tag));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::{BinRead, Endian};
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue, synthetic};
+    use crate::helpers::ValidationOptions;
+    use crate::template::{Schema, create_template};
+    use crate::validate::validate;
+
+    fn synthetic_schema() -> Schema {
+        let ctsemeta = synthetic();
+        // Through JSON like export-schema and create-template
+        serde_json::from_value(serde_json::to_value(Schema::from_ctsemeta(ctsemeta)).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn create_template_from_schema() {
+        let template = create_template(synthetic_schema(), "CTalosProgress").unwrap();
+        assert!(validate(&template).is_empty());
+        assert_eq!(template.internal_objects.internal_object.len(), 1);
+        let object = &template.internal_objects.internal_object[0];
+        assert_eq!((object.Object, object.Type), (0, 4));
+        let InternalObjectDataValue::Struct {
+            Base: None,
+            members,
+        } = &object.value
+        else {
+            unreachable!()
+        };
+        assert!(matches!(
+            members.get("m_strName"),
+            Some(InternalObjectDataValue::CString(name)) if name.is_empty()
+        ));
+        assert!(matches!(
+            members.get("m_ulCount"),
+            Some(InternalObjectDataValue::ULONG(0))
+        ));
+        assert!(matches!(
+            members.get("m_pNext"),
+            Some(InternalObjectDataValue::Pointer(-1))
+        ));
+
+        // Writes a save that reads back the same
+        let bytes = template.to_bytes(Endian::Little).unwrap();
+        let read = CTSEMeta::read_options(
+            &mut Cursor::new(&bytes),
+            Endian::Little,
+            ValidationOptions {
+                strict: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(read.to_bytes(Endian::Little).unwrap(), bytes);
+
+        // By ID too
+        assert!(create_template(synthetic_schema(), "4").is_ok());
+        assert_eq!(
+            create_template(synthetic_schema(), "CTalosThing")
+                .err()
+                .unwrap()
+                .to_string(),
+            "no type named \"CTalosThing\""
+        );
+        // Pointers aren't objects on their own but still have a default
+        let template = create_template(synthetic_schema(), "CTalosProgress*").unwrap();
+        assert!(matches!(
+            template.internal_objects.internal_object[0].value,
+            InternalObjectDataValue::Pointer(-1)
+        ));
+    }
+}
@@ -0,0 +1,176 @@
+// Reads objects into application types through serde instead of matching on
+// InternalObjectDataValue. Values are converted to JSON without the variant
+// names first, so a struct is an object of its members, an array is an array,
+// and every other value is what's inside its variant, e.g. a Pointer is its
+// object ID and a Vec3D is {"x": 1.0, "y": 2.0, "z": 3.0}.
+use anyhow::{Context, Result, anyhow};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::ctsemeta::{InternalObject, InternalObjectDataValue, InternalTypes};
+
+// The members of the bases come first, a base that isn't a struct is kept
+// under "Base"
+pub fn plain_value(value: &InternalObjectDataValue) -> Result<Value> {
+    match value {
+        InternalObjectDataValue::Struct { Base, members } => {
+            let mut map = Map::new();
+            if let Some(base) = Base {
+                match plain_value(base)? {
+                    Value::Object(base) => map.extend(base),
+                    base => {
+                        map.insert("Base".to_owned(), base);
+                    }
+                }
+            }
+            for (name, value) in members.iter() {
+                map.insert(name.clone(), plain_value(value)?);
+            }
+            Ok(Value::Object(map))
+        }
+        InternalObjectDataValue::Array(values)
+        | InternalObjectDataValue::StaticStackArray(values)
+        | InternalObjectDataValue::DynamicContainerValues(values) => values
+            .iter()
+            .map(plain_value)
+            .collect::<Result<_>>()
+            .map(Value::Array),
+        value => match serde_json::to_value(value)? {
+            Value::Object(map) if map.len() == 1 => Ok(map.into_iter().next().unwrap().1),
+            value => Ok(value),
+        },
+    }
+}
+
+impl InternalObject {
+    fn describe(&self, schema: &InternalTypes) -> String {
+        match schema.by_id().get(&self.Type) {
+            Some(data_type) => format!("object {} ({})", self.Object, data_type.Name),
+            None => format!("object {} (type {})", self.Object, self.Type),
+        }
+    }
+
+    // e.g. let progress: TalosProgress = object.into_typed(&types)?
+    pub fn into_typed<T: DeserializeOwned>(self, schema: &InternalTypes) -> Result<T> {
+        let value = plain_value(&self.value)?;
+        serde_json::from_value(value).with_context(|| self.describe(schema))
+    }
+
+    // A single struct member by its ident name, searching the bases too
+    pub fn into_typed_field<T: DeserializeOwned>(
+        self,
+        name: &str,
+        schema: &InternalTypes,
+    ) -> Result<T> {
+        let Value::Object(mut members) = plain_value(&self.value)? else {
+            return Err(anyhow!("{} isn't a struct", self.describe(schema)));
+        };
+        let value = members
+            .remove(name)
+            .ok_or_else(|| anyhow!("{} has no member {:?}", self.describe(schema), name))?;
+        serde_json::from_value(value)
+            .with_context(|| format!("member {:?} of {}", name, self.describe(schema)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use serde::Deserialize;
+
+    use crate::ctsemeta::{CTSEMeta, InternalObjectDataValue, StructMembers};
+
+    #[allow(non_snake_case)]
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TalosProgress {
+        m_strName: String,
+        m_ulCount: u32,
+        m_fTime: f32,
+        m_pNext: i32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Vector {
+        x: f32,
+        y: f32,
+        z: f32,
+    }
+
+    #[test]
+    fn into_typed() {
+        let ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        let schema = &ctsemeta.internal_types;
+        let object = ctsemeta.internal_objects.internal_object[0].clone();
+
+        assert_eq!(
+            object.clone().into_typed::<TalosProgress>(schema).unwrap(),
+            TalosProgress {
+                m_strName: "Cloud_1_01".to_owned(),
+                m_ulCount: 42,
+                m_fTime: 1.5,
+                m_pNext: 1,
+            }
+        );
+        let count: u32 = object
+            .clone()
+            .into_typed_field("m_ulCount", schema)
+            .unwrap();
+        assert_eq!(count, 42);
+
+        assert_eq!(
+            object
+                .clone()
+                .into_typed_field::<u32>("m_ulScore", schema)
+                .unwrap_err()
+                .to_string(),
+            "object 0 (CTalosProgress) has no member \"m_ulScore\""
+        );
+        assert_eq!(
+            object
+                .into_typed_field::<u32>("m_strName", schema)
+                .unwrap_err()
+                .to_string(),
+            "member \"m_strName\" of object 0 (CTalosProgress)"
+        );
+
+        // Members of the base are found too
+        let mut derived = ctsemeta.internal_objects.internal_object[1].clone();
+        let base = std::mem::replace(
+            &mut derived.value,
+            InternalObjectDataValue::Struct {
+                Base: None,
+                members: StructMembers(vec![(
+                    "m_vPosition".to_owned(),
+                    InternalObjectDataValue::Vec3D {
+                        x: 1.0,
+                        y: 2.0,
+                        z: 3.0,
+                    },
+                )]),
+            },
+        );
+        let InternalObjectDataValue::Struct { Base, .. } = &mut derived.value else {
+            unreachable!()
+        };
+        *Base = Some(Box::new(base));
+        assert_eq!(
+            derived
+                .clone()
+                .into_typed_field::<Vector>("m_vPosition", schema)
+                .unwrap(),
+            Vector {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            }
+        );
+        assert_eq!(
+            derived.into_typed_field::<i32>("m_pNext", schema).unwrap(),
+            -1
+        );
+    }
+}
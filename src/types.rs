@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+use serde::Serialize;
+
+use crate::ctsemeta::{CTSEMeta, DataType, DataTypeType};
+
+#[derive(Serialize)]
+pub struct TypeRef {
+    pub id: u32,
+    pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MemberSummary {
+    pub id: u32,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub data_type: TypeRef,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum TypeKind {
+    Primitive {
+        bytes: u32,
+        lbe: u32,
+    },
+    Enum {
+        bytes: u32,
+    },
+    Pointer {
+        to: TypeRef,
+    },
+    Array {
+        of: TypeRef,
+        rows: u32,
+        cols: u32,
+    },
+    Struct {
+        base: Option<TypeRef>,
+        members: Vec<MemberSummary>,
+    },
+    StaticStackArray {
+        of: TypeRef,
+    },
+    DynamicContainer {
+        of: TypeRef,
+    },
+    // chain is every type the typedef goes through, ending with the concrete one
+    TypeDef {
+        #[serde(rename = "for")]
+        for_type: TypeRef,
+        chain: Vec<TypeRef>,
+    },
+    External,
+    Unknown {
+        tag: u32,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Serialize)]
+pub struct TypeSummary {
+    pub id: u32,
+    pub name: String,
+    pub format: Option<u32>,
+    #[serde(flatten)]
+    pub kind: TypeKind,
+}
+
+// Internal types first in file order, then external types
+pub fn list_types(ctsemeta: &CTSEMeta) -> Vec<TypeSummary> {
+    let types = ctsemeta.internal_types.by_id();
+    let idents = ctsemeta.idents.by_id();
+    let type_ref = |id: u32| TypeRef {
+        id,
+        name: type_name(ctsemeta, &types, id),
+    };
+
+    let mut summaries = ctsemeta
+        .internal_types
+        .types
+        .iter()
+        .map(|data_type| TypeSummary {
+            id: data_type.DataType,
+            name: data_type.Name.clone(),
+            format: Some(data_type.Format),
+            kind: match &data_type.Type {
+                DataTypeType::Primitive { Bytes, LBE } => TypeKind::Primitive {
+                    bytes: *Bytes,
+                    lbe: *LBE,
+                },
+                DataTypeType::Enum { Bytes } => TypeKind::Enum { bytes: *Bytes },
+                DataTypeType::Pointer { To } => TypeKind::Pointer { to: type_ref(*To) },
+                DataTypeType::Array { Of, rows, cols } => TypeKind::Array {
+                    of: type_ref(*Of),
+                    rows: *rows,
+                    cols: *cols,
+                },
+                DataTypeType::Struct { Base, members } => TypeKind::Struct {
+                    base: (*Base != -1).then(|| type_ref(*Base as u32)),
+                    members: members
+                        .iter()
+                        .map(|member| MemberSummary {
+                            id: member.ID,
+                            name: idents.get(&member.ID).map(|x| (*x).to_owned()),
+                            data_type: type_ref(member.Type),
+                        })
+                        .collect(),
+                },
+                DataTypeType::StaticStackArray { Of } => {
+                    TypeKind::StaticStackArray { of: type_ref(*Of) }
+                }
+                DataTypeType::DynamicContainer { Of } => {
+                    TypeKind::DynamicContainer { of: type_ref(*Of) }
+                }
+                DataTypeType::TypeDef { For } => TypeKind::TypeDef {
+                    for_type: type_ref(*For),
+                    chain: typedef_chain(&types, *For)
+                        .into_iter()
+                        .map(type_ref)
+                        .collect(),
+                },
+                DataTypeType::Unknown { tag, data } => TypeKind::Unknown {
+                    tag: *tag,
+                    data: data.clone(),
+                },
+            },
+        })
+        .collect::<Vec<_>>();
+
+    summaries.extend(
+        ctsemeta
+            .external_types
+            .types
+            .iter()
+            .map(|external_type| TypeSummary {
+                id: external_type.Type,
+                name: external_type.Name.clone(),
+                format: None,
+                kind: TypeKind::External,
+            }),
+    );
+
+    summaries
+}
+
+fn type_name(ctsemeta: &CTSEMeta, types: &HashMap<u32, &DataType>, id: u32) -> Option<String> {
+    types.get(&id).map(|x| x.Name.clone()).or_else(|| {
+        ctsemeta
+            .external_types
+            .types
+            .iter()
+            .find(|x| x.Type == id)
+            .map(|x| x.Name.clone())
+    })
+}
+
+// Stops at the first non-typedef, a missing type, or a cycle
+fn typedef_chain(types: &HashMap<u32, &DataType>, id: u32) -> Vec<u32> {
+    let mut chain = vec![id];
+    let mut current = id;
+    while let Some(DataTypeType::TypeDef { For }) = types.get(&current).map(|x| &x.Type) {
+        if chain.contains(For) {
+            break;
+        }
+        chain.push(*For);
+        current = *For;
+    }
+    chain
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name.as_ref() {
+            Some(name) => write!(f, "#{} {}", self.id, name),
+            None => write!(f, "#{} <missing>", self.id),
+        }
+    }
+}
+
+fn write_kind(out: &mut String, kind: &TypeKind) -> fmt::Result {
+    match kind {
+        TypeKind::Primitive { bytes, lbe } => {
+            write!(out, "Primitive, {} bytes, LBE {}", bytes, lbe)
+        }
+        TypeKind::Enum { bytes } => write!(out, "Enum, {} bytes", bytes),
+        TypeKind::Pointer { to } => write!(out, "Pointer to {}", to),
+        TypeKind::Array { of, rows, cols } => write!(out, "Array of {} [{}x{}]", of, rows, cols),
+        TypeKind::Struct { members, .. } => write!(out, "Struct, {} members", members.len()),
+        TypeKind::StaticStackArray { of } => write!(out, "StaticStackArray of {}", of),
+        TypeKind::DynamicContainer { of } => write!(out, "DynamicContainer of {}", of),
+        TypeKind::TypeDef { chain, .. } => {
+            write!(out, "TypeDef for ")?;
+            for (i, type_ref) in chain.iter().enumerate() {
+                if i != 0 {
+                    write!(out, " -> ")?;
+                }
+                write!(out, "{}", type_ref)?;
+            }
+            Ok(())
+        }
+        TypeKind::External => write!(out, "External"),
+        TypeKind::Unknown { tag, data } => {
+            write!(out, "Unknown kind {}, {} bytes", tag, data.len())
+        }
+    }
+}
+
+// Struct members are expanded into nested structs up to depth levels deep,
+// depth 0 only prints the type lines
+pub fn render_types(summaries: &[TypeSummary], depth: usize) -> String {
+    let by_id = summaries
+        .iter()
+        .map(|summary| (summary.id, summary))
+        .collect::<HashMap<_, _>>();
+
+    fn write_members(
+        out: &mut String,
+        by_id: &HashMap<u32, &TypeSummary>,
+        kind: &TypeKind,
+        level: usize,
+        depth: usize,
+    ) -> fmt::Result {
+        if level > depth {
+            return Ok(());
+        }
+        let TypeKind::Struct { base, members } = kind else {
+            return Ok(());
+        };
+        let indent = "  ".repeat(level);
+        let nested = |type_ref: &TypeRef| {
+            // Expand through typedefs to the struct they name
+            let mut summary = by_id.get(&type_ref.id)?;
+            if let TypeKind::TypeDef { chain, .. } = &summary.kind {
+                summary = by_id.get(&chain.last()?.id)?;
+            }
+            Some(&summary.kind)
+        };
+
+        if let Some(base) = base {
+            writeln!(out, "{}base: {}", indent, base)?;
+            if let Some(kind) = nested(base) {
+                write_members(out, by_id, kind, level + 1, depth)?;
+            }
+        }
+        for member in members {
+            match member.name.as_ref() {
+                Some(name) => write!(out, "{}{} (ident {})", indent, name, member.id)?,
+                None => write!(out, "{}<ident {}>", indent, member.id)?,
+            }
+            writeln!(out, ": {}", member.data_type)?;
+            if let Some(kind) = nested(&member.data_type) {
+                write_members(out, by_id, kind, level + 1, depth)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut out = String::new();
+    for summary in summaries {
+        let _ = write!(out, "#{} {}", summary.id, summary.name);
+        if let Some(format) = summary.format {
+            let _ = write!(out, " (format {})", format);
+        }
+        out.push_str(": ");
+        let _ = write_kind(&mut out, &summary.kind);
+        out.push('\n');
+        let _ = write_members(&mut out, &by_id, &summary.kind, 1, depth);
+    }
+    out
+}
+
+#[derive(Serialize)]
+pub struct TypeNode {
+    // Member ident name, "base", "for", "element" or "target"
+    pub label: Option<String>,
+    pub id: u32,
+    pub name: Option<String>,
+    pub kind: &'static str,
+    // Serialized size, only known for fixed size leaves
+    pub size: Option<u32>,
+    // Set when the type was already expanded elsewhere in the tree, its
+    // children are left out
+    pub reference: bool,
+    pub children: Vec<Self>,
+}
+
+// Accepts either a numeric type ID or an internal type name
+pub fn find_type(ctsemeta: &CTSEMeta, type_name_or_id: &str) -> Option<u32> {
+    type_name_or_id.parse().ok().or_else(|| {
+        ctsemeta
+            .internal_types
+            .types
+            .iter()
+            .find(|x| x.Name == type_name_or_id)
+            .map(|x| x.DataType)
+    })
+}
+
+pub fn explain_type(ctsemeta: &CTSEMeta, id: u32) -> TypeNode {
+    struct Explainer<'a> {
+        ctsemeta: &'a CTSEMeta,
+        types: HashMap<u32, &'a DataType>,
+        idents: HashMap<u32, &'a str>,
+        // Types on the path from the root, a repeat is a cycle
+        stack: Vec<u32>,
+        // Pointer targets are only expanded the first time they are seen
+        expanded_targets: Vec<u32>,
+    }
+
+    impl Explainer<'_> {
+        fn explain(&mut self, label: Option<String>, id: u32) -> TypeNode {
+            let mut node = TypeNode {
+                label,
+                id,
+                name: type_name(self.ctsemeta, &self.types, id),
+                kind: "External",
+                size: None,
+                reference: false,
+                children: Vec::new(),
+            };
+            let Some(data_type) = self.types.get(&id).copied() else {
+                if node.name.is_none() {
+                    node.kind = "Missing";
+                }
+                return node;
+            };
+            if self.stack.contains(&id) {
+                node.kind = kind_name(&data_type.Type);
+                node.reference = true;
+                return node;
+            }
+
+            self.stack.push(id);
+            node.kind = kind_name(&data_type.Type);
+            match &data_type.Type {
+                DataTypeType::Primitive { Bytes, .. } => {
+                    // CString and friends have a 0 size and a length prefix
+                    node.size = (*Bytes != 0).then_some(*Bytes);
+                }
+                DataTypeType::Enum { Bytes } => node.size = Some(*Bytes),
+                DataTypeType::Pointer { To } => {
+                    node.size = Some(4);
+                    if self.expanded_targets.contains(To) {
+                        let mut target = self.explain(Some("target".to_owned()), *To);
+                        target.reference = true;
+                        target.children.clear();
+                        node.children.push(target);
+                    } else {
+                        self.expanded_targets.push(*To);
+                        node.children
+                            .push(self.explain(Some("target".to_owned()), *To));
+                    }
+                }
+                DataTypeType::Array { Of, .. }
+                | DataTypeType::StaticStackArray { Of }
+                | DataTypeType::DynamicContainer { Of } => {
+                    node.children
+                        .push(self.explain(Some("element".to_owned()), *Of));
+                }
+                DataTypeType::Struct { Base, members } => {
+                    if *Base != -1 {
+                        node.children
+                            .push(self.explain(Some("base".to_owned()), *Base as u32));
+                    }
+                    for member in members {
+                        let label = match self.idents.get(&member.ID) {
+                            Some(name) => (*name).to_owned(),
+                            None => format!("<ident {}>", member.ID),
+                        };
+                        node.children.push(self.explain(Some(label), member.Type));
+                    }
+                }
+                DataTypeType::TypeDef { For } => {
+                    let for_node = self.explain(Some("for".to_owned()), *For);
+                    node.size = for_node.size;
+                    node.children.push(for_node);
+                }
+                DataTypeType::Unknown { .. } => {}
+            }
+            self.stack.pop();
+            node
+        }
+    }
+
+    Explainer {
+        ctsemeta,
+        types: ctsemeta.internal_types.by_id(),
+        idents: ctsemeta.idents.by_id(),
+        stack: Vec::new(),
+        expanded_targets: Vec::new(),
+    }
+    .explain(None, id)
+}
+
+fn kind_name(data_type_type: &DataTypeType) -> &'static str {
+    match data_type_type {
+        DataTypeType::Primitive { .. } => "Primitive",
+        DataTypeType::Enum { .. } => "Enum",
+        DataTypeType::Pointer { .. } => "Pointer",
+        DataTypeType::Array { .. } => "Array",
+        DataTypeType::Struct { .. } => "Struct",
+        DataTypeType::StaticStackArray { .. } => "StaticStackArray",
+        DataTypeType::DynamicContainer { .. } => "DynamicContainer",
+        DataTypeType::TypeDef { .. } => "TypeDef",
+        DataTypeType::Unknown { .. } => "Unknown",
+    }
+}
+
+impl fmt::Display for TypeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_node(f: &mut fmt::Formatter<'_>, node: &TypeNode, level: usize) -> fmt::Result {
+            write!(f, "{}", "  ".repeat(level))?;
+            if let Some(label) = node.label.as_ref() {
+                write!(f, "{}: ", label)?;
+            }
+            write!(
+                f,
+                "{}, {}",
+                TypeRef {
+                    id: node.id,
+                    name: node.name.clone(),
+                },
+                node.kind
+            )?;
+            match node.size {
+                Some(size) => write!(f, ", {} bytes", size)?,
+                None if matches!(node.kind, "Primitive") => write!(f, ", variable size")?,
+                None => {}
+            }
+            if node.reference {
+                write!(f, " (see above)")?;
+            }
+            writeln!(f)?;
+            for child in &node.children {
+                write_node(f, child, level + 1)?;
+            }
+            Ok(())
+        }
+
+        write_node(f, self, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::ctsemeta::{CTSEMeta, DataType, DataTypeType};
+    use crate::types::{explain_type, find_type, list_types, render_types};
+
+    #[test]
+    fn list_and_render() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        for (id, name, for_type) in [(5, "CProgress", 6), (6, "CTalosProgressDef", 4)] {
+            ctsemeta.internal_types.types.push(DataType {
+                DataType: id,
+                Name: name.to_owned(),
+                Format: 0,
+                Type: DataTypeType::TypeDef { For: for_type },
+            });
+        }
+
+        let summaries = list_types(&ctsemeta);
+        assert_eq!(
+            serde_json::to_value(&summaries[3]).unwrap(),
+            serde_json::json!({
+                "id": 3,
+                "name": "CTalosProgress*",
+                "format": 0,
+                "kind": "Pointer",
+                "to": { "id": 4, "name": "CTalosProgress" },
+            })
+        );
+
+        let rendered = render_types(&summaries, 1);
+        assert!(rendered.contains(
+            "#4 CTalosProgress (format 0): Struct, 4 members\n  m_strName (ident 0): #1 CString\n"
+        ));
+        assert!(rendered.contains(
+            "#5 CProgress (format 0): TypeDef for #6 CTalosProgressDef -> #4 CTalosProgress\n"
+        ));
+        assert!(!rendered.contains("\n  m_pNext (ident 3): #3 CTalosProgress*\n    "));
+        assert!(!render_types(&summaries, 0).contains("m_strName"));
+    }
+
+    #[test]
+    fn explain() {
+        let ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        assert_eq!(find_type(&ctsemeta, "CTalosProgress"), Some(4));
+        assert_eq!(find_type(&ctsemeta, "3"), Some(3));
+        assert_eq!(find_type(&ctsemeta, "CMissing"), None);
+
+        // The pointer back to the struct is a cycle and must be cut
+        let node = explain_type(&ctsemeta, 4);
+        assert_eq!(
+            node.to_string(),
+            "#4 CTalosProgress, Struct\n  m_strName: #1 CString, Primitive, variable size\n  \
+             m_ulCount: #0 ULONG, Primitive, 4 bytes\n  m_fTime: #2 FLOAT, Primitive, 4 \
+             bytes\n  m_pNext: #3 CTalosProgress*, Pointer, 4 bytes\n    target: #4 \
+             CTalosProgress, Struct (see above)\n"
+        );
+        let json = serde_json::to_value(&node).unwrap();
+        assert_eq!(json["children"][3]["children"][0]["reference"], true);
+    }
+}
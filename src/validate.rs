@@ -0,0 +1,761 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::ctsemeta::{
+    CTSEMeta,
+    DataType,
+    DataTypeType,
+    Ident,
+    IdentRef,
+    InternalObject,
+    InternalObjectDataValue,
+    InternalObjectType,
+    container_holds_values,
+    csynced_primitive,
+    is_csynced,
+    is_matrix4x4,
+    is_placement3d,
+    is_vec3d,
+    member_name,
+    resolve_type,
+};
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Problem {
+    // JSON pointer into the extracted JSON
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+fn variant_name(value: &InternalObjectDataValue) -> &'static str {
+    match value {
+        InternalObjectDataValue::Pointer(_) => "Pointer",
+        InternalObjectDataValue::CString(_) => "CString",
+        InternalObjectDataValue::CStringRaw(_) => "CStringRaw",
+        InternalObjectDataValue::IDENT(_) => "IDENT",
+        InternalObjectDataValue::Bool(_) => "Bool",
+        InternalObjectDataValue::UBYTE(_) => "UBYTE",
+        InternalObjectDataValue::UWORD(_) => "UWORD",
+        InternalObjectDataValue::SWORD(_) => "SWORD",
+        InternalObjectDataValue::ULONG(_) => "ULONG",
+        InternalObjectDataValue::SLONG(_) => "SLONG",
+        InternalObjectDataValue::UINDEX(_) => "UINDEX",
+        InternalObjectDataValue::UQUAD(_) => "UQUAD",
+        InternalObjectDataValue::SQUAD(_) => "SQUAD",
+        InternalObjectDataValue::FLOAT(_) => "FLOAT",
+        InternalObjectDataValue::SFLOAT(_) => "SFLOAT",
+        InternalObjectDataValue::DOUBLE(_) => "DOUBLE",
+        InternalObjectDataValue::TIME(_) => "TIME",
+        InternalObjectDataValue::TICK(_) => "TICK",
+        InternalObjectDataValue::Primitive(_) => "Primitive",
+        InternalObjectDataValue::UBYTEEnum(_) => "UBYTEEnum",
+        InternalObjectDataValue::UWORDEnum(_) => "UWORDEnum",
+        InternalObjectDataValue::SLONGEnum(_) => "SLONGEnum",
+        InternalObjectDataValue::SQUADEnum(_) => "SQUADEnum",
+        InternalObjectDataValue::Enum(_) => "Enum",
+        InternalObjectDataValue::Array(_) => "Array",
+        InternalObjectDataValue::Struct { .. } => "Struct",
+        InternalObjectDataValue::CSyncedSLONG(_) => "CSyncedSLONG",
+        InternalObjectDataValue::StaticStackArray(_) => "StaticStackArray",
+        InternalObjectDataValue::Vec3D { .. } => "Vec3D",
+        InternalObjectDataValue::Placement3D { .. } => "Placement3D",
+        InternalObjectDataValue::Matrix4x4(_) => "Matrix4x4",
+        InternalObjectDataValue::DynamicContainer(_) => "DynamicContainer",
+        InternalObjectDataValue::DynamicContainerValues(_) => "DynamicContainerValues",
+    }
+}
+
+// The variants reading the type can produce, this has to agree with
+// InternalObject's BinRead
+fn expected_variants(
+    data_type: &DataType,
+    types: &HashMap<u32, &DataType>,
+) -> &'static [&'static str] {
+    match &data_type.Type {
+        // Saves from before Vec3D and Placement3D have the FLOATs as an Array
+        // or raw bytes
+        DataTypeType::Array { .. } if is_vec3d(types, data_type) => &["Vec3D", "Array"],
+        DataTypeType::Primitive { .. } if is_vec3d(types, data_type) => &["Vec3D", "Primitive"],
+        DataTypeType::Array { .. } if is_placement3d(types, data_type) => &["Placement3D", "Array"],
+        // Same for saves extracted before Matrix4x4
+        DataTypeType::Array { .. } if is_matrix4x4(types, data_type) => &["Matrix4x4", "Array"],
+        DataTypeType::Primitive { .. } if is_placement3d(types, data_type) => {
+            &["Placement3D", "Primitive"]
+        }
+        DataTypeType::Primitive { Bytes, .. } => match data_type.Name.as_str() {
+            "CString" => &["CString", "CStringRaw"],
+            "IDENT" => &["IDENT"],
+            "BOOL" | "SBOOL" if *Bytes == 1 => &["Bool", "UBYTE"],
+            "BOOL" | "SBOOL" if *Bytes == 2 => &["Bool", "UWORD"],
+            "BOOL" | "SBOOL" if *Bytes == 4 => &["Bool", "ULONG"],
+            "UBYTE" => &["UBYTE"],
+            "UWORD" if *Bytes == 2 => &["UWORD"],
+            "SWORD" if *Bytes == 2 => &["SWORD"],
+            "ULONG" => &["ULONG"],
+            "SLONG" => &["SLONG"],
+            "UINDEX" if *Bytes == 4 => &["UINDEX"],
+            "UQUAD" => &["UQUAD"],
+            "SQUAD" => &["SQUAD"],
+            "FLOAT" => &["FLOAT"],
+            "SFLOAT" if *Bytes == 2 => &["SFLOAT"],
+            "DOUBLE" if *Bytes == 8 => &["DOUBLE"],
+            "TIME" if matches!(Bytes, 4 | 8) => &["TIME"],
+            "TICK" if matches!(Bytes, 4 | 8) => &["TICK"],
+            _ => &["Primitive"],
+        },
+        DataTypeType::Enum { Bytes: 1 } => &["UBYTEEnum"],
+        DataTypeType::Enum { Bytes: 2 } => &["UWORDEnum"],
+        DataTypeType::Enum { Bytes: 4 } => &["SLONGEnum"],
+        DataTypeType::Enum { Bytes: 8 } => &["SQUADEnum"],
+        DataTypeType::Enum { .. } => &["Enum"],
+        DataTypeType::Pointer { .. } => &["Pointer"],
+        DataTypeType::Array { .. } => &["Array"],
+        DataTypeType::Struct { .. } => &["Struct"],
+        DataTypeType::StaticStackArray { .. } => &["StaticStackArray"],
+        DataTypeType::DynamicContainer { Of } if container_holds_values(types, *Of) => {
+            &["DynamicContainerValues"]
+        }
+        DataTypeType::DynamicContainer { .. } => &["DynamicContainer"],
+        // resolve_type never returns these
+        DataTypeType::TypeDef { .. } => &[],
+        DataTypeType::Unknown { .. } => &[],
+    }
+}
+
+// Escapes a JSON pointer reference token
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+struct Validator<'a> {
+    types: &'a HashMap<u32, &'a DataType>,
+    idents: &'a HashMap<u32, &'a str>,
+    // Pointer targets aren't checked without the objects
+    objects: Option<BTreeSet<u32>>,
+    problems: Vec<Problem>,
+}
+
+impl Validator<'_> {
+    fn problem(&mut self, path: &str, message: String) {
+        self.problems.push(Problem {
+            path: path.to_owned(),
+            message,
+        });
+    }
+
+    fn check_target(&mut self, path: &str, target: u32) {
+        if self
+            .objects
+            .as_ref()
+            .is_some_and(|objects| !objects.contains(&target))
+        {
+            self.problem(
+                path,
+                format!("points to object {} which doesn't exist", target),
+            );
+        }
+    }
+
+    fn validate_row(
+        &mut self,
+        path: &str,
+        values: &[InternalObjectDataValue],
+        data_type: &DataType,
+        of: u32,
+        cols: u32,
+    ) {
+        if values.len() != cols as usize {
+            self.problem(
+                path,
+                format!(
+                    "array has {} elements but type {} has {}",
+                    values.len(),
+                    data_type.Name,
+                    cols
+                ),
+            );
+        }
+        for (i, value) in values.iter().enumerate() {
+            self.validate_value(&format!("{}/{}", path, i), value, of);
+        }
+    }
+
+    fn validate_value(&mut self, path: &str, value: &InternalObjectDataValue, type_id: u32) {
+        let Some(data_type) = resolve_type(self.types, type_id) else {
+            self.problem(path, format!("type {} isn't in internal_types", type_id));
+            return;
+        };
+        if let DataTypeType::Unknown { tag, .. } = &data_type.Type {
+            self.problem(
+                path,
+                format!("type {} has unknown kind {}", data_type.Name, tag),
+            );
+            return;
+        }
+
+        // Memberless CSynced structs hold their primitive's value
+        if is_csynced(data_type) {
+            // As extracted before that
+            if data_type.Name == "CSyncedSLONG"
+                && matches!(value, InternalObjectDataValue::CSyncedSLONG(_))
+            {
+                return;
+            }
+            match csynced_primitive(self.types, data_type) {
+                Some(primitive) => self.validate_value(path, value, primitive.DataType),
+                None => self.problem(
+                    path,
+                    format!(
+                        "{} has no members and its primitive isn't in the types",
+                        data_type.Name
+                    ),
+                ),
+            }
+            return;
+        }
+
+        let expected = expected_variants(data_type, self.types);
+        let variant = variant_name(value);
+        if !expected.contains(&variant) {
+            self.problem(
+                path,
+                format!(
+                    "found {} but type {} needs {}",
+                    variant,
+                    data_type.Name,
+                    expected.join(" or ")
+                ),
+            );
+            return;
+        }
+        let path = format!("{}/{}", path, variant);
+
+        match (value, &data_type.Type) {
+            (InternalObjectDataValue::Primitive(bytes), DataTypeType::Primitive { Bytes, .. })
+            | (InternalObjectDataValue::Enum(bytes), DataTypeType::Enum { Bytes })
+                if bytes.len() != *Bytes as usize =>
+            {
+                self.problem(
+                    &path,
+                    format!(
+                        "{} has {} bytes but type {} is {} bytes",
+                        variant,
+                        bytes.len(),
+                        data_type.Name,
+                        Bytes
+                    ),
+                );
+            }
+            (InternalObjectDataValue::IDENT(IdentRef::Name(name)), _)
+                if !self.idents.values().any(|ident| ident == name) =>
+            {
+                self.problem(&path, format!("ident {} is not in Idents", name));
+            }
+            (InternalObjectDataValue::Pointer(pointer), _) if *pointer != -1 => {
+                self.check_target(&path, *pointer as u32);
+            }
+            (InternalObjectDataValue::Array(values), DataTypeType::Array { Of, rows: 1, cols }) => {
+                self.validate_row(&path, values, data_type, *Of, *cols);
+            }
+            (InternalObjectDataValue::Array(values), DataTypeType::Array { Of, rows, cols }) => {
+                if values.len() != *rows as usize {
+                    self.problem(
+                        &path,
+                        format!(
+                            "array has {} rows but type {} has {}",
+                            values.len(),
+                            data_type.Name,
+                            rows
+                        ),
+                    );
+                }
+                for (i, row) in values.iter().enumerate() {
+                    let path = format!("{}/{}", path, i);
+                    match row {
+                        InternalObjectDataValue::Array(values) => self.validate_row(
+                            &format!("{}/Array", path),
+                            values,
+                            data_type,
+                            *Of,
+                            *cols,
+                        ),
+                        row => self.problem(
+                            &path,
+                            format!(
+                                "found {} but rows of type {} need Array",
+                                variant_name(row),
+                                data_type.Name
+                            ),
+                        ),
+                    }
+                }
+            }
+            (
+                InternalObjectDataValue::StaticStackArray(values),
+                DataTypeType::StaticStackArray { Of },
+            ) => {
+                for (i, value) in values.iter().enumerate() {
+                    self.validate_value(&format!("{}/{}", path, i), value, *Of);
+                }
+            }
+            (
+                InternalObjectDataValue::DynamicContainerValues(values),
+                DataTypeType::DynamicContainer { Of },
+            ) => {
+                for (i, value) in values.iter().enumerate() {
+                    self.validate_value(&format!("{}/{}", path, i), value, *Of);
+                }
+            }
+            (InternalObjectDataValue::DynamicContainer(values), _) => {
+                for (i, value) in values.iter().enumerate() {
+                    self.check_target(&format!("{}/{}", path, i), *value);
+                }
+            }
+            (
+                InternalObjectDataValue::Struct { Base, members },
+                DataTypeType::Struct {
+                    Base: base_type,
+                    members: member_types,
+                },
+            ) => {
+                match (Base, *base_type) {
+                    (Some(base), base_type) if base_type != -1 => {
+                        self.validate_value(&format!("{}/Base", path), base, base_type as u32)
+                    }
+                    (Some(_), _) => self.problem(
+                        &format!("{}/Base", path),
+                        format!("type {} has no base", data_type.Name),
+                    ),
+                    (None, -1) => {}
+                    (None, base_type) => self.problem(
+                        &format!("{}/Base", path),
+                        format!("missing base of type {}", base_type),
+                    ),
+                }
+
+                // Same matching as writing, by ident name or ID
+                let mut used = vec![false; members.len()];
+                for member_type in member_types {
+                    let name = member_name(self.idents, member_type.ID);
+                    let id = member_type.ID.to_string();
+                    let Some(i) = (0..members.len())
+                        .find(|i| !used[*i] && (members[*i].0 == name || members[*i].0 == id))
+                    else {
+                        self.problem(
+                            &format!("{}/members", path),
+                            format!("struct {} is missing member {}", data_type.Name, name),
+                        );
+                        continue;
+                    };
+                    used[i] = true;
+                    self.validate_value(
+                        &format!("{}/members/{}", path, escape(&members[i].0)),
+                        &members[i].1,
+                        member_type.Type,
+                    );
+                }
+                for (i, _) in used.iter().enumerate().filter(|(_, used)| !**used) {
+                    self.problem(
+                        &format!("{}/members/{}", path, escape(&members[i].0)),
+                        format!("struct {} has no member {}", data_type.Name, members[i].0),
+                    );
+                }
+                if members.len() != member_types.len() {
+                    self.problem(
+                        &format!("{}/members", path),
+                        format!(
+                            "struct has {} members but type {} has {}",
+                            members.len(),
+                            data_type.Name,
+                            member_types.len()
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// IDs the tables repeat, a later entry shadows the earlier one when objects
+// are read, and object types that don't match the objects. Reading a save only
+// warns about these, validate treats them as problems.
+pub fn check_tables(
+    idents: &[Ident],
+    types: &[DataType],
+    object_types: &[InternalObjectType],
+    objects: &[InternalObject],
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut problem = |path: String, message: String| problems.push(Problem { path, message });
+
+    let mut seen = HashMap::new();
+    for (i, ident) in idents.iter().enumerate() {
+        if let Some(first) = seen.insert(ident.Ident, &ident.Name) {
+            problem(
+                format!("/idents/idents/{}", i),
+                format!("ident {} is both {} and {}", ident.Ident, first, ident.Name),
+            );
+        }
+    }
+
+    let mut seen = HashMap::new();
+    for (i, data_type) in types.iter().enumerate() {
+        if let Some(first) = seen.insert(data_type.DataType, &data_type.Name) {
+            problem(
+                format!("/internal_types/types/{}", i),
+                format!(
+                    "type {} is both {} and {}",
+                    data_type.DataType, first, data_type.Name
+                ),
+            );
+        }
+    }
+
+    let object_type_of = objects
+        .iter()
+        .map(|object| (object.Object, object.Type))
+        .collect::<HashMap<_, _>>();
+    let mut seen = BTreeSet::new();
+    for (i, object_type) in object_types.iter().enumerate() {
+        let path = format!("/internal_object_types/types/{}", i);
+        if !seen.insert(object_type.Object) {
+            problem(
+                path,
+                format!("object {} has more than one type", object_type.Object),
+            );
+            continue;
+        }
+        match object_type_of.get(&object_type.Object) {
+            Some(&type_id) if type_id != object_type.Type => problem(
+                path,
+                format!(
+                    "object {} has type {} but the object says {}",
+                    object_type.Object, object_type.Type, type_id
+                ),
+            ),
+            Some(_) => {}
+            None => problem(path, format!("object {} doesn't exist", object_type.Object)),
+        }
+    }
+    for (i, object) in objects.iter().enumerate() {
+        if !seen.contains(&object.Object) {
+            problem(
+                format!("/internal_objects/internal_object/{}", i),
+                format!("object {} isn't in the object types", object.Object),
+            );
+        }
+    }
+
+    problems
+}
+
+// Checks every object's value against its type definition so hand edited JSON
+// fails with a readable message instead of a binrw error or a broken save
+pub fn validate(ctsemeta: &CTSEMeta) -> Vec<Problem> {
+    let types = ctsemeta.internal_types.by_id();
+    let idents = ctsemeta.idents.by_id();
+    let mut validator = Validator {
+        types: &types,
+        idents: &idents,
+        objects: Some(
+            ctsemeta
+                .internal_objects
+                .internal_object
+                .iter()
+                .map(|object| object.Object)
+                .chain(
+                    ctsemeta
+                        .external_objects
+                        .external_objects
+                        .iter()
+                        .map(|object| object.Object),
+                )
+                .collect(),
+        ),
+        problems: Vec::new(),
+    };
+
+    for (i, object) in ctsemeta.internal_objects.internal_object.iter().enumerate() {
+        validator.validate_value(
+            &format!("/internal_objects/internal_object/{}/value", i),
+            &object.value,
+            object.Type,
+        );
+    }
+    validator.problems.extend(check_tables(
+        &ctsemeta.idents.idents,
+        &ctsemeta.internal_types.types,
+        &ctsemeta.internal_object_types.types,
+        &ctsemeta.internal_objects.internal_object,
+    ));
+
+    validator.problems
+}
+
+// Types the tables refer to that neither internal_types nor external_types
+// declare, and external objects in resource files that aren't listed. The
+// game can't load a save with any of these.
+pub fn check_declarations(ctsemeta: &CTSEMeta) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut problem = |path: String, message: String| problems.push(Problem { path, message });
+    let declared = ctsemeta
+        .internal_types
+        .types
+        .iter()
+        .map(|data_type| data_type.DataType)
+        .chain(
+            ctsemeta
+                .external_types
+                .types
+                .iter()
+                .map(|external_type| external_type.Type),
+        )
+        .collect::<BTreeSet<_>>();
+
+    for (i, data_type) in ctsemeta.internal_types.types.iter().enumerate() {
+        let referenced = match &data_type.Type {
+            DataTypeType::Primitive { .. }
+            | DataTypeType::Enum { .. }
+            | DataTypeType::Unknown { .. } => Vec::new(),
+            DataTypeType::Pointer { To: id }
+            | DataTypeType::Array { Of: id, .. }
+            | DataTypeType::StaticStackArray { Of: id }
+            | DataTypeType::DynamicContainer { Of: id }
+            | DataTypeType::TypeDef { For: id } => vec![*id],
+            DataTypeType::Struct { Base, members } => (*Base != -1)
+                .then_some(*Base as u32)
+                .into_iter()
+                .chain(members.iter().map(|member| member.Type))
+                .collect(),
+        };
+        for id in referenced.into_iter().filter(|id| !declared.contains(id)) {
+            problem(
+                format!("/internal_types/types/{}", i),
+                format!(
+                    "type {} refers to type {} which isn't in internal_types or external_types",
+                    data_type.Name, id
+                ),
+            );
+        }
+    }
+
+    for (i, object_type) in ctsemeta
+        .edit_object_types
+        .edit_object_types
+        .iter()
+        .enumerate()
+    {
+        if !declared.contains(&object_type.Type) {
+            problem(
+                format!("/edit_object_types/edit_object_types/{}", i),
+                format!(
+                    "edit object {} has type {} which isn't in internal_types or external_types",
+                    object_type.Object, object_type.Type
+                ),
+            );
+        }
+    }
+
+    let resource_files = ctsemeta.resource_files.resource_files.len();
+    for (i, object) in ctsemeta
+        .external_objects
+        .external_objects
+        .iter()
+        .enumerate()
+    {
+        let path = format!("/external_objects/external_objects/{}", i);
+        if !declared.contains(&object.Type) {
+            problem(
+                path.clone(),
+                format!(
+                    "external object {} has type {} which isn't in internal_types or \
+                     external_types",
+                    object.Object, object.Type
+                ),
+            );
+        }
+        if object.File as usize >= resource_files {
+            problem(
+                path,
+                format!(
+                    "external object {} is in resource file {} but resource_files only has {}",
+                    object.Object, object.File, resource_files
+                ),
+            );
+        }
+    }
+
+    problems
+}
+
+impl CTSEMeta {
+    // validate plus check_declarations, for saves that were read from disk as
+    // well as edited JSON
+    pub fn full_validate(&self) -> Vec<Problem> {
+        let mut problems = validate(self);
+        problems.extend(check_declarations(self));
+        problems
+    }
+}
+
+// Only checks the shape of a single value, paths are relative to the value
+pub fn validate_value(
+    value: &InternalObjectDataValue,
+    type_id: u32,
+    types: &HashMap<u32, &DataType>,
+    idents: &HashMap<u32, &str>,
+) -> Vec<Problem> {
+    let mut validator = Validator {
+        types,
+        idents,
+        objects: None,
+        problems: Vec::new(),
+    };
+    validator.validate_value("", value, type_id);
+    validator.problems
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::ctsemeta::{
+        CTSEMeta,
+        DataTypeType,
+        DataTypeTypeStructMember,
+        EditObjectType,
+        Ident,
+        InternalObjectDataValue,
+        InternalObjectType,
+    };
+    use crate::validate::{check_tables, validate};
+
+    #[test]
+    fn validate_synthetic() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        assert!(validate(&ctsemeta).is_empty());
+
+        let InternalObjectDataValue::Struct { members, .. } =
+            &mut ctsemeta.internal_objects.internal_object[0].value
+        else {
+            unreachable!()
+        };
+        *members.get_mut("m_ulCount").unwrap() = InternalObjectDataValue::FLOAT(1.0);
+        *members.get_mut("m_pNext").unwrap() = InternalObjectDataValue::Pointer(5);
+        members.0.remove(2);
+
+        let problems = validate(&ctsemeta)
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                "/internal_objects/internal_object/0/value/Struct/members/m_ulCount: found FLOAT \
+                 but type ULONG needs ULONG",
+                "/internal_objects/internal_object/0/value/Struct/members: struct CTalosProgress \
+                 is missing member m_fTime",
+                "/internal_objects/internal_object/0/value/Struct/members/m_pNext/Pointer: points \
+                 to object 5 which doesn't exist",
+                "/internal_objects/internal_object/0/value/Struct/members: struct has 3 members \
+                 but type CTalosProgress has 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_conflicts() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        let ident = &ctsemeta.idents.idents[0];
+        ctsemeta.idents.idents.push(Ident {
+            Ident: ident.Ident,
+            Name: "m_strOther".to_owned(),
+        });
+        let mut data_type = ctsemeta.internal_types.types[1].clone();
+        data_type.Name = "CTString".to_owned();
+        ctsemeta.internal_types.types.push(data_type);
+        ctsemeta.internal_object_types.types[1].Type = 2;
+        ctsemeta
+            .internal_object_types
+            .types
+            .push(InternalObjectType { Object: 0, Type: 4 });
+        ctsemeta
+            .internal_object_types
+            .types
+            .push(InternalObjectType { Object: 7, Type: 4 });
+
+        let problems = check_tables(
+            &ctsemeta.idents.idents,
+            &ctsemeta.internal_types.types,
+            &ctsemeta.internal_object_types.types,
+            &ctsemeta.internal_objects.internal_object,
+        )
+        .iter()
+        .map(|problem| problem.to_string())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                "/idents/idents/4: ident 0 is both m_strName and m_strOther",
+                "/internal_types/types/5: type 1 is both CString and CTString",
+                "/internal_object_types/types/1: object 1 has type 2 but the object says 4",
+                "/internal_object_types/types/2: object 0 has more than one type",
+                "/internal_object_types/types/3: object 7 doesn't exist",
+            ]
+        );
+    }
+
+    #[test]
+    fn full_validate() {
+        let mut ctsemeta: CTSEMeta =
+            serde_json::from_reader(BufReader::new(File::open("data/Synthetic.json").unwrap()))
+                .unwrap();
+        assert!(ctsemeta.full_validate().is_empty());
+
+        let DataTypeType::Struct { members, .. } = &mut ctsemeta.internal_types.types[4].Type
+        else {
+            unreachable!()
+        };
+        members.push(DataTypeTypeStructMember { ID: 3, Type: 9 });
+        ctsemeta
+            .edit_object_types
+            .edit_object_types
+            .push(EditObjectType { Object: 0, Type: 8 });
+        ctsemeta.internal_objects.internal_object[1].Type = 7;
+        ctsemeta.internal_object_types.types[1].Type = 7;
+
+        let problems = ctsemeta
+            .full_validate()
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                "/internal_objects/internal_object/0/value/Struct/members: struct \
+                 CTalosProgress is missing member m_pNext",
+                "/internal_objects/internal_object/0/value/Struct/members: struct has 4 members \
+                 but type CTalosProgress has 5",
+                "/internal_objects/internal_object/1/value: type 7 isn't in internal_types",
+                "/internal_types/types/4: type CTalosProgress refers to type 9 which isn't in \
+                 internal_types or external_types",
+                "/edit_object_types/edit_object_types/0: edit object 0 has type 8 which isn't in \
+                 internal_types or external_types",
+            ]
+        );
+    }
+}
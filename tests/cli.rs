@@ -0,0 +1,520 @@
+use assert_cmd::Command;
+
+fn synthetic() -> serde_json::Value {
+    serde_json::from_slice(&std::fs::read("data/Synthetic.json").unwrap()).unwrap()
+}
+
+fn create_from_stdin() -> Vec<u8> {
+    Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["create", "-", "-", "--json"])
+        .write_stdin(std::fs::read("data/Synthetic.json").unwrap())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone()
+}
+
+#[test]
+fn create_to_stdout() {
+    let save = create_from_stdin();
+    // Gzipped, with the CT extra field patched in even though stdout can't seek
+    assert_eq!(&save[..2], [0x1F, 0x8B]);
+    assert_eq!(&save[0x0C..0x0E], b"CT");
+    assert_ne!(&save[0x10..0x18], [0; 8]);
+}
+
+#[test]
+fn extract_from_stdin() {
+    let save = create_from_stdin();
+    let assert = Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["x", "-", "-", "--json"])
+        .write_stdin(save)
+        .assert()
+        .success();
+    let output = assert.get_output();
+    // No memory stream name is guessed for stdin, and none was signed
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    let extracted: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        extracted["internal_objects"],
+        synthetic()["internal_objects"]
+    );
+}
+
+#[test]
+fn stdio_paths() {
+    let save = create_from_stdin();
+    let run = |args: &[&str], stdin: Vec<u8>| {
+        let output = Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(args)
+            .write_stdin(stdin)
+            .output()
+            .unwrap();
+        (
+            output.status.success(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    };
+
+    // A lone - is only the input
+    let (success, _, stderr) = run(&["x", "-"], save.clone());
+    assert!(!success);
+    assert!(stderr.contains("no output path"), "{}", stderr);
+    let (success, stdout, _) = run(&["x", "-", "--stats"], save);
+    assert!(success);
+    assert!(stdout.contains("CTalosProgress"), "{}", stdout);
+
+    let synthetic = std::fs::read("data/Synthetic.json").unwrap();
+    let (success, _, stderr) = run(&["c", "-", "-", "--json", "--checksum"], synthetic);
+    assert!(!success);
+    assert!(stderr.contains("--checksum"), "{}", stderr);
+}
+
+#[test]
+fn hash_methods() {
+    for (hash_method, header_name) in [("sha1", "Sha1"), ("Tiger", "Tiger"), ("SHA256", "Sha256")] {
+        let save = Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(["c", "-", "-", "--json", "--hash-method", hash_method])
+            .write_stdin(std::fs::read("data/Synthetic.json").unwrap())
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+
+        let output = Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(["x", "-", "--header-only"])
+            .write_stdin(save.clone())
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let header: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(header["hash_method"], header_name);
+
+        let assert = Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(["x", "-", "-", "--json"])
+            .write_stdin(save)
+            .assert()
+            .success();
+        let output = assert.get_output();
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+        let extracted: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            extracted["internal_objects"],
+            synthetic()["internal_objects"]
+        );
+    }
+
+    let output = Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["c", "-", "-", "--json", "--hash-method", "md5"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("hash method must be"));
+}
+
+#[test]
+fn exit_codes() {
+    let dir = std::env::temp_dir().join(format!("sse_exit_codes_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+    let code = |args: &[&str]| {
+        Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+
+    assert_eq!(
+        code(&["x", &path("missing.dat"), &path("missing.json")]),
+        Some(2)
+    );
+
+    std::fs::write(path("garbage"), "not a save at all").unwrap();
+    assert_eq!(
+        code(&["x", &path("garbage"), &path("garbage.json")]),
+        Some(3)
+    );
+
+    // A signature stream around a payload that isn't CTSEMETA
+    assert_eq!(
+        code(&["sign-only", &path("garbage"), &path("garbage.dat")]),
+        Some(0)
+    );
+    assert_eq!(
+        code(&["x", &path("garbage.dat"), &path("garbage.json"), "-j"]),
+        Some(4)
+    );
+
+    let save = path("synthetic.dat");
+    assert_eq!(
+        code(&["c", "data/Synthetic.json", &save, "-j", "--no-gz"]),
+        Some(0)
+    );
+    assert_eq!(code(&["verify", &save, "--no-gz"]), Some(0));
+    let mut bytes = std::fs::read(&save).unwrap();
+    let len = bytes.len();
+    bytes[len - 0x100] ^= 1;
+    std::fs::write(&save, bytes).unwrap();
+    assert_eq!(code(&["verify", &save, "--no-gz"]), Some(5));
+
+    let mut synthetic = synthetic();
+    let types = synthetic["internal_types"]["types"].as_array_mut().unwrap();
+    // m_ulCount becomes a FLOAT while the objects still hold ULONGs
+    let progress = types
+        .iter_mut()
+        .find(|t| t["Name"] == "CTalosProgress")
+        .unwrap();
+    progress["Type"]["Struct"]["members"][1]["Type"] = 2.into();
+    std::fs::write(path("invalid.json"), synthetic.to_string()).unwrap();
+    assert_eq!(code(&["c", &path("invalid.json"), &save, "-j"]), Some(6));
+
+    // INFO counts are recalculated on write, so a wrong one doesn't round trip
+    assert_eq!(code(&["c", "data/Synthetic.json", &save, "-j"]), Some(0));
+    assert_eq!(code(&["x", &save, &path("payload.bin")]), Some(0));
+    let mut payload = std::fs::read(path("payload.bin")).unwrap();
+    let info = payload.windows(4).position(|w| w == b"INFO").unwrap();
+    payload[info + 4] += 1;
+    std::fs::write(path("payload.bin"), payload).unwrap();
+    assert_eq!(code(&["sign-only", &path("payload.bin"), &save]), Some(0));
+    assert_eq!(code(&["x", &save, "--check-round-trip"]), Some(7));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn backup() {
+    let dir = std::env::temp_dir().join(format!("sse_backup_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let save = dir.join("PlayerProfile.dat");
+    std::fs::write(&save, "original").unwrap();
+    Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args([
+            "c",
+            "data/Synthetic.json",
+            save.to_str().unwrap(),
+            "-j",
+            "--backup",
+        ])
+        .assert()
+        .success();
+    assert_eq!(&std::fs::read(&save).unwrap()[..2], [0x1F, 0x8B]);
+    assert_eq!(
+        std::fs::read(dir.join("PlayerProfile.dat.bak")).unwrap(),
+        b"original"
+    );
+    // No temporary files left behind
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn edit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("sse_edit_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let save = dir.join("PlayerProfile.dat");
+    let backup = dir.join("PlayerProfile.dat.bak");
+    Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["c", "data/Synthetic.json", save.to_str().unwrap(), "-j"])
+        .assert()
+        .success();
+    let original = std::fs::read(&save).unwrap();
+    let editor = |name: &str, script: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_str().unwrap().to_owned()
+    };
+    let edit = |editor: &str| {
+        Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(["edit", save.to_str().unwrap(), "--editor", editor])
+            .env_remove("VISUAL")
+            .env_remove("EDITOR")
+            .output()
+            .unwrap()
+    };
+
+    // Failing or not changing anything leaves the save alone
+    assert!(edit(&editor("fail.sh", "exit 1")).status.success());
+    assert!(edit(&editor("noop.sh", "true")).status.success());
+    assert_eq!(std::fs::read(&save).unwrap(), original);
+    assert!(!backup.exists());
+
+    // Invalid edits are refused and kept for another try
+    let output = edit(&editor(
+        "break.sh",
+        r#"sed -i 's/"ULONG": 7/"FLOAT": 7/' "$1""#,
+    ));
+    assert_eq!(output.status.code(), Some(6));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let kept = stderr
+        .lines()
+        .find_map(|line| line.split("the edited JSON was kept at ").nth(1))
+        .unwrap_or_else(|| panic!("{}", stderr));
+    std::fs::remove_file(kept.trim()).unwrap();
+    assert_eq!(std::fs::read(&save).unwrap(), original);
+
+    assert!(
+        edit(&editor(
+            "edit.sh",
+            r#"sed -i 's/"ULONG": 7/"ULONG": 8/' "$1""#
+        ))
+        .status
+        .success()
+    );
+    assert_eq!(std::fs::read(&backup).unwrap(), original);
+    let extracted = Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["get", save.to_str().unwrap(), "objects/1/m_ulCount"])
+        .output()
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&extracted.stdout).unwrap();
+    assert_eq!(value, serde_json::json!({"ULONG": 8}));
+
+    // The editor goes through the shell, so it can have quoted arguments
+    assert!(
+        edit(r#"sed -i 's/"ULONG": 8/"ULONG": 9/'"#)
+            .status
+            .success()
+    );
+    let extracted = Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["get", save.to_str().unwrap(), "objects/1/m_ulCount"])
+        .output()
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&extracted.stdout).unwrap();
+    assert_eq!(value, serde_json::json!({"ULONG": 9}));
+
+    // No editor at all
+    let output = Command::cargo_bin("SeriousSaveEditor")
+        .unwrap()
+        .args(["edit", save.to_str().unwrap()])
+        .env_remove("VISUAL")
+        .env_remove("EDITOR")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no editor"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch() {
+    let dir = std::env::temp_dir().join(format!("sse_batch_{}", std::process::id()));
+    let [saves, extracted, created] = ["saves", "extracted", "created"].map(|name| dir.join(name));
+    std::fs::create_dir_all(&saves).unwrap();
+    for name in ["PlayerProfile.dat", "PlayerProfile_unrestricted.dat"] {
+        Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args([
+                "c",
+                "data/Synthetic.json",
+                saves.join(name).to_str().unwrap(),
+                "-j",
+            ])
+            .assert()
+            .success();
+    }
+    std::fs::write(saves.join("Broken.dat"), "not a save").unwrap();
+    let batch = |args: &[&str]| {
+        Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(args)
+            .args(["--batch", "-j", "--jobs", "2"])
+            .output()
+            .unwrap()
+    };
+
+    // The broken save fails without stopping the others
+    let output = batch(&["x", saves.to_str().unwrap(), extracted.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Broken.dat: failed"), "{}", stdout);
+    assert!(stdout.contains("PlayerProfile.dat: ok"), "{}", stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("1 of 3 saves failed to extract"),
+        "{}",
+        stderr
+    );
+    let mut names = std::fs::read_dir(&extracted)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "PlayerProfile.dat.json",
+            "PlayerProfile_unrestricted.dat.json"
+        ]
+    );
+
+    // Back to saves with the suffix taken off
+    let output = batch(&["c", extracted.to_str().unwrap(), created.to_str().unwrap()]);
+    assert!(output.status.success());
+    for name in ["PlayerProfile.dat", "PlayerProfile_unrestricted.dat"] {
+        assert!(created.join(name).exists());
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn validate_saves() {
+    let dir = std::env::temp_dir().join(format!("sse_validate_saves_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+    let run = |args: &[&str]| {
+        Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let save = path("synthetic.dat");
+    assert!(
+        run(&["c", "data/Synthetic.json", &save, "-j"])
+            .status
+            .success()
+    );
+    let output = run(&["validate", &save]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("no problems found\n"));
+
+    // A pointer type no object uses, to a type that isn't declared anywhere
+    let mut synthetic = synthetic();
+    synthetic["internal_types"]["types"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "DataType": 5,
+            "Name": "CTalosThing*",
+            "Format": 0,
+            "Type": { "Pointer": { "To": 9 } }
+        }));
+    std::fs::write(path("dangling.json"), synthetic.to_string()).unwrap();
+    let save = path("dangling.dat");
+    assert!(
+        run(&["c", &path("dangling.json"), &save, "-j"])
+            .status
+            .success()
+    );
+
+    // The JSON, the save, and the save while extracting all fail the same way
+    let json = path("dangling.json");
+    let extracted = path("dangling.dat.json");
+    for args in [
+        ["validate", &json].as_slice(),
+        &["validate", &save],
+        &["x", &save, &extracted, "-j", "--validate"],
+    ] {
+        let output = run(args);
+        assert_eq!(output.status.code(), Some(6));
+        assert!(String::from_utf8_lossy(&output.stderr).contains(
+            "/internal_types/types/5: type CTalosThing* refers to type 9 which isn't in \
+             internal_types or external_types"
+        ));
+    }
+    // Extracting still wrote the JSON
+    assert!(std::fs::exists(&extracted).unwrap());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn signature_requirements() {
+    let dir =
+        std::env::temp_dir().join(format!("sse_signature_requirements_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let save = dir.join("PlayerProfile.dat").to_str().unwrap().to_owned();
+    let extracted = dir.join("PlayerProfile.json").to_str().unwrap().to_owned();
+    let run = |args: &[&str]| {
+        Command::cargo_bin("SeriousSaveEditor")
+            .unwrap()
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    // Signed with the memory stream name guessed from the file name
+    assert!(
+        run(&[
+            "c",
+            "data/Synthetic.json",
+            &save,
+            "-j",
+            "-g",
+            "-u",
+            "1100001075d8dea"
+        ])
+        .status
+        .success()
+    );
+    let output = run(&["info", &save, "--no-guess-memory-stream-name"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        "this save's signature covers: memory stream name (not provided), userid (not provided)"
+    ));
+
+    let output = run(&[
+        "x",
+        &save,
+        &extracted,
+        "-j",
+        "--no-guess-memory-stream-name",
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "this save's signature covers a memory stream name, pass it with -m, likely ones are \
+             \"<memory stream:PlayerProfile_unrestricted.dat>\", \"<memory stream:PlayerProfile.dat>\""
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("this save's signature covers a userid"),
+        "{}",
+        stderr
+    );
+
+    // No hints when they're given
+    let output = run(&["x", &save, &extracted, "-j", "-u", "1100001075d8dea"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    let output = run(&[
+        "diff",
+        &save,
+        &save,
+        "--no-guess-memory-stream-name",
+        "-m",
+        "<memory stream:PlayerProfile.dat>",
+        "-u",
+        "1100001075d8dea",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}